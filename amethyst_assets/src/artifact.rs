@@ -0,0 +1,523 @@
+//! Preprocessing of source assets into cached, engine-ready build artifacts.
+
+use std::{
+    fs,
+    hash::Hasher,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use fnv::FnvHasher;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{Asset, ErrorKind, Format, FormatValue, Reload, Result, ResultExt, Source};
+
+/// A `Format` whose output can be stored as a build artifact by an `ArtifactCache`.
+///
+/// The importer decodes the source file (e.g. a PNG or an OBJ file) into `Asset::Data`,
+/// which is then serialized into a binary blob. Turning that data into the actual asset
+/// is still done by the `Processor` at runtime.
+///
+/// Only the file named by the asset is checked for changes. Importers reading further files
+/// from the source, like the buffers of a glTF file, are not rebuilt when only those change.
+pub trait Importer<A: Asset>: Format<A> {
+    /// The version of this importer.
+    ///
+    /// Increase this whenever the data produced by the importer changes,
+    /// so artifacts created by older versions are rebuilt.
+    const VERSION: u32;
+}
+
+/// A directory of build artifacts, each holding the imported data of a source asset.
+///
+/// Artifacts are keyed by the asset type, the source and name of the asset, the importer, its
+/// version and the import options, and record a hash of the source file they were created from.
+/// An artifact is rebuilt when the source file changed; if the source file is missing (e.g. in a
+/// shipped game which only contains the artifacts), the artifact is used as is.
+///
+/// To build artifacts ahead of time, call `build` for every asset from a build step. At runtime,
+/// pass the cache to `Loader::set_artifact_cache` and load the assets with `Loader::load_cached`.
+#[derive(Debug)]
+pub struct ArtifactCache {
+    dir: PathBuf,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Artifact {
+    source_hash: u64,
+    source_modified: u64,
+    data: Vec<u8>,
+}
+
+impl ArtifactCache {
+    /// Creates a new artifact cache, storing the artifacts in the given directory.
+    pub fn new<P>(dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        ArtifactCache { dir: dir.into() }
+    }
+
+    /// Returns the directory the artifacts are stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Imports the asset `name` from `source` and writes its artifact,
+    /// unless an up to date artifact already exists.
+    ///
+    /// `source_id` is the id the source is added to the `Loader` with, `""` for the default
+    /// (directory) source, so assets with the same name in different sources get different
+    /// artifacts.
+    pub fn build<A, I>(
+        &self,
+        name: &str,
+        importer: &I,
+        options: I::Options,
+        source_id: &str,
+        source: &Arc<dyn Source>,
+    ) -> Result<()>
+    where
+        A: Asset,
+        A::Data: Serialize + DeserializeOwned,
+        I: Importer<A>,
+        I::Options: Serialize,
+    {
+        self.fetch::<A, I>(name, importer, options, source_id, source)
+            .map(|_| ())
+    }
+
+    /// Returns the data for the asset `name`, read from its artifact if that is up to date.
+    ///
+    /// Otherwise, the asset is imported from `source` and its artifact is (re-)written.
+    /// Failing to write the artifact is not an error, so this also works with read-only caches
+    /// and with data which cannot be serialized.
+    ///
+    /// See `build` for a description of `source_id`.
+    pub fn fetch<A, I>(
+        &self,
+        name: &str,
+        importer: &I,
+        options: I::Options,
+        source_id: &str,
+        source: &Arc<dyn Source>,
+    ) -> Result<A::Data>
+    where
+        A: Asset,
+        A::Data: Serialize + DeserializeOwned,
+        I: Importer<A>,
+        I::Options: Serialize,
+    {
+        #[cfg(feature = "profiler")]
+        profile_scope!("fetch_artifact");
+
+        let path = self.artifact_path::<A, I>(name, source_id, &options)?;
+        let artifact = read_artifact(&path);
+        let modified = source.modified(name).ok();
+
+        if let Some(ref artifact) = artifact {
+            let unchanged = match modified {
+                None => true,
+                Some(m) => m != 0 && m == artifact.source_modified,
+            };
+            if unchanged {
+                if let Some(data) = decode::<A>(&path, artifact) {
+                    return Ok(data);
+                }
+            }
+        }
+
+        let bytes = source.load(name).chain_err(|| ErrorKind::Source)?;
+        let source_hash = hash_bytes(&bytes);
+
+        if let Some(artifact) = artifact {
+            if artifact.source_hash == source_hash {
+                if let Some(data) = decode::<A>(&path, &artifact) {
+                    write_artifact(
+                        &path,
+                        &Artifact {
+                            source_modified: modified.unwrap_or(0),
+                            ..artifact
+                        },
+                    );
+                    return Ok(data);
+                }
+            }
+        }
+
+        debug!(
+            "{:?}: Building artifact for asset {:?} with importer {:?} (version {})",
+            A::NAME,
+            name,
+            <I as Format<A>>::NAME,
+            I::VERSION,
+        );
+        let data = importer
+            .import(name.to_owned(), source.clone(), options, false)?
+            .data;
+        match bincode::serialize(&data) {
+            Ok(encoded) => write_artifact(
+                &path,
+                &Artifact {
+                    source_hash,
+                    source_modified: modified.unwrap_or(0),
+                    data: encoded,
+                },
+            ),
+            Err(e) => warn!("Failed to serialize artifact for asset {:?}: {}", name, e),
+        }
+
+        Ok(data)
+    }
+
+    fn artifact_path<A, I>(
+        &self,
+        name: &str,
+        source_id: &str,
+        options: &I::Options,
+    ) -> Result<PathBuf>
+    where
+        A: Asset,
+        I: Importer<A>,
+        I::Options: Serialize,
+    {
+        let options = bincode::serialize(options).chain_err(|| "Failed serializing options")?;
+        let mut hasher = FnvHasher::default();
+        hasher.write(A::NAME.as_bytes());
+        hasher.write_u8(0);
+        hasher.write(source_id.as_bytes());
+        hasher.write_u8(0);
+        hasher.write(name.as_bytes());
+        hasher.write_u32(I::VERSION);
+        hasher.write(&options);
+
+        let mut path = self.dir.join(<I as Format<A>>::NAME);
+        path.push(format!("{:016x}.bin", hasher.finish()));
+
+        Ok(path)
+    }
+}
+
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = FnvHasher::default();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+fn read_artifact(path: &Path) -> Option<Artifact> {
+    let bytes = fs::read(path).ok()?;
+    match bincode::deserialize(&bytes) {
+        Ok(artifact) => Some(artifact),
+        Err(e) => {
+            warn!("Ignoring corrupt artifact {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+fn write_artifact(path: &Path, artifact: &Artifact) {
+    let result = bincode::serialize(artifact)
+        .chain_err(|| "Failed serializing artifact")
+        .and_then(|bytes| {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .chain_err(|| format!("Failed to create directory {:?}", parent))?;
+            }
+            fs::write(path, bytes).chain_err(|| format!("Failed to write file {:?}", path))
+        });
+    if let Err(e) = result {
+        warn!("Failed to write artifact {:?}: {}", path, e);
+    }
+}
+
+fn decode<A>(path: &Path, artifact: &Artifact) -> Option<A::Data>
+where
+    A: Asset,
+    A::Data: DeserializeOwned,
+{
+    match bincode::deserialize(&artifact.data) {
+        Ok(data) => Some(data),
+        Err(e) => {
+            warn!(
+                "Rebuilding artifact {:?} which failed to decode: {}",
+                path, e
+            );
+            None
+        }
+    }
+}
+
+/// Reload object for assets loaded through an `ArtifactCache`.
+///
+/// Reloading re-fetches the data, which rebuilds the artifact if the source changed.
+pub(crate) struct ArtifactReload<A, I>
+where
+    A: Asset,
+    I: Importer<A>,
+{
+    cache: Arc<ArtifactCache>,
+    importer: I,
+    modified: u64,
+    options: I::Options,
+    path: String,
+    source_id: String,
+    source: Arc<dyn Source>,
+}
+
+impl<A, I> ArtifactReload<A, I>
+where
+    A: Asset,
+    I: Importer<A>,
+{
+    pub fn new(
+        cache: Arc<ArtifactCache>,
+        importer: I,
+        options: I::Options,
+        path: String,
+        source_id: String,
+        source: Arc<dyn Source>,
+    ) -> Self {
+        let modified = source.modified(&path).unwrap_or(0);
+        ArtifactReload {
+            cache,
+            importer,
+            modified,
+            options,
+            path,
+            source_id,
+            source,
+        }
+    }
+}
+
+impl<A, I> Clone for ArtifactReload<A, I>
+where
+    A: Asset,
+    I: Importer<A> + Clone,
+    I::Options: Clone,
+{
+    fn clone(&self) -> Self {
+        ArtifactReload {
+            cache: self.cache.clone(),
+            importer: self.importer.clone(),
+            modified: self.modified,
+            options: self.options.clone(),
+            path: self.path.clone(),
+            source_id: self.source_id.clone(),
+            source: self.source.clone(),
+        }
+    }
+}
+
+impl<A, I> Reload<A> for ArtifactReload<A, I>
+where
+    A: Asset,
+    A::Data: Serialize + DeserializeOwned,
+    I: Importer<A> + Clone + Send + Sync + 'static,
+    I::Options: Clone + Serialize + Sync,
+{
+    fn needs_reload(&self) -> bool {
+        self.modified != 0 && (self.source.modified(&self.path).unwrap_or(0) > self.modified)
+    }
+
+    fn name(&self) -> String {
+        self.path.clone()
+    }
+
+    fn format(&self) -> &'static str {
+        <I as Format<A>>::NAME
+    }
+
+    fn reload(self: Box<Self>) -> Result<FormatValue<A>> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("reload_artifact");
+
+        let data = self.cache.fetch::<A, I>(
+            &self.path,
+            &self.importer,
+            self.options.clone(),
+            &self.source_id,
+            &self.source,
+        )?;
+        let reload = ArtifactReload::new(
+            self.cache.clone(),
+            self.importer.clone(),
+            self.options.clone(),
+            self.path.clone(),
+            self.source_id.clone(),
+            self.source.clone(),
+        );
+
+        Ok(FormatValue {
+            data,
+            reload: Some(Box::new(reload)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use amethyst_core::specs::prelude::VecStorage;
+
+    use super::*;
+    use crate::{Handle, MemorySource, SimpleFormat};
+
+    struct Text(String);
+
+    impl Asset for Text {
+        const NAME: &'static str = "test::Text";
+        type Data = String;
+        type HandleStorage = VecStorage<Handle<Self>>;
+    }
+
+    /// Imports text, counting how often it is called.
+    #[derive(Clone, Default)]
+    struct CountingImporter {
+        imports: Arc<AtomicUsize>,
+    }
+
+    impl CountingImporter {
+        fn imports(&self) -> usize {
+            self.imports.load(Ordering::SeqCst)
+        }
+    }
+
+    impl SimpleFormat<Text> for CountingImporter {
+        const NAME: &'static str = "COUNTING";
+        type Options = ();
+
+        fn import(&self, bytes: Vec<u8>, _: ()) -> Result<String> {
+            self.imports.fetch_add(1, Ordering::SeqCst);
+            Ok(String::from_utf8(bytes)?)
+        }
+    }
+
+    impl Importer<Text> for CountingImporter {
+        const VERSION: u32 = 1;
+    }
+
+    /// Text in upper case, imported with the same importer as `Text`.
+    struct Upper(String);
+
+    impl Asset for Upper {
+        const NAME: &'static str = "test::Upper";
+        type Data = String;
+        type HandleStorage = VecStorage<Handle<Self>>;
+    }
+
+    impl SimpleFormat<Upper> for CountingImporter {
+        const NAME: &'static str = "COUNTING";
+        type Options = ();
+
+        fn import(&self, bytes: Vec<u8>, _: ()) -> Result<String> {
+            self.imports.fetch_add(1, Ordering::SeqCst);
+            Ok(String::from_utf8(bytes)?.to_uppercase())
+        }
+    }
+
+    impl Importer<Upper> for CountingImporter {
+        const VERSION: u32 = 1;
+    }
+
+    /// An artifact cache in a directory of its own, which is removed when the cache is dropped.
+    struct TestCache(ArtifactCache);
+
+    impl TestCache {
+        fn new(test: &str) -> Self {
+            let dir = env::temp_dir().join(format!(
+                "amethyst_artifacts_{}_{}",
+                std::process::id(),
+                test
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            TestCache(ArtifactCache::new(dir))
+        }
+
+        fn fetch(
+            &self,
+            importer: &CountingImporter,
+            source_id: &str,
+            source: &Arc<dyn Source>,
+        ) -> String {
+            self.0
+                .fetch::<Text, _>("text", importer, (), source_id, source)
+                .unwrap()
+        }
+    }
+
+    impl Drop for TestCache {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(self.0.dir());
+        }
+    }
+
+    fn source(text: &str) -> (MemorySource, Arc<dyn Source>) {
+        let memory = MemorySource::new();
+        memory.insert("text", text);
+        (memory.clone(), Arc::new(memory))
+    }
+
+    #[test]
+    fn artifact_is_reused_until_the_source_changes() {
+        let cache = TestCache::new("reuse");
+        let importer = CountingImporter::default();
+        let (memory, source) = source("old");
+
+        assert_eq!("old", cache.fetch(&importer, "memory", &source));
+        assert_eq!("old", cache.fetch(&importer, "memory", &source));
+        assert_eq!(1, importer.imports());
+
+        memory.insert("text", "new");
+        assert_eq!("new", cache.fetch(&importer, "memory", &source));
+        assert_eq!(2, importer.imports());
+    }
+
+    #[test]
+    fn artifact_is_used_without_the_source_file() {
+        let cache = TestCache::new("shipped");
+        let importer = CountingImporter::default();
+        let (_, source) = source("built");
+        cache
+            .0
+            .build::<Text, _>("text", &importer, (), "memory", &source)
+            .unwrap();
+
+        let shipped: Arc<dyn Source> = Arc::new(MemorySource::new());
+        assert_eq!("built", cache.fetch(&importer, "memory", &shipped));
+        assert_eq!(1, importer.imports());
+    }
+
+    #[test]
+    fn artifacts_are_keyed_by_source() {
+        let cache = TestCache::new("sources");
+        let importer = CountingImporter::default();
+        let (_, first) = source("first");
+        let (_, second) = source("second");
+
+        assert_eq!("first", cache.fetch(&importer, "first", &first));
+        assert_eq!("second", cache.fetch(&importer, "second", &second));
+        assert_eq!("first", cache.fetch(&importer, "first", &first));
+        assert_eq!(2, importer.imports());
+    }
+
+    #[test]
+    fn artifacts_are_keyed_by_asset_type() {
+        let cache = TestCache::new("asset_types");
+        let importer = CountingImporter::default();
+        let (_, source) = source("text");
+
+        assert_eq!("text", cache.fetch(&importer, "memory", &source));
+        let upper = cache
+            .0
+            .fetch::<Upper, _>("text", &importer, (), "memory", &source)
+            .unwrap();
+        assert_eq!("TEXT", upper);
+        assert_eq!("text", cache.fetch(&importer, "memory", &source));
+        assert_eq!(2, importer.imports());
+    }
+}
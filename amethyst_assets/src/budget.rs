@@ -0,0 +1,152 @@
+use std::mem::size_of;
+
+use fnv::FnvHashMap;
+
+/// A memory budget for the assets of a single `AssetStorage`.
+///
+/// Without a budget, an asset is freed as soon as the last `Handle` pointing to it is dropped.
+/// With a budget, assets that are only referenced by `WeakHandle`s (e.g. through a `Cache`) stay
+/// resident as long as the storage is within its budget, and can be revived by upgrading a weak
+/// handle. Once the budget is exceeded, the least recently used of those assets are evicted first.
+///
+/// ### Example:
+///
+/// ```rust,ignore
+/// world
+///     .write_resource::<AssetStorage<Mesh>>()
+///     .set_budget(MemoryBudget::new(256 * 1024 * 1024).with_size_fn(mesh_size));
+/// ```
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""), Debug(bound = ""))]
+pub struct MemoryBudget<A> {
+    limit: usize,
+    #[derivative(Debug = "ignore")]
+    size_fn: fn(&A) -> usize,
+}
+
+impl<A> MemoryBudget<A> {
+    /// Creates a new budget of `limit` bytes.
+    ///
+    /// Until a size function is set with `with_size_fn`, every asset is assumed to occupy
+    /// `size_of::<A>()` bytes.
+    pub fn new(limit: usize) -> Self {
+        MemoryBudget {
+            limit,
+            size_fn: default_size::<A>,
+        }
+    }
+
+    /// Sets the function used to compute the number of bytes an asset occupies.
+    pub fn with_size_fn(mut self, size_fn: fn(&A) -> usize) -> Self {
+        self.size_fn = size_fn;
+        self
+    }
+
+    /// Returns the number of bytes this budget allows.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Returns the number of bytes the given asset occupies.
+    pub fn size_of(&self, asset: &A) -> usize {
+        (self.size_fn)(asset)
+    }
+}
+
+fn default_size<A>(_: &A) -> usize {
+    size_of::<A>()
+}
+
+/// Keeps track of the memory occupied by the assets of an `AssetStorage`,
+/// and of which assets are only kept alive by the storage itself.
+#[derive(Derivative)]
+#[derivative(Default(bound = ""))]
+pub(crate) struct Residency<A> {
+    budget: Option<MemoryBudget<A>>,
+    sizes: FnvHashMap<u32, usize>,
+    used_bytes: usize,
+    /// Assets not referenced by any `Handle` outside the storage,
+    /// with the frame number at which they became unreferenced.
+    idle: FnvHashMap<u32, u64>,
+}
+
+impl<A> Residency<A> {
+    pub fn budget(&self) -> Option<&MemoryBudget<A>> {
+        self.budget.as_ref()
+    }
+
+    /// Sets the budget and recomputes the sizes of all given assets.
+    pub fn set_budget<'a, I>(&mut self, budget: MemoryBudget<A>, assets: I)
+    where
+        A: 'a,
+        I: IntoIterator<Item = (u32, &'a A)>,
+    {
+        self.sizes = assets
+            .into_iter()
+            .map(|(id, asset)| (id, budget.size_of(asset)))
+            .collect();
+        self.used_bytes = self.sizes.values().sum();
+        self.budget = Some(budget);
+    }
+
+    pub fn clear_budget(&mut self) {
+        self.budget = None;
+        self.sizes.clear();
+        self.used_bytes = 0;
+        self.idle.clear();
+    }
+
+    pub fn used_bytes(&self) -> usize {
+        self.used_bytes
+    }
+
+    /// Records the size of a newly inserted or replaced asset.
+    pub fn insert(&mut self, id: u32, asset: &A) {
+        if let Some(ref budget) = self.budget {
+            let size = budget.size_of(asset);
+            if let Some(old) = self.sizes.insert(id, size) {
+                self.used_bytes -= old;
+            }
+            self.used_bytes += size;
+        }
+    }
+
+    pub fn remove(&mut self, id: u32) {
+        if let Some(size) = self.sizes.remove(&id) {
+            self.used_bytes -= size;
+        }
+        self.idle.remove(&id);
+    }
+
+    /// Marks an asset as referenced from outside the storage.
+    pub fn touch(&mut self, id: u32) {
+        self.idle.remove(&id);
+    }
+
+    /// Marks an asset as no longer referenced from outside the storage.
+    ///
+    /// Returns `false` if there is no budget, in which case the asset should be freed right away.
+    pub fn release(&mut self, id: u32, frame_number: u64) -> bool {
+        if self.budget.is_some() {
+            self.idle.entry(id).or_insert(frame_number);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the least recently used idle asset if the budget is exceeded.
+    pub fn evict(&mut self) -> Option<u32> {
+        let limit = self.budget.as_ref()?.limit();
+        if self.used_bytes <= limit {
+            return None;
+        }
+        let id = self
+            .idle
+            .iter()
+            .min_by_key(|&(_, frame)| *frame)
+            .map(|(id, _)| *id)?;
+        self.remove(id);
+        Some(id)
+    }
+}
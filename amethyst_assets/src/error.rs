@@ -28,5 +28,10 @@ error_chain! {
         UnusedHandle {
             description("Asset was loaded but no handle to it was saved.")
         }
+
+        /// Returned if all handles to an asset were dropped before it finished loading.
+        Cancelled {
+            description("Asset loading was cancelled because all handles to it were dropped.")
+        }
     }
 }
@@ -1,8 +1,8 @@
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize};
 
 use crate::{
     error::{Error, ResultExt},
-    Asset, SimpleFormat,
+    Asset, MigrationFormat, SimpleFormat,
 };
 
 /// Format for loading from Ron files.
@@ -18,14 +18,33 @@ where
     type Options = ();
 
     fn import(&self, bytes: Vec<u8>, _: ()) -> Result<T::Data, Error> {
+        MigrationFormat::deserialize(self, &bytes)
+    }
+}
+
+impl MigrationFormat for RonFormat {
+    const VERSIONED_NAME: &'static str = "VersionedRon";
+    type Value = ron::value::Value;
+
+    fn deserialize<D>(&self, bytes: &[u8]) -> Result<D, Error>
+    where
+        D: DeserializeOwned,
+    {
         use ron::de::Deserializer;
         let mut d =
-            Deserializer::from_bytes(&bytes).chain_err(|| "Failed deserializing Ron file")?;
-        let val = T::Data::deserialize(&mut d).chain_err(|| "Failed parsing Ron file")?;
+            Deserializer::from_bytes(bytes).chain_err(|| "Failed deserializing Ron file")?;
+        let val = D::deserialize(&mut d).chain_err(|| "Failed parsing Ron file")?;
         d.end().chain_err(|| "Failed parsing Ron file")?;
 
         Ok(val)
     }
+
+    fn from_value<D>(&self, value: Self::Value) -> Result<D, Error>
+    where
+        D: DeserializeOwned,
+    {
+        D::deserialize(value).chain_err(|| "Failed parsing migrated Ron document")
+    }
 }
 
 /// Format for loading from Json files.
@@ -43,11 +62,31 @@ where
     type Options = ();
 
     fn import(&self, bytes: Vec<u8>, _: ()) -> Result<T::Data, Error> {
+        MigrationFormat::deserialize(self, &bytes)
+    }
+}
+
+#[cfg(feature = "json")]
+impl MigrationFormat for JsonFormat {
+    const VERSIONED_NAME: &'static str = "VersionedJson";
+    type Value = serde_json::Value;
+
+    fn deserialize<D>(&self, bytes: &[u8]) -> Result<D, Error>
+    where
+        D: DeserializeOwned,
+    {
         use serde_json::de::Deserializer;
-        let mut d = Deserializer::from_slice(&bytes);
-        let val = T::Data::deserialize(&mut d).chain_err(|| "Failed deserializing Json file")?;
+        let mut d = Deserializer::from_slice(bytes);
+        let val = D::deserialize(&mut d).chain_err(|| "Failed deserializing Json file")?;
         d.end().chain_err(|| "Failed parsing Json file")?;
 
         Ok(val)
     }
+
+    fn from_value<D>(&self, value: Self::Value) -> Result<D, Error>
+    where
+        D: DeserializeOwned,
+    {
+        serde_json::from_value(value).chain_err(|| "Failed parsing migrated Json document")
+    }
 }
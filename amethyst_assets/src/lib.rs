@@ -32,27 +32,39 @@ extern crate shred_derive;
 extern crate thread_profiler;
 
 pub use crate::{
+    artifact::{ArtifactCache, Importer},
     asset::{Asset, Format, FormatValue, SimpleFormat},
+    budget::MemoryBudget,
     cache::Cache,
     error::{Error, ErrorKind, Result, ResultExt},
     formats::RonFormat,
     helper::AssetLoaderSystemData,
-    loader::Loader,
-    prefab::{AssetPrefab, Prefab, PrefabData, PrefabError, PrefabLoader, PrefabLoaderSystem},
-    progress::{Completion, Progress, ProgressCounter, Tracker},
+    loader::{LoadRequest, Loader},
+    manifest::{AssetGroup, AssetManifest, ManifestEntry},
+    migration::{MigrationFormat, Migrations, Versioned},
+    prefab::{
+        AssetPrefab, Prefab, PrefabData, PrefabError, PrefabLoader, PrefabLoaderSystem,
+        PrefabReloadPolicy,
+    },
+    progress::{AssetErrorMeta, Completion, Progress, ProgressCounter, Tracker},
     reload::{HotReloadBundle, HotReloadStrategy, HotReloadSystem, Reload, SingleFile},
-    source::{Directory, Source},
+    source::{Directory, EmbeddedSource, MemorySource, Source},
     storage::{AssetStorage, Handle, ProcessingState, Processor, WeakHandle},
 };
 #[cfg(feature = "json")]
 pub use formats::JsonFormat;
 
+mod artifact;
 mod asset;
+mod budget;
 mod cache;
 mod error;
 mod formats;
 mod helper;
 mod loader;
+mod manifest;
+mod meta;
+mod migration;
 mod prefab;
 mod progress;
 mod reload;
@@ -1,18 +1,44 @@
-use std::{borrow::Borrow, hash::Hash, path::PathBuf, sync::Arc};
+use std::{
+    any::{Any, TypeId},
+    borrow::Borrow,
+    cmp::Ordering,
+    collections::BinaryHeap,
+    hash::Hash,
+    mem,
+    path::PathBuf,
+    sync::{
+        atomic::{self, AtomicUsize},
+        Arc,
+    },
+};
 
 use fnv::FnvHashMap;
+use parking_lot::Mutex;
 use rayon::ThreadPool;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use amethyst_core::specs::prelude::Resources;
 
 use crate::{
-    storage::{AssetStorage, Handle, Processed},
-    Asset, Directory, ErrorKind, Format, FormatValue, Progress, ResultExt, Source,
+    artifact::{ArtifactCache, ArtifactReload, Importer},
+    manifest::{load_entry, AssetGroup, AssetManifest, LoadEntryFn},
+    meta::FormatRegistry,
+    storage::{AssetStorage, Handle, Processed, WeakHandle},
+    Asset, Directory, Error, ErrorKind, Format, FormatValue, Progress, Reload, Result, ResultExt,
+    Source, Tracker,
 };
 
 /// The asset loader, holding the sources and a reference to the `ThreadPool`.
 pub struct Loader {
+    artifacts: Option<Arc<ArtifactCache>>,
     directory: Arc<Directory>,
+    formats: FnvHashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    group_loaders: FnvHashMap<&'static str, LoadEntryFn>,
     hot_reload: bool,
+    loaded: Mutex<LoadedAssets>,
+    manifest: AssetManifest,
     pool: Arc<ThreadPool>,
+    queue: Arc<LoadQueue>,
     sources: FnvHashMap<String, Arc<dyn Source>>,
 }
 
@@ -24,9 +50,15 @@ impl Loader {
         P: Into<PathBuf>,
     {
         Loader {
+            artifacts: None,
             directory: Arc::new(Directory::new(directory)),
+            formats: Default::default(),
+            group_loaders: Default::default(),
             hot_reload: true,
+            loaded: Default::default(),
+            manifest: Default::default(),
             pool,
+            queue: Default::default(),
             sources: Default::default(),
         }
     }
@@ -50,6 +82,193 @@ impl Loader {
         self.hot_reload = value;
     }
 
+    /// Sets the `ArtifactCache` used by `load_cached` and `load_cached_from`.
+    pub fn set_artifact_cache(&mut self, cache: ArtifactCache) {
+        self.artifacts = Some(Arc::new(cache));
+    }
+
+    /// Registers a format for `load_auto`.
+    ///
+    /// `options` are used for assets that have no sidecar file, or whose sidecar file does not
+    /// declare any options. `extensions` lists the file extensions (without the leading dot)
+    /// for which this format is picked if an asset has no sidecar file or its sidecar file does
+    /// not declare a format. Registering a format with the same name as an already registered
+    /// one replaces it.
+    pub fn register_format<A, F>(&mut self, format: F, options: F::Options, extensions: &[&str])
+    where
+        A: Asset,
+        F: Format<A> + Sync,
+        F::Options: Clone + Sync + for<'a> Deserialize<'a>,
+    {
+        let registry = self
+            .formats
+            .entry(TypeId::of::<A>())
+            .or_insert_with(|| Box::new(Arc::new(FormatRegistry::<A>::default())))
+            .downcast_mut::<Arc<FormatRegistry<A>>>()
+            .expect("Unreachable: Format registries are stored by the `TypeId` of their asset");
+        Arc::make_mut(registry).register(format, options, extensions);
+        self.group_loaders.insert(A::NAME, load_entry::<A>);
+    }
+
+    /// Sets the `AssetManifest` declaring the groups loaded by `load_group`.
+    pub fn set_manifest(&mut self, manifest: AssetManifest) {
+        self.manifest = manifest;
+    }
+
+    /// Reads the `AssetManifest` from a RON file in the default (directory) source,
+    /// and sets it as the manifest of this `Loader`.
+    pub fn load_manifest<N>(&mut self, name: N) -> Result<()>
+    where
+        N: AsRef<str>,
+    {
+        let name = name.as_ref();
+        let bytes = self.directory.load(name).chain_err(|| ErrorKind::Source)?;
+        let manifest = ron::de::from_bytes(&bytes)
+            .chain_err(|| format!("Failed parsing asset manifest {:?}", name))?;
+        self.set_manifest(manifest);
+        Ok(())
+    }
+
+    /// Returns the `AssetManifest` of this `Loader`.
+    pub fn manifest(&self) -> &AssetManifest {
+        &self.manifest
+    }
+
+    /// Loads all assets of a group declared in the `AssetManifest`.
+    ///
+    /// The assets are loaded like with `load_auto_from`, using the format named in the manifest
+    /// if any. The options of a sidecar file are then only used if it declares the same format or
+    /// none. The format and the asset type of every entry must have been registered with
+    /// `register_format`, and the `AssetStorage` of every asset type must be in `res`.
+    ///
+    /// Returns an error if the group is not declared or an entry can not be loaded. Errors
+    /// while importing the assets are reported through the `AssetGroup`.
+    pub fn load_group(&self, name: &str, res: &Resources) -> Result<AssetGroup> {
+        let entries = self
+            .manifest
+            .group(name)
+            .ok_or_else(|| format!("No asset group {:?} in the asset manifest", name))?;
+
+        let mut group = AssetGroup::new(name.to_owned());
+        for entry in entries {
+            let load = self
+                .group_loaders
+                .get(entry.asset.as_str())
+                .ok_or_else(|| {
+                    format!(
+                        "No format registered for assets of type {:?} in asset group {:?}",
+                        entry.asset, name,
+                    )
+                })?;
+            let handle = load(self, entry, &mut group.progress, res)
+                .chain_err(|| format!("Failed loading asset group {:?}", name))?;
+            group.handles.push((entry.clone(), handle));
+        }
+
+        Ok(group)
+    }
+
+    /// Loads an asset from the default (directory) source, picking the format and its options
+    /// from its sidecar file or its extension.
+    ///
+    /// See `load_auto_from` for more information.
+    pub fn load_auto<A, N, P>(&self, name: N, progress: P, storage: &AssetStorage<A>) -> Handle<A>
+    where
+        A: Asset,
+        N: Into<String>,
+        P: Progress,
+    {
+        self.load_auto_from(name, "", progress, storage)
+    }
+
+    /// Loads an asset from a custom source, picking the format and its options
+    /// from its sidecar file or its extension.
+    ///
+    /// The sidecar file of an asset is a RON file next to it, named like the asset with an
+    /// additional `.meta` extension, e.g. `texture/logo.png.meta` for `texture/logo.png`.
+    /// Both of its fields are optional:
+    ///
+    /// ```ron
+    /// (
+    ///     format: "PNG",
+    ///     options: (
+    ///         channel: Unorm,
+    ///         mip_levels: 4,
+    ///     ),
+    /// )
+    /// ```
+    ///
+    /// The format is looked up by its `Format::NAME` among the formats registered with
+    /// `register_format`. If no format is declared, the format registered for the extension of
+    /// the asset is used. If no options are declared, the options passed to `register_format`
+    /// are used.
+    ///
+    /// Like `load_shared`, this returns the existing handle if the asset has already been loaded
+    /// with `load_auto_from` and a handle to it is still alive.
+    ///
+    /// See `load_from` for a description of the parameters.
+    pub fn load_auto_from<A, N, P, S>(
+        &self,
+        name: N,
+        source: &S,
+        progress: P,
+        storage: &AssetStorage<A>,
+    ) -> Handle<A>
+    where
+        A: Asset,
+        N: Into<String>,
+        P: Progress,
+        S: AsRef<str> + Eq + Hash + ?Sized,
+        String: Borrow<S>,
+    {
+        self.load_registered(name, String::new(), source, progress, storage)
+    }
+
+    /// Loads an asset with a format registered with `register_format`,
+    /// or with the format picked by `load_auto_from` if `format` is empty.
+    pub(crate) fn load_registered<A, N, P, S>(
+        &self,
+        name: N,
+        format: String,
+        source: &S,
+        progress: P,
+        storage: &AssetStorage<A>,
+    ) -> Handle<A>
+    where
+        A: Asset,
+        N: Into<String>,
+        P: Progress,
+        S: AsRef<str> + Eq + Hash + ?Sized,
+        String: Borrow<S>,
+    {
+        let registry = self
+            .formats
+            .get(&TypeId::of::<A>())
+            .and_then(|r| r.downcast_ref::<Arc<FormatRegistry<A>>>())
+            .cloned()
+            .unwrap_or_default();
+
+        let name = name.into();
+        let key = LoadKey::new::<A, FormatRegistry<A>>(source.as_ref(), &name);
+        self.deduplicate(key, format, progress, |format, progress, shared| {
+            let load = QueuedLoad {
+                name,
+                format_name: "[auto]",
+                source: source.as_ref(),
+                priority: 0,
+                shared,
+            };
+            self.queue_load(
+                load,
+                progress,
+                storage,
+                move |name, source, create_reload| {
+                    FormatRegistry::import(&registry, name, &format, source, create_reload)
+                },
+            )
+        })
+    }
+
     /// Loads an asset with a given format from the default (directory) source.
     /// If you want to load from a custom source instead, use `load_from`.
     ///
@@ -73,9 +292,37 @@ impl Loader {
         self.load_from::<A, F, _, _, _>(name, format, options, "", progress, storage)
     }
 
+    /// Loads an asset with a given format and priority from the default (directory) source.
+    ///
+    /// See `load_request` for more information.
+    pub fn load_with_priority<A, F, N, P>(
+        &self,
+        name: N,
+        format: F,
+        options: F::Options,
+        priority: i32,
+        progress: P,
+        storage: &AssetStorage<A>,
+    ) -> Handle<A>
+    where
+        A: Asset,
+        F: Format<A>,
+        N: Into<String>,
+        P: Progress,
+    {
+        let request = LoadRequest::new(name, format, options).with_priority(priority);
+        self.load_request(request, progress, storage)
+    }
+
     /// Loads an asset with a given id and format from a custom source.
     /// The actual work is done in a worker thread, thus this method immediately returns a handle.
     ///
+    /// Every call loads the asset again, use `load_shared` to reuse an asset which has already
+    /// been loaded.
+    ///
+    /// The load is queued with the default priority of `0`,
+    /// see `load_request` for more information.
+    ///
     /// ## Parameters
     ///
     /// * `name`: this is just an identifier for the asset, most likely a file name e.g.
@@ -94,7 +341,7 @@ impl Loader {
         format: F,
         options: F::Options,
         source: &S,
-        mut progress: P,
+        progress: P,
         storage: &AssetStorage<A>,
     ) -> Handle<A>
     where
@@ -104,15 +351,264 @@ impl Loader {
         P: Progress,
         S: AsRef<str> + Eq + Hash + ?Sized,
         String: Borrow<S>,
+    {
+        let request = LoadRequest::new(name, format, options).with_source(source.as_ref());
+        self.load_request(request, progress, storage)
+    }
+
+    /// Loads an asset described by a `LoadRequest`.
+    /// The actual work is done in a worker thread, thus this method immediately returns a handle.
+    ///
+    /// Pending loads are started in order of descending priority, loads with the same priority
+    /// are started in the order they were requested. If all handles to the asset are dropped
+    /// before its data has been imported, the load is cancelled and the progress is notified
+    /// with `ErrorKind::Cancelled`.
+    ///
+    /// See `load_from` for a description of the other parameters.
+    pub fn load_request<A, F, P>(
+        &self,
+        request: LoadRequest<F, F::Options>,
+        progress: P,
+        storage: &AssetStorage<A>,
+    ) -> Handle<A>
+    where
+        A: Asset,
+        F: Format<A> + 'static,
+        P: Progress,
     {
         #[cfg(feature = "profiler")]
         profile_scope!("load_asset_from");
-        use crate::progress::Tracker;
+
+        let LoadRequest {
+            name,
+            format,
+            options,
+            source,
+            priority,
+        } = request;
+        let load = QueuedLoad {
+            name,
+            format_name: F::NAME,
+            source: &source,
+            priority,
+            shared: SharedLoad::default(),
+        };
+        self.queue_load(
+            load,
+            progress,
+            storage,
+            move |name, source, create_reload| format.import(name, source, options, create_reload),
+        )
+    }
+
+    /// Loads an asset described by a `LoadRequest`, reusing the asset if it has already been
+    /// loaded.
+    ///
+    /// If an asset with the same name has already been loaded with `load_shared` from the same
+    /// source with the same format and options, and a handle to it is still alive, that handle
+    /// is returned instead. `progress` is then notified once the shared load finishes. Loads
+    /// which failed are not reused.
+    ///
+    /// See `load_request` for more information.
+    pub fn load_shared<A, F, P>(
+        &self,
+        request: LoadRequest<F, F::Options>,
+        progress: P,
+        storage: &AssetStorage<A>,
+    ) -> Handle<A>
+    where
+        A: Asset,
+        F: Format<A> + 'static,
+        F::Options: Clone + PartialEq,
+        P: Progress,
+    {
+        #[cfg(feature = "profiler")]
+        profile_scope!("load_shared_asset_from");
+
+        let LoadRequest {
+            name,
+            format,
+            options,
+            source,
+            priority,
+        } = request;
+        let key = LoadKey::new::<A, F>(&source, &name);
+        self.deduplicate(key, options, progress, |options, progress, shared| {
+            let load = QueuedLoad {
+                name,
+                format_name: F::NAME,
+                source: &source,
+                priority,
+                shared,
+            };
+            self.queue_load(
+                load,
+                progress,
+                storage,
+                move |name, source, create_reload| {
+                    format.import(name, source, options, create_reload)
+                },
+            )
+        })
+    }
+
+    /// Loads an asset with a given importer from the default (directory) source,
+    /// reading its data from the `ArtifactCache` of this `Loader` if possible.
+    ///
+    /// See `load_cached_from` for more information.
+    pub fn load_cached<A, I, N, P>(
+        &self,
+        name: N,
+        importer: I,
+        options: I::Options,
+        progress: P,
+        storage: &AssetStorage<A>,
+    ) -> Handle<A>
+    where
+        A: Asset,
+        A::Data: Serialize + DeserializeOwned,
+        I: Importer<A> + Clone + Send + Sync + 'static,
+        I::Options: Clone + Serialize + PartialEq + Sync,
+        N: Into<String>,
+        P: Progress,
+    {
+        self.load_cached_from(name, importer, options, "", progress, storage)
+    }
+
+    /// Loads an asset with a given id and importer from a custom source,
+    /// reading its data from the `ArtifactCache` of this `Loader` if possible.
+    ///
+    /// If the artifact of the asset is missing or out of date, the asset is imported from
+    /// the source and the artifact is updated. Without an artifact cache set through
+    /// `set_artifact_cache`, this behaves exactly like `load_shared`.
+    ///
+    /// Like `load_shared`, this returns the existing handle if the asset has already been loaded
+    /// with `load_cached_from` and a handle to it is still alive.
+    ///
+    /// See `load_from` for a description of the parameters.
+    pub fn load_cached_from<A, I, N, P, S>(
+        &self,
+        name: N,
+        importer: I,
+        options: I::Options,
+        source: &S,
+        progress: P,
+        storage: &AssetStorage<A>,
+    ) -> Handle<A>
+    where
+        A: Asset,
+        A::Data: Serialize + DeserializeOwned,
+        I: Importer<A> + Clone + Send + Sync + 'static,
+        I::Options: Clone + Serialize + PartialEq + Sync,
+        N: Into<String>,
+        P: Progress,
+        S: AsRef<str> + Eq + Hash + ?Sized,
+        String: Borrow<S>,
+    {
+        let cache = match self.artifacts {
+            Some(ref cache) => cache.clone(),
+            None => {
+                let request =
+                    LoadRequest::new(name, importer, options).with_source(source.as_ref());
+                return self.load_shared(request, progress, storage);
+            }
+        };
 
         let name = name.into();
-        let source = source.as_ref();
+        let source_id = source.as_ref().to_owned();
+        let key = LoadKey::new::<A, I>(&source_id, &name);
+        self.deduplicate(key, options, progress, |options, progress, shared| {
+            let load = QueuedLoad {
+                name,
+                format_name: <I as Format<A>>::NAME,
+                source: source.as_ref(),
+                priority: 0,
+                shared,
+            };
+            self.queue_load(
+                load,
+                progress,
+                storage,
+                move |name, source, create_reload| {
+                    let data = cache.fetch::<A, I>(
+                        &name,
+                        &importer,
+                        options.clone(),
+                        &source_id,
+                        &source,
+                    )?;
+                    let reload = if create_reload {
+                        let reload =
+                            ArtifactReload::new(cache, importer, options, name, source_id, source);
+                        Some(Box::new(reload) as Box<dyn Reload<A>>)
+                    } else {
+                        None
+                    };
 
-        let format_name = F::NAME;
+                    Ok(FormatValue { data, reload })
+                },
+            )
+        })
+    }
+
+    /// Returns the handle of an asset which has already been loaded with the same key and
+    /// options if it is still alive and did not fail to load. Otherwise, the asset is loaded
+    /// with `load`.
+    ///
+    /// If the handle is reused, `progress` is notified once the shared load finishes.
+    fn deduplicate<A, O, P, L>(
+        &self,
+        key: LoadKey,
+        options: O,
+        mut progress: P,
+        load: L,
+    ) -> Handle<A>
+    where
+        A: Asset,
+        O: Clone + PartialEq + Send + 'static,
+        P: Progress,
+        L: FnOnce(O, P, SharedLoad) -> Handle<A>,
+    {
+        let mut loaded = self.loaded.lock();
+        loaded.prune();
+
+        if let Some((handle, shared)) = loaded.get::<A, O>(&key, &options) {
+            debug!(
+                "{:?}: Reusing already loaded asset (handle id: {:?})",
+                A::NAME,
+                handle,
+            );
+            progress.add_assets(1);
+            shared.add_tracker(Box::new(progress.create_tracker()));
+            return handle;
+        }
+
+        let shared = SharedLoad::default();
+        let handle = load(options.clone(), progress, shared.clone());
+        loaded.insert(key, &handle, options, shared);
+
+        handle
+    }
+
+    fn queue_load<A, P, I>(
+        &self,
+        load: QueuedLoad<'_>,
+        mut progress: P,
+        storage: &AssetStorage<A>,
+        import: I,
+    ) -> Handle<A>
+    where
+        A: Asset,
+        P: Progress,
+        I: FnOnce(String, Arc<dyn Source>, bool) -> Result<FormatValue<A>> + Send + 'static,
+    {
+        let QueuedLoad {
+            name,
+            format_name,
+            source,
+            priority,
+            shared,
+        } = load;
         let source_name = match source {
             "" => "[default source]",
             other => other,
@@ -135,9 +631,13 @@ impl Loader {
         };
 
         progress.add_assets(1);
-        let tracker = progress.create_tracker();
+        let tracker = SharedTracker {
+            tracker: Box::new(progress.create_tracker()),
+            shared,
+        };
 
-        let handle_clone = handle.clone();
+        let handle_id = handle.id();
+        let weak_handle = handle.downgrade();
         let processed = storage.processed.clone();
 
         let hot_reload = self.hot_reload;
@@ -145,21 +645,39 @@ impl Loader {
         let cl = move || {
             #[cfg(feature = "profiler")]
             profile_scope!("load_asset_from_worker");
-            let data = format
-                .import(name.clone(), source, options, hot_reload)
-                .chain_err(|| ErrorKind::Format(F::NAME));
             let tracker = Box::new(tracker) as Box<dyn Tracker>;
+            if weak_handle.is_dead() {
+                processed.push(Processed::Cancelled {
+                    handle_id,
+                    name,
+                    tracker,
+                });
+                return;
+            }
 
-            processed.push(Processed::NewAsset {
-                data,
-                handle,
-                name,
-                tracker,
-            });
+            let data = import(name.clone(), source, hot_reload)
+                .chain_err(|| ErrorKind::Format(format_name));
+
+            let p = match weak_handle.upgrade() {
+                Some(handle) => Processed::NewAsset {
+                    data,
+                    handle,
+                    name,
+                    tracker,
+                },
+                None => Processed::Cancelled {
+                    handle_id,
+                    name,
+                    tracker,
+                },
+            };
+            processed.push(p);
         };
-        self.pool.spawn(cl);
+        self.queue.push(priority, cl);
+        let queue = self.queue.clone();
+        self.pool.spawn(move || queue.run_next());
 
-        handle_clone
+        handle
     }
 
     /// Load an asset from data and return a handle.
@@ -187,6 +705,43 @@ impl Loader {
         handle
     }
 
+    /// Records where the asset of `handle` was loaded from, which is returned by `origin` for
+    /// as long as the handle is alive.
+    ///
+    /// This is used by `PrefabData` implementations to extract the files of the assets used by
+    /// live entities, see `AssetPrefab`.
+    pub fn set_origin<A, O>(&self, handle: &Handle<A>, origin: O)
+    where
+        A: Asset,
+        O: Send + 'static,
+    {
+        let mut loaded = self.loaded.lock();
+        loaded.prune();
+        loaded.origins.insert(
+            (TypeId::of::<A>(), handle.id()),
+            LoadOrigin {
+                handle: Box::new(handle.downgrade()),
+                origin: Box::new(origin),
+            },
+        );
+    }
+
+    /// Returns where the asset of `handle` was loaded from, if it has been recorded with
+    /// `set_origin`.
+    pub fn origin<A, O>(&self, handle: &Handle<A>) -> Option<O>
+    where
+        A: Asset,
+        O: Clone + 'static,
+    {
+        self.loaded
+            .lock()
+            .origins
+            .get(&(TypeId::of::<A>(), handle.id()))
+            .filter(|origin| !origin.handle.is_dead())
+            .and_then(|origin| origin.origin.downcast_ref::<O>())
+            .cloned()
+    }
+
     fn source(&self, source: &str) -> Arc<dyn Source> {
         self.sources
             .get(source)
@@ -194,3 +749,608 @@ impl Loader {
             .clone()
     }
 }
+
+/// An asset to load with `Loader::load_request`, together with its format, source and priority.
+///
+/// ### Example:
+///
+/// ```rust,ignore
+/// let request = LoadRequest::new("mesh/cube.obj", ObjFormat, ())
+///     .with_source("network")
+///     .with_priority(10);
+/// let handle = loader.load_request(request, &mut progress, &storage);
+/// ```
+#[derive(Clone, Debug)]
+pub struct LoadRequest<F, O> {
+    name: String,
+    format: F,
+    options: O,
+    source: String,
+    priority: i32,
+}
+
+impl<F, O> LoadRequest<F, O> {
+    /// Creates a request loading the asset `name` with the given format and options from the
+    /// default (directory) source, with the default priority of `0`.
+    pub fn new<N>(name: N, format: F, options: O) -> Self
+    where
+        N: Into<String>,
+    {
+        LoadRequest {
+            name: name.into(),
+            format,
+            options,
+            source: String::new(),
+            priority: 0,
+        }
+    }
+
+    /// Loads the asset from a source added with `Loader::add_source` instead.
+    pub fn with_source<S>(mut self, source: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.source = source.into();
+        self
+    }
+
+    /// Sets the priority of the load, higher priorities are started first.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+/// A load passed to `Loader::queue_load`.
+struct QueuedLoad<'a> {
+    name: String,
+    format_name: &'static str,
+    source: &'a str,
+    priority: i32,
+    shared: SharedLoad,
+}
+
+/// Identifies an asset loaded by the `Loader`, up to its format options.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct LoadKey {
+    asset: TypeId,
+    format: TypeId,
+    source: String,
+    name: String,
+}
+
+impl LoadKey {
+    fn new<A, F>(source: &str, name: &str) -> Self
+    where
+        A: Asset,
+        F: 'static,
+    {
+        LoadKey {
+            asset: TypeId::of::<A>(),
+            format: TypeId::of::<F>(),
+            source: source.to_owned(),
+            name: name.to_owned(),
+        }
+    }
+}
+
+/// The number of keys below which `LoadedAssets` is never pruned.
+const MIN_PRUNE_KEYS: usize = 64;
+
+/// The assets loaded by the `Loader`, used to deduplicate loads.
+#[derive(Default)]
+struct LoadedAssets {
+    assets: FnvHashMap<LoadKey, Vec<LoadedAsset>>,
+    /// Where assets have been loaded from, by asset type and handle id.
+    origins: FnvHashMap<(TypeId, u32), LoadOrigin>,
+    /// The number of keys and origins at which the entries of dropped assets are removed next.
+    prune_at: usize,
+}
+
+impl LoadedAssets {
+    /// Returns a handle to the asset loaded with `key` and `options` together with its load,
+    /// if the asset is still alive and did not fail to load.
+    fn get<A, O>(&mut self, key: &LoadKey, options: &O) -> Option<(Handle<A>, SharedLoad)>
+    where
+        A: Asset,
+        O: PartialEq + 'static,
+    {
+        let assets = self.assets.get_mut(key)?;
+        assets.retain(LoadedAsset::is_alive);
+
+        assets
+            .iter()
+            .filter(|asset| asset.options.downcast_ref::<O>() == Some(options))
+            .filter_map(|asset| {
+                asset
+                    .handle
+                    .as_any()
+                    .downcast_ref::<WeakHandle<A>>()
+                    .and_then(WeakHandle::upgrade)
+                    .map(|handle| (handle, asset.shared.clone()))
+            })
+            .next()
+    }
+
+    fn insert<A, O>(&mut self, key: LoadKey, handle: &Handle<A>, options: O, shared: SharedLoad)
+    where
+        A: Asset,
+        O: Send + 'static,
+    {
+        self.assets
+            .entry(key)
+            .or_insert_with(Vec::new)
+            .push(LoadedAsset {
+                handle: Box::new(handle.downgrade()),
+                options: Box::new(options),
+                shared,
+            });
+    }
+
+    /// Removes the entries of dropped and failed assets, the keys left without entries and the
+    /// origins of dropped assets.
+    ///
+    /// To keep the cost per load constant, this only happens once the number of keys and origins
+    /// doubled since the last time.
+    fn prune(&mut self) {
+        if self.assets.len() + self.origins.len() < self.prune_at {
+            return;
+        }
+
+        self.assets.retain(|_, assets| {
+            assets.retain(LoadedAsset::is_alive);
+            !assets.is_empty()
+        });
+        self.origins.retain(|_, origin| !origin.handle.is_dead());
+        self.prune_at = ((self.assets.len() + self.origins.len()) * 2).max(MIN_PRUNE_KEYS);
+    }
+}
+
+/// Where an asset has been loaded from, recorded with `Loader::set_origin`.
+struct LoadOrigin {
+    handle: Box<dyn AnyWeakHandle>,
+    origin: Box<dyn Any + Send>,
+}
+
+/// A weak handle to an asset loaded by the `Loader`, with the options it was loaded with.
+struct LoadedAsset {
+    handle: Box<dyn AnyWeakHandle>,
+    options: Box<dyn Any + Send>,
+    shared: SharedLoad,
+}
+
+impl LoadedAsset {
+    fn is_alive(&self) -> bool {
+        !self.handle.is_dead() && !self.shared.is_failed()
+    }
+}
+
+/// A `WeakHandle` with its asset type erased.
+trait AnyWeakHandle: Send {
+    fn as_any(&self) -> &dyn Any;
+
+    fn is_dead(&self) -> bool;
+}
+
+impl<A: Asset> AnyWeakHandle for WeakHandle<A> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_dead(&self) -> bool {
+        WeakHandle::is_dead(self)
+    }
+}
+
+/// The state of a load, shared by all requests deduplicated into it.
+#[derive(Clone, Default)]
+struct SharedLoad(Arc<Mutex<LoadState>>);
+
+enum LoadState {
+    /// The trackers of requests deduplicated into the load, notified once it finishes.
+    Loading(Vec<Box<dyn Tracker>>),
+    Loaded,
+    Failed,
+}
+
+impl Default for LoadState {
+    fn default() -> Self {
+        LoadState::Loading(Vec::new())
+    }
+}
+
+impl SharedLoad {
+    fn add_tracker(&self, tracker: Box<dyn Tracker>) {
+        let mut state = self.0.lock();
+        match *state {
+            LoadState::Loading(ref mut trackers) => trackers.push(tracker),
+            // Failed loads are never reused, so the asset has been loaded.
+            LoadState::Loaded | LoadState::Failed => tracker.success(),
+        }
+    }
+
+    fn is_failed(&self) -> bool {
+        match *self.0.lock() {
+            LoadState::Failed => true,
+            _ => false,
+        }
+    }
+
+    fn finish(&self, state: LoadState) -> Vec<Box<dyn Tracker>> {
+        match mem::replace(&mut *self.0.lock(), state) {
+            LoadState::Loading(trackers) => trackers,
+            LoadState::Loaded | LoadState::Failed => Vec::new(),
+        }
+    }
+}
+
+/// The tracker of a queued load, which also notifies the trackers of requests that have been
+/// deduplicated into it.
+struct SharedTracker {
+    tracker: Box<dyn Tracker>,
+    shared: SharedLoad,
+}
+
+impl Tracker for SharedTracker {
+    fn success(self: Box<Self>) {
+        let SharedTracker { tracker, shared } = *self;
+        tracker.success();
+        for tracker in shared.finish(LoadState::Loaded) {
+            tracker.success();
+        }
+    }
+
+    fn fail(
+        self: Box<Self>,
+        handle_id: u32,
+        asset_type_name: &'static str,
+        asset_name: String,
+        error: Error,
+    ) {
+        let SharedTracker { tracker, shared } = *self;
+        let message = error.to_string();
+        tracker.fail(handle_id, asset_type_name, asset_name.clone(), error);
+        for tracker in shared.finish(LoadState::Failed) {
+            tracker.fail(
+                handle_id,
+                asset_type_name,
+                asset_name.clone(),
+                Error::from(message.clone()),
+            );
+        }
+    }
+}
+
+/// Loads which have been requested but not started yet, ordered by priority.
+#[derive(Default)]
+struct LoadQueue {
+    jobs: Mutex<BinaryHeap<LoadJob>>,
+    next_seq: AtomicUsize,
+}
+
+impl LoadQueue {
+    fn push<F>(&self, priority: i32, job: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let seq = self.next_seq.fetch_add(1, atomic::Ordering::Relaxed);
+        self.jobs.lock().push(LoadJob {
+            priority,
+            seq,
+            job: Box::new(job),
+        });
+    }
+
+    /// Runs the pending load with the highest priority.
+    ///
+    /// One call to this is spawned on the thread pool for every pushed job.
+    fn run_next(&self) {
+        let job = self.jobs.lock().pop();
+        if let Some(job) = job {
+            job.job.run();
+        }
+    }
+}
+
+struct LoadJob {
+    priority: i32,
+    seq: usize,
+    job: Box<dyn LoadTask>,
+}
+
+impl PartialEq for LoadJob {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for LoadJob {}
+
+impl PartialOrd for LoadJob {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for LoadJob {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Higher priority first, then first come first served.
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+trait LoadTask: Send {
+    fn run(self: Box<Self>);
+}
+
+impl<F> LoadTask for F
+where
+    F: FnOnce() + Send,
+{
+    fn run(self: Box<Self>) {
+        (*self)()
+    }
+}
+
+/// Creates a thread pool whose only worker thread is kept busy until the returned sender is
+/// dropped, so tests can run the queued loads themselves with `Loader::run_queued_loads`.
+#[cfg(test)]
+pub(crate) fn blocked_pool() -> (Arc<ThreadPool>, std::sync::mpsc::Sender<()>) {
+    use std::sync::mpsc;
+
+    use rayon::ThreadPoolBuilder;
+
+    let pool = Arc::new(ThreadPoolBuilder::new().num_threads(1).build().unwrap());
+    let (started, wait_started) = mpsc::channel();
+    let (release, wait_release) = mpsc::channel::<()>();
+    pool.spawn(move || {
+        started.send(()).unwrap();
+        let _ = wait_release.recv();
+    });
+    wait_started.recv().unwrap();
+    (pool, release)
+}
+
+#[cfg(test)]
+impl Loader {
+    /// Runs all queued loads on the current thread, in the order of their priority.
+    pub(crate) fn run_queued_loads(&self) {
+        while !self.queue.jobs.lock().is_empty() {
+            self.queue.run_next();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use amethyst_core::specs::prelude::VecStorage;
+
+    use super::*;
+    use crate::{Completion, MemorySource, ProcessingState, ProgressCounter, SimpleFormat};
+
+    struct Text(String);
+
+    impl Asset for Text {
+        const NAME: &'static str = "test::Text";
+        type Data = String;
+        type HandleStorage = VecStorage<Handle<Self>>;
+    }
+
+    #[derive(Clone)]
+    struct TextFormat;
+
+    impl SimpleFormat<Text> for TextFormat {
+        const NAME: &'static str = "TEXT";
+        type Options = ();
+
+        fn import(&self, bytes: Vec<u8>, _: ()) -> Result<String> {
+            Ok(String::from_utf8(bytes)?)
+        }
+    }
+
+    fn blocked_loader(source: MemorySource) -> (Loader, mpsc::Sender<()>) {
+        let (pool, release) = blocked_pool();
+        let mut loader = Loader::new(".", pool);
+        loader.add_source("memory", source);
+        (loader, release)
+    }
+
+    fn process(loader: &Loader, storage: &mut AssetStorage<Text>) {
+        storage.process(
+            |text| Ok(ProcessingState::Loaded(Text(text))),
+            0,
+            &loader.pool,
+            None,
+        );
+    }
+
+    #[test]
+    fn queue_runs_loads_by_priority_then_request_order() {
+        let queue = LoadQueue::default();
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let loads = [
+            (0, "first"),
+            (10, "urgent"),
+            (-5, "background"),
+            (0, "second"),
+            (10, "also urgent"),
+        ];
+        for &(priority, name) in &loads {
+            let order = order.clone();
+            queue.push(priority, move || order.lock().push(name));
+        }
+        for _ in 0..loads.len() {
+            queue.run_next();
+        }
+
+        assert_eq!(
+            vec!["urgent", "also urgent", "first", "second", "background"],
+            *order.lock()
+        );
+    }
+
+    #[test]
+    fn load_request_is_queued_with_its_priority() {
+        let source = MemorySource::new();
+        source.insert("low", "low");
+        source.insert("high", "high");
+        let (loader, _release) = blocked_loader(source);
+        let mut storage = AssetStorage::<Text>::new();
+
+        let low = loader.load_request(
+            LoadRequest::new("low", TextFormat, ()).with_source("memory"),
+            (),
+            &storage,
+        );
+        let high = loader.load_request(
+            LoadRequest::new("high", TextFormat, ())
+                .with_source("memory")
+                .with_priority(1),
+            (),
+            &storage,
+        );
+        loader.queue.run_next();
+        process(&loader, &mut storage);
+        assert_eq!(Some("high"), storage.get(&high).map(|text| &*text.0));
+        assert!(storage.get(&low).is_none());
+
+        loader.queue.run_next();
+        process(&loader, &mut storage);
+        assert_eq!(Some("low"), storage.get(&low).map(|text| &*text.0));
+    }
+
+    #[test]
+    fn dropping_all_handles_cancels_the_load() {
+        let source = MemorySource::new();
+        source.insert("text", "hello");
+        let (loader, _release) = blocked_loader(source);
+        let mut storage = AssetStorage::<Text>::new();
+        let mut progress = ProgressCounter::new();
+
+        let handle = loader.load_from("text", TextFormat, (), "memory", &mut progress, &storage);
+        drop(handle);
+        loader.queue.run_next();
+        process(&loader, &mut storage);
+
+        assert_eq!(Completion::Failed, progress.complete());
+        let errors = progress.errors();
+        assert_eq!(1, errors.len());
+        match *errors[0].error.kind() {
+            ErrorKind::Cancelled => {}
+            ref other => panic!("Expected the load to be cancelled, got {:?}", other),
+        }
+    }
+
+    fn load_shared_text<P: Progress>(
+        loader: &Loader,
+        progress: P,
+        storage: &AssetStorage<Text>,
+    ) -> Handle<Text> {
+        let request = LoadRequest::new("text", TextFormat, ()).with_source("memory");
+        loader.load_shared(request, progress, storage)
+    }
+
+    #[test]
+    fn deduplicated_load_reports_progress_when_the_shared_load_finishes() {
+        let source = MemorySource::new();
+        source.insert("text", "hello");
+        let (loader, _release) = blocked_loader(source);
+        let mut storage = AssetStorage::<Text>::new();
+        let mut first_progress = ProgressCounter::new();
+        let mut second_progress = ProgressCounter::new();
+
+        let first = load_shared_text(&loader, &mut first_progress, &storage);
+        let second = load_shared_text(&loader, &mut second_progress, &storage);
+        assert_eq!(first, second);
+        assert_eq!(1, loader.queue.jobs.lock().len());
+        assert_eq!(Completion::Loading, second_progress.complete());
+
+        loader.queue.run_next();
+        process(&loader, &mut storage);
+
+        assert_eq!(Completion::Complete, first_progress.complete());
+        assert_eq!(Completion::Complete, second_progress.complete());
+        assert_eq!(1, second_progress.num_finished());
+    }
+
+    #[test]
+    fn failed_loads_are_not_reused() {
+        let source = MemorySource::new();
+        let (loader, _release) = blocked_loader(source.clone());
+        let mut storage = AssetStorage::<Text>::new();
+        let mut first_progress = ProgressCounter::new();
+        let mut second_progress = ProgressCounter::new();
+
+        let first = load_shared_text(&loader, &mut first_progress, &storage);
+        loader.queue.run_next();
+        process(&loader, &mut storage);
+        assert_eq!(Completion::Failed, first_progress.complete());
+
+        source.insert("text", "hello");
+        let second = load_shared_text(&loader, &mut second_progress, &storage);
+        assert_ne!(first, second);
+        loader.queue.run_next();
+        process(&loader, &mut storage);
+
+        assert_eq!(Completion::Complete, second_progress.complete());
+        assert_eq!("hello", storage.get(&second).unwrap().0);
+    }
+
+    #[test]
+    fn load_from_does_not_reuse_assets() {
+        let source = MemorySource::new();
+        source.insert("text", "hello");
+        let (loader, _release) = blocked_loader(source);
+        let storage = AssetStorage::<Text>::new();
+
+        let shared = load_shared_text(&loader, (), &storage);
+        let first = loader.load_from("text", TextFormat, (), "memory", (), &storage);
+        let second = loader.load_from("text", TextFormat, (), "memory", (), &storage);
+        assert_ne!(shared, first);
+        assert_ne!(first, second);
+        assert_eq!(3, loader.queue.jobs.lock().len());
+    }
+
+    #[test]
+    fn pruning_removes_dropped_assets() {
+        let storage = AssetStorage::<Text>::new();
+        let kept = storage.allocate();
+        let mut loaded = LoadedAssets::default();
+        loaded.insert(
+            LoadKey::new::<Text, TextFormat>("", "kept"),
+            &kept,
+            (),
+            SharedLoad::default(),
+        );
+        for i in 0..MIN_PRUNE_KEYS {
+            let key = LoadKey::new::<Text, TextFormat>("", &i.to_string());
+            loaded.insert(key, &storage.allocate(), (), SharedLoad::default());
+        }
+
+        loaded.prune();
+
+        assert_eq!(1, loaded.assets.len());
+        assert!(loaded
+            .get::<Text, ()>(&LoadKey::new::<Text, TextFormat>("", "kept"), &())
+            .is_some());
+    }
+
+    #[test]
+    fn load_auto_picks_the_format_from_the_sidecar() {
+        let source = MemorySource::new();
+        source.insert("greeting.txt", "hello");
+        source.insert("greeting.txt.meta", r#"(format: "TEXT")"#);
+        let (mut loader, _release) = blocked_loader(source);
+        loader.register_format::<Text, _>(TextFormat, (), &["text"]);
+        let mut storage = AssetStorage::<Text>::new();
+
+        let handle = loader.load_auto_from("greeting.txt", "memory", (), &storage);
+        loader.queue.run_next();
+        process(&loader, &mut storage);
+        assert_eq!(Some("hello"), storage.get(&handle).map(|text| &*text.0));
+    }
+}
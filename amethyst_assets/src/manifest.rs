@@ -0,0 +1,341 @@
+//! Asset manifests declaring named groups of assets, loaded with `Loader::load_group`.
+
+use std::any::Any;
+
+use fnv::FnvHashMap;
+
+use amethyst_core::specs::prelude::Resources;
+
+use crate::{
+    Asset, AssetErrorMeta, AssetStorage, Completion, Handle, Loader, ProgressCounter, Result,
+};
+
+/// A manifest declaring named groups of assets.
+///
+/// Manifests are usually written in RON and read with `Loader::load_manifest`:
+///
+/// ```ron
+/// (
+///     groups: {
+///         "level_1": [
+///             (asset: "renderer::Mesh", name: "mesh/cube.obj"),
+///             (asset: "renderer::Texture", name: "texture/crate.png", format: "PNG"),
+///             (asset: "renderer::Texture", name: "texture/logo.png", source: "embedded"),
+///         ],
+///     },
+/// )
+/// ```
+///
+/// `asset` is the `Asset::NAME` of the asset type. If `format` is omitted, the format is picked
+/// like with `Loader::load_auto`. If `source` is omitted, the default (directory) source is used.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct AssetManifest {
+    #[serde(default)]
+    groups: FnvHashMap<String, Vec<ManifestEntry>>,
+}
+
+impl AssetManifest {
+    /// Creates an empty manifest.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds an asset to a group, creating the group if it does not exist yet.
+    pub fn add(&mut self, group: &str, entry: ManifestEntry) {
+        self.groups
+            .entry(group.to_owned())
+            .or_insert_with(Vec::new)
+            .push(entry);
+    }
+
+    /// Returns the assets of a group.
+    pub fn group(&self, group: &str) -> Option<&[ManifestEntry]> {
+        self.groups.get(group).map(Vec::as_slice)
+    }
+
+    /// Returns the names of all groups.
+    pub fn groups(&self) -> impl Iterator<Item = &str> {
+        self.groups.keys().map(String::as_str)
+    }
+}
+
+/// A single asset in an `AssetManifest`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct ManifestEntry {
+    /// The `Asset::NAME` of the asset type, e.g. `"renderer::Mesh"`.
+    pub asset: String,
+    /// The name of the asset in its source, e.g. `"mesh/cube.obj"`.
+    pub name: String,
+    /// The `Format::NAME` of the format to load the asset with.
+    ///
+    /// If empty, the format is picked from the sidecar file or the extension of the asset.
+    #[serde(default)]
+    pub format: String,
+    /// The id of the source to load the asset from, or an empty string for the default source.
+    #[serde(default)]
+    pub source: String,
+}
+
+impl ManifestEntry {
+    /// Creates an entry for an asset of type `A` loaded from the default source,
+    /// with the format picked from its sidecar file or extension.
+    pub fn new<A, N>(name: N) -> Self
+    where
+        A: Asset,
+        N: Into<String>,
+    {
+        ManifestEntry {
+            asset: A::NAME.to_owned(),
+            name: name.into(),
+            format: String::new(),
+            source: String::new(),
+        }
+    }
+}
+
+/// The assets of a group loaded with `Loader::load_group`.
+///
+/// The group keeps all of its assets alive. Dropping the group, or calling `unload`, releases
+/// the handles of the group, so the assets are freed unless they are still used elsewhere.
+///
+/// A group can hold assets of different types, so handles are requested with their asset type
+/// through `handle`, which fails if the asset is of another type.
+pub struct AssetGroup {
+    name: String,
+    pub(crate) progress: ProgressCounter,
+    pub(crate) handles: Vec<(ManifestEntry, Box<dyn Any + Send + Sync>)>,
+}
+
+impl AssetGroup {
+    pub(crate) fn new(name: String) -> Self {
+        AssetGroup {
+            name,
+            progress: ProgressCounter::new(),
+            handles: Vec::new(),
+        }
+    }
+
+    /// Returns the name of the group.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the progress of all assets in the group.
+    pub fn progress(&self) -> &ProgressCounter {
+        &self.progress
+    }
+
+    /// Returns the completion of all assets in the group.
+    pub fn complete(&self) -> Completion {
+        self.progress.complete()
+    }
+
+    /// Returns `true` if all assets in the group have been imported without error.
+    pub fn is_complete(&self) -> bool {
+        self.progress.is_complete()
+    }
+
+    /// Removes all errors that occurred while loading the group and returns them.
+    pub fn errors(&self) -> Vec<AssetErrorMeta> {
+        self.progress.errors()
+    }
+
+    /// Returns the handle of the asset of type `A` with the given name.
+    ///
+    /// Returns an error if the group has no asset with this name, or if the asset is not of
+    /// type `A`.
+    pub fn handle<A>(&self, name: &str) -> Result<Handle<A>>
+    where
+        A: Asset,
+    {
+        let mut entries = self
+            .handles
+            .iter()
+            .filter(|&&(ref entry, _)| entry.name == name)
+            .peekable();
+        let asset = match entries.peek() {
+            Some(&&(ref entry, _)) => entry.asset.clone(),
+            None => {
+                return Err(format!("No asset {:?} in asset group {:?}", name, self.name).into())
+            }
+        };
+        entries
+            .filter_map(|&(_, ref handle)| handle.downcast_ref::<Handle<A>>())
+            .next()
+            .cloned()
+            .ok_or_else(|| {
+                format!(
+                    "Asset {:?} in asset group {:?} is of type {:?}, not {:?}",
+                    name,
+                    self.name,
+                    asset,
+                    A::NAME,
+                )
+                .into()
+            })
+    }
+
+    /// Returns the entries and handles of all assets of type `A` in the group.
+    pub fn handles<A>(&self) -> impl Iterator<Item = (&ManifestEntry, &Handle<A>)>
+    where
+        A: Asset,
+    {
+        self.handles.iter().filter_map(|(entry, handle)| {
+            handle
+                .downcast_ref::<Handle<A>>()
+                .map(|handle| (entry, handle))
+        })
+    }
+
+    /// Releases the handles of all assets in the group.
+    pub fn unload(self) {}
+}
+
+pub(crate) type LoadEntryFn = fn(
+    &Loader,
+    &ManifestEntry,
+    &mut ProgressCounter,
+    &Resources,
+) -> Result<Box<dyn Any + Send + Sync>>;
+
+/// Loads the asset of a `ManifestEntry`, which must be of type `A`.
+pub(crate) fn load_entry<A>(
+    loader: &Loader,
+    entry: &ManifestEntry,
+    progress: &mut ProgressCounter,
+    res: &Resources,
+) -> Result<Box<dyn Any + Send + Sync>>
+where
+    A: Asset,
+{
+    let storage = res
+        .try_fetch::<AssetStorage<A>>()
+        .ok_or_else(|| format!("No `AssetStorage` for assets of type {:?}", A::NAME))?;
+    let handle = loader.load_registered(
+        entry.name.as_str(),
+        entry.format.clone(),
+        entry.source.as_str(),
+        progress,
+        &storage,
+    );
+    Ok(Box::new(handle))
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::specs::prelude::VecStorage;
+
+    use super::*;
+    use crate::{loader::blocked_pool, MemorySource, ProcessingState, SimpleFormat};
+
+    struct Text(String);
+
+    impl Asset for Text {
+        const NAME: &'static str = "test::Text";
+        type Data = String;
+        type HandleStorage = VecStorage<Handle<Self>>;
+    }
+
+    #[derive(Clone)]
+    struct TextFormat;
+
+    impl SimpleFormat<Text> for TextFormat {
+        const NAME: &'static str = "TEXT";
+        type Options = ();
+
+        fn import(&self, bytes: Vec<u8>, _: ()) -> Result<String> {
+            Ok(String::from_utf8(bytes)?)
+        }
+    }
+
+    struct Bytes;
+
+    impl Asset for Bytes {
+        const NAME: &'static str = "test::Bytes";
+        type Data = Vec<u8>;
+        type HandleStorage = VecStorage<Handle<Self>>;
+    }
+
+    #[test]
+    fn parse_manifest() {
+        let manifest: AssetManifest = ron::de::from_str(
+            r#"(
+                groups: {
+                    "level_1": [
+                        (asset: "renderer::Mesh", name: "mesh/cube.obj"),
+                        (asset: "renderer::Texture", name: "texture/logo.png", format: "PNG"),
+                    ],
+                },
+            )"#,
+        )
+        .unwrap();
+
+        assert!(manifest.group("level_2").is_none());
+        let group = manifest.group("level_1").unwrap();
+        assert_eq!(2, group.len());
+        assert_eq!("renderer::Mesh", group[0].asset);
+        assert_eq!("", group[0].format);
+        assert_eq!("", group[0].source);
+        assert_eq!("PNG", group[1].format);
+        assert_eq!("texture/logo.png", group[1].name);
+    }
+
+    #[test]
+    fn load_group_tracks_all_of_its_assets() {
+        let source = MemorySource::new();
+        source.insert("greeting.text", "hello");
+        let (pool, _release) = blocked_pool();
+        let mut loader = Loader::new(".", pool.clone());
+        loader.add_source("memory", source);
+        loader.register_format::<Text, _>(TextFormat, (), &["text"]);
+        let mut manifest = AssetManifest::new();
+        for name in &["greeting.text", "missing.text"] {
+            let entry = ManifestEntry {
+                source: "memory".to_owned(),
+                ..ManifestEntry::new::<Text, _>(*name)
+            };
+            manifest.add("level_1", entry);
+        }
+        loader.set_manifest(manifest);
+        let mut res = Resources::new();
+        res.insert(AssetStorage::<Text>::new());
+
+        assert!(loader.load_group("level_2", &res).is_err());
+        let group = loader.load_group("level_1", &res).unwrap();
+        assert_eq!("level_1", group.name());
+        assert_eq!(2, group.progress().num_assets());
+        assert_eq!(Completion::Loading, group.complete());
+
+        loader.run_queued_loads();
+        res.fetch_mut::<AssetStorage<Text>>().process(
+            |text| Ok(ProcessingState::Loaded(Text(text))),
+            0,
+            &pool,
+            None,
+        );
+        assert_eq!(Completion::Failed, group.complete());
+        assert_eq!(1, group.progress().num_failed());
+        let errors = group.errors();
+        assert_eq!(1, errors.len());
+        assert_eq!("missing.text", errors[0].asset_name);
+        assert_eq!(Text::NAME, errors[0].asset_type_name);
+
+        let handle = group.handle::<Text>("greeting.text").unwrap();
+        assert_eq!(
+            Some("hello"),
+            res.fetch::<AssetStorage<Text>>()
+                .get(&handle)
+                .map(|text| &*text.0)
+        );
+        assert_eq!(2, group.handles::<Text>().count());
+        assert!(group.handle::<Text>("unknown.text").is_err());
+        assert!(group.handle::<Bytes>("greeting.text").is_err());
+
+        // Unloading the group releases its handles.
+        let weak = handle.downgrade();
+        drop(handle);
+        assert!(!weak.is_dead());
+        group.unload();
+        assert!(weak.is_dead());
+    }
+}
@@ -0,0 +1,381 @@
+//! Sidecar `.meta` files and the format registry used by `Loader::load_auto`.
+
+use std::{path::Path, sync::Arc};
+
+use ron::de::from_bytes;
+use serde::{Deserialize, Deserializer};
+
+use crate::{Asset, Format, FormatValue, Reload, Result, ResultExt, Source};
+
+/// The extension of sidecar files, which is appended to the full asset name.
+///
+/// The sidecar of `texture/logo.png` is `texture/logo.png.meta`.
+const META_EXTENSION: &str = "meta";
+
+/// The format part of a sidecar file, parsed before the format and its options type are known.
+#[derive(Deserialize)]
+struct MetaHeader {
+    #[serde(default)]
+    format: String,
+}
+
+/// The options part of a sidecar file, parsed once the format has been picked.
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "O: Deserialize<'de>"))]
+struct MetaOptions<O> {
+    #[serde(default = "none", deserialize_with = "some")]
+    options: Option<O>,
+}
+
+fn none<O>() -> Option<O> {
+    None
+}
+
+fn some<'de, D, O>(deserializer: D) -> ::std::result::Result<Option<O>, D::Error>
+where
+    D: Deserializer<'de>,
+    O: Deserialize<'de>,
+{
+    O::deserialize(deserializer).map(Some)
+}
+
+type ImportFn<A> =
+    dyn Fn(String, Arc<dyn Source>, Option<&[u8]>, bool) -> Result<FormatValue<A>> + Send + Sync;
+
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""))]
+struct RegisteredFormat<A: Asset> {
+    name: &'static str,
+    extensions: Vec<String>,
+    import: Arc<ImportFn<A>>,
+}
+
+/// The formats `Loader::load_auto` can pick from for assets of type `A`.
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""), Default(bound = ""))]
+pub(crate) struct FormatRegistry<A: Asset> {
+    formats: Vec<RegisteredFormat<A>>,
+}
+
+impl<A: Asset> FormatRegistry<A> {
+    /// Registers a format, replacing any format with the same name.
+    pub fn register<F>(&mut self, format: F, options: F::Options, extensions: &[&str])
+    where
+        F: Format<A> + Sync,
+        F::Options: Clone + Sync + for<'a> Deserialize<'a>,
+    {
+        let import = move |name, source, meta: Option<&[u8]>, create_reload| {
+            let options = match meta {
+                Some(bytes) => from_bytes::<MetaOptions<F::Options>>(bytes)
+                    .chain_err(|| format!("Failed parsing options of format {:?}", F::NAME))?
+                    .options
+                    .unwrap_or_else(|| options.clone()),
+                None => options.clone(),
+            };
+            format.import(name, source, options, create_reload)
+        };
+
+        self.formats.retain(|f| f.name != F::NAME);
+        self.formats.push(RegisteredFormat {
+            name: F::NAME,
+            extensions: extensions.iter().map(|e| e.to_lowercase()).collect(),
+            import: Arc::new(import),
+        });
+    }
+
+    /// Imports an asset with the given format, or if `format` is empty, with the format declared
+    /// in its sidecar file or the format registered for its extension.
+    ///
+    /// The options of the sidecar file are ignored if it declares another format than the given
+    /// one.
+    ///
+    /// The reload object returned with the data also reloads the asset when its sidecar file is
+    /// added, changed or removed.
+    pub fn import(
+        registry: &Arc<Self>,
+        name: String,
+        format: &str,
+        source: Arc<dyn Source>,
+        create_reload: bool,
+    ) -> Result<FormatValue<A>> {
+        let meta_name = format!("{}.{}", name, META_EXTENSION);
+        let meta_modified = source.modified(&meta_name).ok();
+        // A missing sidecar is not an error, the defaults are used instead.
+        let meta = source.load(&meta_name).ok();
+        let declared = match meta {
+            Some(ref bytes) => {
+                from_bytes::<MetaHeader>(bytes)
+                    .chain_err(|| format!("Failed parsing sidecar file of {:?}", name))?
+                    .format
+            }
+            None => String::new(),
+        };
+        let requested = format;
+        let format = if requested.is_empty() {
+            declared.clone()
+        } else {
+            requested.to_owned()
+        };
+        // The options of a sidecar declaring another format are written for that format.
+        let meta = if declared.is_empty() || declared == format {
+            meta
+        } else {
+            None
+        };
+
+        let registered = if format.is_empty() {
+            let extension = Path::new(&name)
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(str::to_lowercase)
+                .unwrap_or_default();
+            registry
+                .formats
+                .iter()
+                .find(|f| f.extensions.contains(&extension))
+                .ok_or_else(|| {
+                    format!(
+                        "No format registered for assets of type {:?} with extension {:?}",
+                        A::NAME,
+                        extension,
+                    )
+                })?
+        } else {
+            registry
+                .formats
+                .iter()
+                .find(|f| f.name == format)
+                .ok_or_else(|| {
+                    format!(
+                        "No format {:?} registered for assets of type {:?}",
+                        format,
+                        A::NAME,
+                    )
+                })?
+        };
+
+        let mut value = (registered.import)(
+            name.clone(),
+            source.clone(),
+            meta.as_ref().map(Vec::as_slice),
+            create_reload,
+        )?;
+        if create_reload {
+            value.reload = Some(Box::new(MetaReload {
+                registry: registry.clone(),
+                name,
+                format: requested.to_owned(),
+                format_name: registered.name,
+                source,
+                meta_name,
+                meta_modified,
+                inner: value.reload.take(),
+            }));
+        }
+
+        Ok(value)
+    }
+}
+
+/// Reloads an asset loaded with `Loader::load_auto` when either the asset or its sidecar file
+/// changes, picking the format and options again.
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""))]
+struct MetaReload<A: Asset> {
+    registry: Arc<FormatRegistry<A>>,
+    name: String,
+    format: String,
+    format_name: &'static str,
+    source: Arc<dyn Source>,
+    meta_name: String,
+    meta_modified: Option<u64>,
+    inner: Option<Box<dyn Reload<A>>>,
+}
+
+impl<A: Asset> Reload<A> for MetaReload<A> {
+    fn needs_reload(&self) -> bool {
+        self.inner
+            .as_ref()
+            .map_or(false, |inner| inner.needs_reload())
+            || self.source.modified(&self.meta_name).ok() != self.meta_modified
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn format(&self) -> &'static str {
+        self.format_name
+    }
+
+    fn reload(self: Box<Self>) -> Result<FormatValue<A>> {
+        FormatRegistry::import(&self.registry, self.name, &self.format, self.source, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::specs::prelude::VecStorage;
+
+    use super::*;
+    use crate::{Handle, MemorySource, SimpleFormat};
+
+    struct Text(String);
+
+    impl Asset for Text {
+        const NAME: &'static str = "test::Text";
+        type Data = String;
+        type HandleStorage = VecStorage<Handle<Self>>;
+    }
+
+    #[derive(Clone, Default, Deserialize)]
+    struct TextOptions {
+        prefix: String,
+    }
+
+    #[derive(Clone)]
+    struct TextFormat;
+
+    impl SimpleFormat<Text> for TextFormat {
+        const NAME: &'static str = "TEXT";
+        type Options = TextOptions;
+
+        fn import(&self, bytes: Vec<u8>, options: TextOptions) -> Result<String> {
+            Ok(options.prefix + &String::from_utf8(bytes)?)
+        }
+    }
+
+    #[derive(Clone)]
+    struct ReversedFormat;
+
+    impl SimpleFormat<Text> for ReversedFormat {
+        const NAME: &'static str = "REVERSED";
+        type Options = TextOptions;
+
+        fn import(&self, bytes: Vec<u8>, options: TextOptions) -> Result<String> {
+            Ok(options.prefix + &String::from_utf8(bytes)?.chars().rev().collect::<String>())
+        }
+    }
+
+    fn registry() -> Arc<FormatRegistry<Text>> {
+        let mut registry = FormatRegistry::default();
+        registry.register(TextFormat, TextOptions::default(), &["txt"]);
+        registry.register(ReversedFormat, TextOptions::default(), &["rev", "REV2"]);
+        Arc::new(registry)
+    }
+
+    fn import(registry: &Arc<FormatRegistry<Text>>, name: &str, source: &MemorySource) -> String {
+        FormatRegistry::import(
+            registry,
+            name.to_owned(),
+            "",
+            Arc::new(source.clone()),
+            false,
+        )
+        .unwrap()
+        .data
+    }
+
+    #[test]
+    fn format_is_picked_by_extension() {
+        let registry = registry();
+        let source = MemorySource::new();
+        source.insert("a.txt", "abc");
+        source.insert("b.rev", "abc");
+        source.insert("c.Rev2", "abc");
+        source.insert("d.png", "abc");
+
+        assert_eq!("abc", import(&registry, "a.txt", &source));
+        assert_eq!("cba", import(&registry, "b.rev", &source));
+        assert_eq!("cba", import(&registry, "c.Rev2", &source));
+        assert!(
+            FormatRegistry::import(&registry, "d.png".into(), "", Arc::new(source), false).is_err()
+        );
+    }
+
+    #[test]
+    fn sidecar_picks_format_and_options() {
+        let registry = registry();
+        let source = MemorySource::new();
+        source.insert("a.txt", "abc");
+        source.insert(
+            "a.txt.meta",
+            r#"(format: "REVERSED", options: (prefix: "> "))"#,
+        );
+        source.insert("b.txt", "abc");
+        source.insert("b.txt.meta", r#"(options: (prefix: "- "))"#);
+        source.insert("c.txt", "abc");
+        source.insert("c.txt.meta", r#"(format: "REVERSED")"#);
+
+        assert_eq!("> cba", import(&registry, "a.txt", &source));
+        assert_eq!("- abc", import(&registry, "b.txt", &source));
+        assert_eq!("cba", import(&registry, "c.txt", &source));
+    }
+
+    #[test]
+    fn requested_format_takes_precedence_over_sidecar() {
+        let registry = registry();
+        let source = MemorySource::new();
+        source.insert("a.txt", "abc");
+        source.insert(
+            "a.txt.meta",
+            r#"(format: "REVERSED", options: (prefix: "> "))"#,
+        );
+        source.insert("b.txt", "abc");
+        source.insert("b.txt.meta", r#"(format: "TEXT", options: (prefix: "> "))"#);
+        source.insert("c.txt", "abc");
+        source.insert("c.txt.meta", r#"(options: (prefix: "> "))"#);
+        let import = |name: &str| {
+            FormatRegistry::import(
+                &registry,
+                name.into(),
+                "TEXT",
+                Arc::new(source.clone()),
+                false,
+            )
+            .unwrap()
+            .data
+        };
+
+        // The options are written for the format declared in the sidecar.
+        assert_eq!("abc", import("a.txt"));
+        assert_eq!("> abc", import("b.txt"));
+        assert_eq!("> abc", import("c.txt"));
+    }
+
+    #[test]
+    fn editing_the_sidecar_reloads_the_asset() {
+        let registry = registry();
+        let source = MemorySource::new();
+        source.insert("a.txt", "abc");
+        let reload = |source: &MemorySource| {
+            FormatRegistry::import(
+                &registry,
+                "a.txt".into(),
+                "",
+                Arc::new(source.clone()),
+                true,
+            )
+            .unwrap()
+            .reload
+            .expect("No reload object was created")
+        };
+
+        let added = reload(&source);
+        assert!(!added.needs_reload());
+        source.insert("a.txt.meta", r#"(format: "REVERSED")"#);
+        assert!(added.needs_reload());
+        assert_eq!("cba", added.reload().unwrap().data);
+
+        let changed = reload(&source);
+        assert!(!changed.needs_reload());
+        source.insert("a.txt.meta", r#"(options: (prefix: "> "))"#);
+        assert!(changed.needs_reload());
+        assert_eq!("> abc", changed.reload().unwrap().data);
+
+        let removed = reload(&source);
+        source.remove("a.txt.meta");
+        assert!(removed.needs_reload());
+        assert_eq!("abc", removed.reload().unwrap().data);
+    }
+}
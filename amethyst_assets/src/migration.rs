@@ -0,0 +1,292 @@
+//! Versioned documents, upgraded by migrations before they are deserialized.
+
+use std::sync::Arc;
+
+use fnv::FnvHashMap;
+use serde::de::{Deserialize, DeserializeOwned, Deserializer, IgnoredAny};
+
+use crate::{Asset, Result, ResultExt, SimpleFormat};
+
+/// A self-describing format whose documents can be parsed into generic values,
+/// so they can be migrated by `Versioned`.
+pub trait MigrationFormat: Clone + Send + Sync + 'static {
+    /// The `Format::NAME` of this format wrapped in `Versioned`.
+    const VERSIONED_NAME: &'static str;
+
+    /// The generic value type documents are migrated as, e.g. `ron::Value`.
+    type Value: DeserializeOwned;
+
+    /// Deserializes a complete document.
+    fn deserialize<D>(&self, bytes: &[u8]) -> Result<D>
+    where
+        D: DeserializeOwned;
+
+    /// Deserializes data from a value.
+    fn from_value<D>(&self, value: Self::Value) -> Result<D>
+    where
+        D: DeserializeOwned;
+}
+
+/// Migrations upgrading older documents to the current version, one version at a time.
+///
+/// ### Example:
+///
+/// ```rust,ignore
+/// let migrations = Migrations::new(2)
+///     .with(0, |value: serde_json::Value| Ok(add_defaults(value)))
+///     .with(1, |value| Ok(rename_field(value, "hp", "health")));
+/// ```
+pub struct Migrations<V> {
+    version: u32,
+    steps: FnvHashMap<u32, Box<dyn Fn(V) -> Result<V> + Send + Sync>>,
+}
+
+impl<V> Migrations<V> {
+    /// Creates an empty set of migrations, for documents whose current version is `version`.
+    pub fn new(version: u32) -> Self {
+        Migrations {
+            version,
+            steps: Default::default(),
+        }
+    }
+
+    /// Adds a migration upgrading documents of version `from` to version `from + 1`.
+    pub fn with<M>(mut self, from: u32, migration: M) -> Self
+    where
+        M: Fn(V) -> Result<V> + Send + Sync + 'static,
+    {
+        self.add(from, migration);
+        self
+    }
+
+    /// Adds a migration upgrading documents of version `from` to version `from + 1`,
+    /// replacing any migration previously added for `from`.
+    pub fn add<M>(&mut self, from: u32, migration: M)
+    where
+        M: Fn(V) -> Result<V> + Send + Sync + 'static,
+    {
+        self.steps.insert(from, Box::new(migration));
+    }
+
+    /// Returns the current version.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Upgrades a document of version `from` to the current version,
+    /// applying all migrations in between in order.
+    pub fn migrate(&self, mut value: V, from: u32) -> Result<V> {
+        if from > self.version {
+            return Err(format!(
+                "Document version {} is newer than the supported version {}",
+                from, self.version,
+            )
+            .into());
+        }
+        for version in from..self.version {
+            let migration = self.steps.get(&version).ok_or_else(|| {
+                format!("No migration from version {} to {}", version, version + 1)
+            })?;
+            value = migration(value).chain_err(|| {
+                format!(
+                    "Failed migrating from version {} to {}",
+                    version,
+                    version + 1
+                )
+            })?;
+        }
+        Ok(value)
+    }
+}
+
+#[derive(Deserialize)]
+struct VersionHeader {
+    #[serde(default)]
+    version: u32,
+    #[serde(default)]
+    data: Present,
+}
+
+/// Whether a field is present, without parsing its value.
+#[derive(Default)]
+struct Present(bool);
+
+impl<'de> Deserialize<'de> for Present {
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        IgnoredAny::deserialize(deserializer).map(|_| Present(true))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "D: DeserializeOwned"))]
+struct VersionedDocument<D> {
+    #[serde(default)]
+    version: u32,
+    data: D,
+}
+
+/// Format wrapper for documents with a version header, upgraded by `Migrations`.
+///
+/// Documents consist of a version and the actual data, e.g. in RON:
+///
+/// ```ron
+/// (
+///     version: 2,
+///     data: (
+///         health: 100,
+///     ),
+/// )
+/// ```
+///
+/// A missing version is treated as version `0`. Documents without a `data` field are not wrapped
+/// at all, the whole document is the data of version `0`, so existing files can be migrated
+/// without editing them first. Documents of the current version are deserialized directly. Older
+/// documents are parsed into a `MigrationFormat::Value`, upgraded by the migrations and then
+/// deserialized.
+///
+/// Note that `ron::Value` does not keep struct names and enum variant names, so RON documents
+/// whose data contains enums other than `Option` can not be migrated. This includes most
+/// `Prefab` documents, use `JsonFormat`, whose values keep variant names, for those.
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""))]
+pub struct Versioned<F: MigrationFormat> {
+    format: F,
+    migrations: Arc<Migrations<F::Value>>,
+}
+
+impl<F> Versioned<F>
+where
+    F: MigrationFormat,
+{
+    /// Wraps a format, upgrading documents with the given migrations.
+    pub fn new(format: F, migrations: Migrations<F::Value>) -> Self {
+        Versioned {
+            format,
+            migrations: Arc::new(migrations),
+        }
+    }
+
+    /// Returns the migrations of this format.
+    pub fn migrations(&self) -> &Migrations<F::Value> {
+        &self.migrations
+    }
+
+    /// Deserializes a versioned document, migrating it if it is older than the current version.
+    pub fn deserialize<D>(&self, bytes: &[u8]) -> Result<D>
+    where
+        D: DeserializeOwned,
+    {
+        let header = self
+            .format
+            .deserialize::<VersionHeader>(bytes)
+            .ok()
+            .filter(|header| header.data.0);
+        let (value, version) = match header {
+            Some(ref header) if header.version == self.migrations.version() => {
+                let document: VersionedDocument<D> = self.format.deserialize(bytes)?;
+                return Ok(document.data);
+            }
+            Some(_) => {
+                let document: VersionedDocument<F::Value> = self.format.deserialize(bytes)?;
+                (document.data, document.version)
+            }
+            None if self.migrations.version() == 0 => return self.format.deserialize(bytes),
+            None => (self.format.deserialize(bytes)?, 0),
+        };
+        let value = self.migrations.migrate(value, version)?;
+        self.format.from_value(value)
+    }
+}
+
+impl<A, F> SimpleFormat<A> for Versioned<F>
+where
+    A: Asset,
+    A::Data: DeserializeOwned,
+    F: MigrationFormat,
+    F::Value: 'static,
+{
+    const NAME: &'static str = F::VERSIONED_NAME;
+    type Options = ();
+
+    fn import(&self, bytes: Vec<u8>, _: ()) -> Result<A::Data> {
+        self.deserialize(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ron::value::Value;
+
+    use crate::RonFormat;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Stats {
+        health: u32,
+    }
+
+    fn rename(value: Value, from: &str, to: &str) -> Result<Value> {
+        match value {
+            Value::Map(mut map) => {
+                let field = map
+                    .remove(&Value::String(from.to_owned()))
+                    .ok_or("Missing field")?;
+                map.insert(Value::String(to.to_owned()), field);
+                Ok(Value::Map(map))
+            }
+            _ => Err("Expected a struct".into()),
+        }
+    }
+
+    fn migrations() -> Migrations<Value> {
+        Migrations::new(2)
+            .with(0, |value| rename(value, "hp", "hit_points"))
+            .with(1, |value| rename(value, "hit_points", "health"))
+    }
+
+    #[test]
+    fn migrate_value() {
+        let value: Value = ron::de::from_str("(hp: 5)").unwrap();
+        let migrated = migrations().migrate(value, 0).unwrap();
+        let expected: Value = ron::de::from_str("(health: 5)").unwrap();
+        assert_eq!(expected, migrated);
+    }
+
+    #[test]
+    fn migrate_rejects_newer_versions() {
+        let value: Value = ron::de::from_str("(health: 5)").unwrap();
+        assert!(migrations().migrate(value, 3).is_err());
+    }
+
+    #[test]
+    fn deserialize_versioned() {
+        let format = Versioned::new(RonFormat, migrations());
+        let current: Stats = format
+            .deserialize(b"(version: 2, data: (health: 5))")
+            .unwrap();
+        let old: Stats = format
+            .deserialize(b"(version: 1, data: (hit_points: 5))")
+            .unwrap();
+        let unversioned: Stats = format.deserialize(b"(data: (hp: 5))").unwrap();
+        assert_eq!(Stats { health: 5 }, current);
+        assert_eq!(Stats { health: 5 }, old);
+        assert_eq!(Stats { health: 5 }, unversioned);
+    }
+
+    #[test]
+    fn deserialize_unwrapped() {
+        let format = Versioned::new(RonFormat, migrations());
+        let unwrapped: Stats = format.deserialize(b"(hp: 5)").unwrap();
+        let named: Stats = format.deserialize(b"Stats(hp: 5)").unwrap();
+        assert_eq!(Stats { health: 5 }, unwrapped);
+        assert_eq!(Stats { health: 5 }, named);
+
+        let format = Versioned::new(RonFormat, Migrations::new(0));
+        let current: Stats = format.deserialize(b"Stats(health: 5)").unwrap();
+        assert_eq!(Stats { health: 5 }, current);
+    }
+}
@@ -36,6 +36,36 @@ where
             Ok(false)
         }
     }
+
+    fn extract_from_entity(
+        entity: Entity,
+        system_data: &Self::SystemData,
+        entities: &[Entity],
+    ) -> Result<Option<Self>, PrefabError> {
+        Ok(T::extract_from_entity(entity, system_data, entities)?.map(Some))
+    }
+
+    fn add_changed_to_entity(
+        &self,
+        inserted: &Self,
+        entity: Entity,
+        system_data: &mut Self::SystemData,
+        entities: &[Entity],
+    ) -> Result<(), PrefabError> {
+        match (self, inserted) {
+            (Some(prefab), Some(inserted)) => {
+                prefab.add_changed_to_entity(inserted, entity, system_data, entities)
+            }
+            (Some(prefab), None) => prefab
+                .add_to_entity(entity, system_data, entities)
+                .map(|_| ()),
+            (None, _) => Ok(()),
+        }
+    }
+
+    fn missing() -> Option<Self> {
+        Some(None)
+    }
 }
 
 impl<'a> PrefabData<'a> for GlobalTransform {
@@ -50,6 +80,27 @@ impl<'a> PrefabData<'a> for GlobalTransform {
     ) -> Result<(), PrefabError> {
         storage.insert(entity, self.clone()).map(|_| ())
     }
+
+    fn extract_from_entity(
+        entity: Entity,
+        storage: &Self::SystemData,
+        _: &[Entity],
+    ) -> Result<Option<Self>, PrefabError> {
+        Ok(storage.get(entity).cloned())
+    }
+
+    fn add_changed_to_entity(
+        &self,
+        inserted: &Self,
+        entity: Entity,
+        storage: &mut Self::SystemData,
+        entities: &[Entity],
+    ) -> Result<(), PrefabError> {
+        if self.0 != inserted.0 {
+            self.add_to_entity(entity, storage, entities)?;
+        }
+        Ok(())
+    }
 }
 
 impl<'a> PrefabData<'a> for Transform {
@@ -68,6 +119,27 @@ impl<'a> PrefabData<'a> for Transform {
         storages.1.insert(entity, GlobalTransform::default())?;
         storages.0.insert(entity, self.clone()).map(|_| ())
     }
+
+    fn extract_from_entity(
+        entity: Entity,
+        storages: &Self::SystemData,
+        _: &[Entity],
+    ) -> Result<Option<Self>, PrefabError> {
+        Ok(storages.0.get(entity).cloned())
+    }
+
+    fn add_changed_to_entity(
+        &self,
+        inserted: &Self,
+        entity: Entity,
+        storages: &mut Self::SystemData,
+        entities: &[Entity],
+    ) -> Result<(), PrefabError> {
+        if self != inserted {
+            self.add_to_entity(entity, storages, entities)?;
+        }
+        Ok(())
+    }
 }
 
 impl<'a> PrefabData<'a> for Named {
@@ -82,6 +154,27 @@ impl<'a> PrefabData<'a> for Named {
     ) -> Result<(), PrefabError> {
         storages.0.insert(entity, self.clone()).map(|_| ())
     }
+
+    fn extract_from_entity(
+        entity: Entity,
+        storages: &Self::SystemData,
+        _: &[Entity],
+    ) -> Result<Option<Self>, PrefabError> {
+        Ok(storages.0.get(entity).cloned())
+    }
+
+    fn add_changed_to_entity(
+        &self,
+        inserted: &Self,
+        entity: Entity,
+        storages: &mut Self::SystemData,
+        entities: &[Entity],
+    ) -> Result<(), PrefabError> {
+        if self.name != inserted.name {
+            self.add_to_entity(entity, storages, entities)?;
+        }
+        Ok(())
+    }
 }
 
 macro_rules! impl_data {
@@ -123,6 +216,51 @@ macro_rules! impl_data {
                 )*
                 Ok(ret)
             }
+
+            fn extract_from_entity(
+                entity: Entity,
+                system_data: &Self::SystemData,
+                entities: &[Entity],
+            ) -> Result<Option<Self>, PrefabError> {
+                #![allow(unused_variables)]
+                let extracted = (
+                    $(
+                        $ty::extract_from_entity(entity, &system_data.$i, entities)?,
+                    )*
+                );
+                if [$( extracted.$i.is_none() ),*].iter().all(|none| *none) {
+                    return Ok(None);
+                }
+                Ok(Some((
+                    $(
+                        match extracted.$i.or_else($ty::missing) {
+                            Some(data) => data,
+                            None => {
+                                return Err(Self::missing_field_error(entity, stringify!($i)));
+                            }
+                        },
+                    )*
+                )))
+            }
+
+            fn add_changed_to_entity(
+                &self,
+                inserted: &Self,
+                entity: Entity,
+                system_data: &mut Self::SystemData,
+                entities: &[Entity],
+            ) -> Result<(), PrefabError> {
+                #![allow(unused_variables)]
+                $(
+                    self.$i.add_changed_to_entity(
+                        &inserted.$i,
+                        entity,
+                        &mut system_data.$i,
+                        entities,
+                    )?;
+                )*
+                Ok(())
+            }
         }
     };
 }
@@ -1,7 +1,16 @@
 use std::marker::PhantomData;
 
-use amethyst_core::specs::prelude::{
-    Component, DenseVecStorage, Entity, FlaggedStorage, Read, ReadExpect, SystemData, WriteStorage,
+use fnv::FnvHashMap;
+
+use amethyst_core::{
+    specs::{
+        error::BoxedErr,
+        prelude::{
+            Component, DenseVecStorage, Entities, Entity, FlaggedStorage, Join, Read, ReadExpect,
+            ReadStorage, SystemData, WriteStorage,
+        },
+    },
+    Parent,
 };
 
 use crate::{Asset, AssetStorage, Format, Handle, Loader, Progress, ProgressCounter};
@@ -62,6 +71,82 @@ pub trait PrefabData<'a> {
     ) -> Result<bool, PrefabError> {
         Ok(false)
     }
+
+    /// Read the data for this prefab back from the given `Entity`.
+    ///
+    /// This is the reverse of `add_to_entity`, and is used to build a `Prefab` from live entities,
+    /// see `Prefab::extract`. The default implementation extracts nothing, implementations which
+    /// can not extract their data should return an error for entities that have it, so it is not
+    /// silently left out of the prefab.
+    ///
+    /// ### Parameters:
+    ///
+    /// - `entity`: `Entity` to read components from
+    /// - `system_data`: `SystemData` needed to read the components
+    /// - `entities`: All entities that are part of the extracted prefab, in prefab order, for
+    ///               mapping links to other entities back to prefab indices.
+    ///
+    /// ### Returns
+    ///
+    /// - `Err(error)` - if an `Error` occurs, or the `Entity` has the data but it can not be
+    ///   extracted
+    /// - `Ok(None)` - if the `Entity` does not have the data
+    /// - `Ok(Some(data))` - the extracted data
+    fn extract_from_entity(
+        _entity: Entity,
+        _system_data: &Self::SystemData,
+        _entities: &[Entity],
+    ) -> Result<Option<Self>, PrefabError>
+    where
+        Self: Sized,
+    {
+        Ok(None)
+    }
+
+    /// Add the parts of this data, which was extracted from a live `Entity`, that differ from
+    /// `inserted`, the data extracted right after the prefab added it. Used by
+    /// `PrefabReloadPolicy::PreserveExisting` to keep components modified at runtime.
+    ///
+    /// The default implementation can not tell what changed and adds all of the data.
+    #[doc(hidden)]
+    fn add_changed_to_entity(
+        &self,
+        _inserted: &Self,
+        entity: Entity,
+        system_data: &mut Self::SystemData,
+        entities: &[Entity],
+    ) -> Result<(), PrefabError>
+    where
+        Self: Sized,
+    {
+        self.add_to_entity(entity, system_data, entities)
+            .map(|_| ())
+    }
+
+    /// The data of an aggregate field for which `extract_from_entity` found nothing, or `None` if
+    /// the field can not be left out. Used by `#[derive(PrefabData)]`, only `Option<T>` can be
+    /// left out.
+    #[doc(hidden)]
+    fn missing() -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// The error for an `Entity` which has only part of the data of an aggregate, returned by
+    /// `extract_from_entity` of `#[derive(PrefabData)]` if a field which can not be left out is
+    /// missing.
+    #[doc(hidden)]
+    fn missing_field_error(entity: Entity, field: &'static str) -> PrefabError
+    where
+        Self: Sized,
+    {
+        PrefabError::Custom(BoxedErr(Box::from(format!(
+            "Can not extract the prefab data of entity {:?}, it has no data for field `{}`",
+            entity, field,
+        ))))
+    }
 }
 
 /// Main `Prefab` structure, containing all data loaded in a single prefab.
@@ -90,6 +175,49 @@ pub trait PrefabData<'a> {
 /// }
 /// ```
 ///
+/// ### Inheritance and nesting:
+///
+/// A prefab can extend a `base` prefab, which is loaded from the given file. The entities of the
+/// base prefab are created first, and the entity at index `i` of the extending prefab is applied
+/// on top of the entity at index `i` of the base prefab, so only the components that differ need
+/// to be declared. Entities past the end of the base prefab are created as usual, and a `parent`
+/// overrides the parent declared by the base prefab.
+///
+/// Overrides are per component, they are not merged field by field: a declared component
+/// is added to the entity like any other, replacing the one of the base prefab. Fields left out
+/// are not taken from the base prefab, so a component must be declared in full, as the
+/// `transform` below.
+///
+/// Any `PrefabEntity` can also instance another prefab file, which is then created with that
+/// entity as its main `Entity`. The data of the `PrefabEntity` itself is applied last, so it
+/// overrides the data of the main entity of the nested prefab.
+///
+/// Referenced prefabs are loaded with `Loader::load_auto`, so a format for `Prefab<T>` must be
+/// registered with `Loader::register_format`. They are resolved by the `PrefabLoaderSystem`
+/// before the prefab is instantiated.
+///
+/// ```ron
+/// #![enable(implicit_some)]
+/// Prefab (
+///     base: "prefab/goblin.ron",
+///     entities: [
+///         (
+///             data: (
+///                 // Replaces the whole transform of the goblin.
+///                 transform: (
+///                     translation: (0.0, 0.5, 0.0),
+///                     scale: (1.2, 1.2, 1.2),
+///                 ),
+///             ),
+///         ),
+///         (
+///             parent: 0,
+///             prefab: "prefab/bow.ron",
+///         ),
+///     ],
+/// )
+/// ```
+///
 /// ### Type parameters:
 ///
 /// - `T`: `PrefabData`
@@ -97,9 +225,15 @@ pub trait PrefabData<'a> {
 pub struct Prefab<T> {
     #[serde(skip)]
     tag: Option<u64>,
+    #[serde(default)]
+    base: Option<String>,
     entities: Vec<PrefabEntity<T>>,
     #[serde(skip)]
     counter: Option<ProgressCounter>,
+    #[serde(skip)]
+    references: Vec<(String, Handle<Prefab<T>>)>,
+    #[serde(skip)]
+    reference_counter: Option<ProgressCounter>,
 }
 
 /// Prefab data container for a single entity
@@ -111,6 +245,7 @@ pub struct Prefab<T> {
 #[serde(default)]
 pub struct PrefabEntity<T> {
     parent: Option<usize>,
+    prefab: Option<String>,
     data: Option<T>,
 }
 
@@ -123,7 +258,24 @@ impl<T> Default for PrefabEntity<T> {
 impl<T> PrefabEntity<T> {
     /// New prefab entity
     pub fn new(parent: Option<usize>, data: Option<T>) -> Self {
-        PrefabEntity { parent, data }
+        PrefabEntity {
+            parent,
+            prefab: None,
+            data,
+        }
+    }
+
+    /// Set the prefab file instanced at this entity
+    pub fn set_prefab<N>(&mut self, name: N)
+    where
+        N: Into<String>,
+    {
+        self.prefab = Some(name.into());
+    }
+
+    /// Get the prefab file instanced at this entity
+    pub fn prefab(&self) -> Option<&str> {
+        self.prefab.as_ref().map(String::as_str)
     }
 
     /// Set parent index
@@ -178,8 +330,11 @@ impl<T> Prefab<T> {
     pub fn new() -> Self {
         Prefab {
             tag: None,
+            base: None,
             entities: vec![PrefabEntity::default()],
             counter: None,
+            references: Vec::new(),
+            reference_counter: None,
         }
     }
 
@@ -187,11 +342,29 @@ impl<T> Prefab<T> {
     pub fn new_main(data: T) -> Self {
         Prefab {
             tag: None,
+            base: None,
             entities: vec![PrefabEntity::new(None, Some(data))],
             counter: None,
+            references: Vec::new(),
+            reference_counter: None,
         }
     }
 
+    /// Set the prefab file this prefab extends
+    ///
+    /// Components declared by this prefab replace the whole components of the base prefab.
+    pub fn set_base<N>(&mut self, name: N)
+    where
+        N: Into<String>,
+    {
+        self.base = Some(name.into());
+    }
+
+    /// Get the prefab file this prefab extends
+    pub fn base(&self) -> Option<&str> {
+        self.base.as_ref().map(String::as_str)
+    }
+
     /// Set main `Entity` data
     pub fn main(&mut self, data: Option<T>) {
         self.entities[0].data = data;
@@ -272,6 +445,105 @@ impl<T> Prefab<T> {
         self.counter = Some(progress);
         Ok(ret)
     }
+
+    /// Build a prefab from a live entity hierarchy.
+    ///
+    /// `root` becomes the main entity of the prefab, and every entity with a chain of `Parent`
+    /// links leading to `root` is added to the prefab, with the data read by
+    /// `PrefabData::extract_from_entity`. Entities are added breadth first, so parents always
+    /// precede their children.
+    ///
+    /// The resulting prefab can be serialized, for example to save a level edited at runtime,
+    /// as long as all extracted data can be serialized.
+    pub fn extract<'a>(
+        root: Entity,
+        entities: &Entities<'a>,
+        parents: &ReadStorage<'a, Parent>,
+        system_data: &<T as PrefabData<'a>>::SystemData,
+    ) -> Result<Self, PrefabError>
+    where
+        T: PrefabData<'a>,
+    {
+        let mut children = FnvHashMap::<Entity, Vec<Entity>>::default();
+        for (entity, parent) in (&**entities, parents).join() {
+            children
+                .entry(parent.entity)
+                .or_insert_with(Vec::new)
+                .push(entity);
+        }
+
+        let mut hierarchy = vec![root];
+        let mut parent_indices = vec![None];
+        let mut next = 0;
+        while next < hierarchy.len() {
+            // Removing the children also guards against cycles in the `Parent` links.
+            if let Some(children) = children.remove(&hierarchy[next]) {
+                for child in children {
+                    hierarchy.push(child);
+                    parent_indices.push(Some(next));
+                }
+            }
+            next += 1;
+        }
+
+        let mut prefab = Prefab::new();
+        prefab.entities = hierarchy
+            .iter()
+            .zip(parent_indices)
+            .map(|(entity, parent)| {
+                let data = T::extract_from_entity(*entity, system_data, &hierarchy)?;
+                Ok(PrefabEntity::new(parent, data))
+            })
+            .collect::<Result<_, PrefabError>>()?;
+        Ok(prefab)
+    }
+
+    /// Names of all prefab files referenced by this prefab, either as base or as nested prefab.
+    fn reference_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        let nested = self.entities.iter().filter_map(|e| e.prefab.as_ref());
+        for name in self.base.iter().chain(nested) {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        names
+    }
+
+    /// Get the handle of a referenced prefab file, if loading it has been triggered
+    fn reference(&self, name: &str) -> Option<&Handle<Prefab<T>>> {
+        self.references
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, handle)| handle)
+    }
+}
+
+/// Decides how `PrefabLoaderSystem` updates live prefab instances when a prefab is hot-reloaded.
+///
+/// Child entities are created or deleted to match the reloaded prefab with every policy
+/// except `Ignore`. Components that are no longer part of the prefab are not removed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrefabReloadPolicy {
+    /// Live instances are left untouched.
+    Ignore,
+    /// The data of the reloaded prefab is applied to live instances,
+    /// overwriting any components modified at runtime.
+    Overwrite,
+    /// Components modified at runtime keep their runtime values, all other components are
+    /// updated with the data of the reloaded prefab.
+    ///
+    /// The data is compared with what the prefab added when the instance was created, which
+    /// relies on `PrefabData::extract_from_entity`. Data that can not be compared, such as
+    /// `#[prefab(Component)]` fields of derived aggregates, always keeps its runtime value, and
+    /// entities whose data can not be extracted are overwritten.
+    PreserveExisting,
+}
+
+impl Default for PrefabReloadPolicy {
+    fn default() -> Self {
+        PrefabReloadPolicy::Overwrite
+    }
 }
 
 /// Tag placed on entities created by the prefab system.
@@ -317,6 +589,9 @@ where
 ///
 /// Will add a `Handle<A>` to the `Entity`
 ///
+/// Every `File` loads its asset with `Loader::load`, so entities referencing the same file get
+/// separate assets. Use `Loader::load_shared` in your own `PrefabData` to share them.
+///
 /// ### Type parameters:
 ///
 /// - `A`: `Asset`,
@@ -368,13 +643,16 @@ where
         system_data: &mut Self::SystemData,
     ) -> Result<bool, PrefabError> {
         let handle = if let AssetPrefab::File(ref name, ref format, ref options) = *self {
-            Some(system_data.0.load(
+            let handle = system_data.0.load(
                 name.as_ref(),
                 format.clone(),
                 options.clone(),
                 progress,
                 &system_data.2,
-            ))
+            );
+            let origin = (name.clone(), format.clone(), options.clone());
+            system_data.0.set_origin(&handle, origin);
+            Some(handle)
         } else {
             None
         };
@@ -385,6 +663,46 @@ where
             Ok(false)
         }
     }
+
+    /// Extracts the file the `Handle` of the entity was loaded from, see `from_handle`.
+    fn extract_from_entity(
+        entity: Entity,
+        system_data: &Self::SystemData,
+        _: &[Entity],
+    ) -> Result<Option<Self>, PrefabError> {
+        match system_data.1.get(entity) {
+            Some(handle) => AssetPrefab::from_handle(entity, handle, &system_data.0).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<A, F> AssetPrefab<A, F>
+where
+    A: Asset,
+    F: Format<A> + Clone,
+    F::Options: Clone,
+{
+    /// Finds the file an `AssetPrefab` loaded the asset of `handle` from, to extract prefabs
+    /// from live entities.
+    ///
+    /// Returns an error if the asset has not been loaded from a file by an `AssetPrefab`, as
+    /// there is no file to refer to then.
+    pub fn from_handle(
+        entity: Entity,
+        handle: &Handle<A>,
+        loader: &Loader,
+    ) -> Result<Self, PrefabError> {
+        match loader.origin::<A, (String, F, F::Options)>(handle) {
+            Some((name, format, options)) => Ok(AssetPrefab::File(name, format, options)),
+            None => Err(PrefabError::Custom(BoxedErr(Box::from(format!(
+                "Can not extract the {:?} asset of entity {:?}, it was not loaded from a file \
+                 by an `AssetPrefab`",
+                A::NAME,
+                entity,
+            ))))),
+        }
+    }
 }
 
 /// Helper structure for loading prefabs.
@@ -439,21 +757,28 @@ where
 
 #[cfg(test)]
 mod tests {
-    use std::sync::Arc;
+    use std::{
+        env, fs,
+        path::PathBuf,
+        process,
+        sync::{mpsc, Arc},
+    };
 
     use rayon::ThreadPoolBuilder;
 
     use amethyst_core::{
-        specs::{Builder, RunNow, World},
-        GlobalTransform, Time, Transform,
+        specs::{Builder, Entity, Join, RunNow, World},
+        GlobalTransform, Named, Parent, Time, Transform,
     };
 
-    use crate::Loader;
+    use crate::{loader::blocked_pool, Loader, RonFormat};
 
     use super::*;
 
     type MyPrefab = Transform;
 
+    type MyAssetPrefab = AssetPrefab<Prefab<MyPrefab>, RonFormat>;
+
     #[test]
     fn test_prefab_load() {
         let mut world = World::new();
@@ -482,4 +807,399 @@ mod tests {
             .get(root_entity)
             .is_some());
     }
+
+    /// A directory with prefab files, which is removed again when dropped.
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(test: &str, files: &[(&str, &str)]) -> Self {
+            let dir = env::temp_dir().join(format!("amethyst_prefab_{}_{}", process::id(), test));
+            fs::create_dir_all(&dir).unwrap();
+            for (name, content) in files {
+                fs::write(dir.join(name), content).unwrap();
+            }
+            TestDir(dir)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Creates a world loading prefab files from `dir`, whose loads are only run by
+    /// `instantiate`. The loads are queued until the returned sender is dropped.
+    fn file_world(dir: &TestDir) -> (World, PrefabLoaderSystem<MyPrefab>, mpsc::Sender<()>) {
+        let mut world = World::new();
+        let (pool, release) = blocked_pool();
+        let mut loader = Loader::new(&dir.0, pool.clone());
+        loader.register_format::<Prefab<MyPrefab>, _>(RonFormat, (), &["ron"]);
+        world.add_resource(pool);
+        world.add_resource(loader);
+        world.add_resource(Time::default());
+        let mut system = PrefabLoaderSystem::<MyPrefab>::default();
+        RunNow::setup(&mut system, &mut world.res);
+        (world, system, release)
+    }
+
+    fn load_data(world: &World, prefab: Prefab<MyPrefab>) -> Handle<Prefab<MyPrefab>> {
+        world.read_resource::<Loader>().load_from_data(
+            prefab,
+            (),
+            &world.read_resource::<AssetStorage<Prefab<MyPrefab>>>(),
+        )
+    }
+
+    /// Instantiates the prefab of `handle` on a new entity, loading the prefab files it refers to.
+    fn instantiate(
+        world: &mut World,
+        system: &mut PrefabLoaderSystem<MyPrefab>,
+        handle: Handle<Prefab<MyPrefab>>,
+    ) -> Entity {
+        let root_entity = world.create_entity().with(handle).build();
+        // Queues the loads of the prefab files, which are processed by the next run.
+        system.run_now(&world.res);
+        world.read_resource::<Loader>().run_queued_loads();
+        system.run_now(&world.res);
+        root_entity
+    }
+
+    fn children(world: &World, parent: Entity) -> Vec<Entity> {
+        let parents = world.read_storage::<Parent>();
+        (&*world.entities(), &parents)
+            .join()
+            .filter(|(_, p)| p.entity == parent)
+            .map(|(entity, _)| entity)
+            .collect()
+    }
+
+    fn translation_x(world: &World, entity: Entity) -> f32 {
+        world
+            .read_storage::<Transform>()
+            .get(entity)
+            .unwrap()
+            .translation()
+            .x
+    }
+
+    #[test]
+    fn test_prefab_base() {
+        let dir = TestDir::new(
+            "base",
+            &[(
+                "base.ron",
+                "Prefab(entities: [
+                    (data: Some((translation: (1.0, 0.0, 0.0)))),
+                    (parent: Some(0), data: Some((translation: (2.0, 0.0, 0.0)))),
+                ])",
+            )],
+        );
+        let (mut world, mut system, _release) = file_world(&dir);
+
+        let mut transform = Transform::default();
+        transform.set_xyz(5.0, 0.0, 0.0);
+        let mut prefab = Prefab::new_main(transform);
+        prefab.set_base("base.ron");
+        let handle = load_data(&world, prefab);
+        let root_entity = instantiate(&mut world, &mut system, handle);
+
+        assert_eq!(5.0, translation_x(&world, root_entity));
+        let children = children(&world, root_entity);
+        assert_eq!(1, children.len(), "Base prefab child was not created");
+        assert_eq!(2.0, translation_x(&world, children[0]));
+    }
+
+    #[test]
+    fn test_prefab_nested() {
+        let dir = TestDir::new(
+            "nested",
+            &[(
+                "child.ron",
+                "Prefab(entities: [
+                    (data: Some((translation: (1.0, 0.0, 0.0)))),
+                    (parent: Some(0), data: Some((translation: (3.0, 0.0, 0.0)))),
+                ])",
+            )],
+        );
+        let (mut world, mut system, _release) = file_world(&dir);
+
+        let mut prefab = Prefab::new_main(Transform::default());
+        let mut transform = Transform::default();
+        transform.set_xyz(7.0, 0.0, 0.0);
+        let nested = prefab.add(Some(0), Some(transform));
+        prefab.entity(nested).unwrap().set_prefab("child.ron");
+        let handle = load_data(&world, prefab);
+        let root_entity = instantiate(&mut world, &mut system, handle);
+
+        let nested_entities = children(&world, root_entity);
+        assert_eq!(1, nested_entities.len(), "Nested prefab was not created");
+        // The data of the instancing entity overrides the main entity of the nested prefab.
+        assert_eq!(7.0, translation_x(&world, nested_entities[0]));
+        let nested_children = children(&world, nested_entities[0]);
+        assert_eq!(
+            1,
+            nested_children.len(),
+            "Nested prefab child was not created"
+        );
+        assert_eq!(3.0, translation_x(&world, nested_children[0]));
+    }
+
+    /// Replaces the loaded prefab of `handle`, like a hot-reload of its file does.
+    fn reload(world: &World, handle: &Handle<Prefab<MyPrefab>>, mut prefab: Prefab<MyPrefab>) {
+        let mut storage = world.write_resource::<AssetStorage<Prefab<MyPrefab>>>();
+        let loaded = storage.get_mut(handle).unwrap();
+        prefab.tag = loaded.tag.map(|tag| tag + 1000);
+        *loaded = prefab;
+    }
+
+    fn translated(x: f32) -> Transform {
+        let mut transform = Transform::default();
+        transform.set_xyz(x, 0.0, 0.0);
+        transform
+    }
+
+    fn set_translation_x(world: &World, entity: Entity, x: f32) {
+        world
+            .write_storage::<Transform>()
+            .get_mut(entity)
+            .unwrap()
+            .set_x(x);
+    }
+
+    #[test]
+    fn test_prefab_reload_overwrites_instances() {
+        let dir = TestDir::new("reload_overwrite", &[]);
+        let (mut world, mut system, _release) = file_world(&dir);
+        let mut prefab = Prefab::new_main(translated(1.0));
+        prefab.add(Some(0), Some(translated(2.0)));
+        let handle = load_data(&world, prefab);
+        let root_entity = instantiate(&mut world, &mut system, handle.clone());
+        let child = children(&world, root_entity)[0];
+        set_translation_x(&world, root_entity, 10.0);
+
+        let mut prefab = Prefab::new_main(translated(3.0));
+        prefab.add(Some(0), Some(translated(4.0)));
+        prefab.add(Some(0), Some(translated(5.0)));
+        reload(&world, &handle, prefab);
+        system.run_now(&world.res);
+
+        assert_eq!(3.0, translation_x(&world, root_entity));
+        let mut child_entities = children(&world, root_entity);
+        assert_eq!(2, child_entities.len(), "Added child was not created");
+        assert!(
+            child_entities.contains(&child),
+            "Existing child was not reused"
+        );
+        child_entities.sort_by(|a, b| {
+            translation_x(&world, *a)
+                .partial_cmp(&translation_x(&world, *b))
+                .unwrap()
+        });
+        assert_eq!(4.0, translation_x(&world, child_entities[0]));
+        assert_eq!(5.0, translation_x(&world, child_entities[1]));
+
+        reload(&world, &handle, Prefab::new_main(translated(6.0)));
+        system.run_now(&world.res);
+        world.maintain();
+
+        assert_eq!(6.0, translation_x(&world, root_entity));
+        assert!(children(&world, root_entity).is_empty());
+        for child in child_entities {
+            assert!(
+                !world.entities().is_alive(child),
+                "Removed child was not deleted"
+            );
+        }
+    }
+
+    #[test]
+    fn test_prefab_reload_preserves_runtime_changes() {
+        let dir = TestDir::new("reload_preserve", &[]);
+        let (mut world, _, _release) = file_world(&dir);
+        let mut system = PrefabLoaderSystem::<MyPrefab>::default()
+            .with_reload_policy(PrefabReloadPolicy::PreserveExisting);
+        RunNow::setup(&mut system, &mut world.res);
+        let mut prefab = Prefab::new_main(translated(1.0));
+        prefab.add(Some(0), Some(translated(2.0)));
+        let handle = load_data(&world, prefab);
+        let root_entity = instantiate(&mut world, &mut system, handle.clone());
+        let child = children(&world, root_entity)[0];
+        set_translation_x(&world, child, 20.0);
+
+        let mut prefab = Prefab::new_main(translated(3.0));
+        prefab.add(Some(0), Some(translated(4.0)));
+        reload(&world, &handle, prefab);
+        system.run_now(&world.res);
+
+        // Unchanged components are updated, components modified at runtime are kept.
+        assert_eq!(3.0, translation_x(&world, root_entity));
+        assert_eq!(20.0, translation_x(&world, child));
+
+        let mut prefab = Prefab::new_main(translated(5.0));
+        prefab.add(Some(0), Some(translated(6.0)));
+        reload(&world, &handle, prefab);
+        system.run_now(&world.res);
+
+        assert_eq!(5.0, translation_x(&world, root_entity));
+        assert_eq!(20.0, translation_x(&world, child));
+    }
+
+    #[test]
+    fn test_prefab_reload_ignored() {
+        let dir = TestDir::new("reload_ignore", &[]);
+        let (mut world, _, _release) = file_world(&dir);
+        let mut system = PrefabLoaderSystem::<MyPrefab>::default()
+            .with_reload_policy(PrefabReloadPolicy::Ignore);
+        RunNow::setup(&mut system, &mut world.res);
+        let handle = load_data(&world, Prefab::new_main(translated(1.0)));
+        let root_entity = instantiate(&mut world, &mut system, handle.clone());
+
+        reload(&world, &handle, Prefab::new_main(translated(3.0)));
+        system.run_now(&world.res);
+
+        assert_eq!(1.0, translation_x(&world, root_entity));
+    }
+
+    #[test]
+    fn test_prefab_extract_round_trip() {
+        let dir = TestDir::new("extract_round_trip", &[]);
+        let (mut world, mut system, _release) = file_world(&dir);
+        let mut transform = Transform::default();
+        transform.set_xyz(1.0, 0.0, 0.0);
+        let root = world.create_entity().with(transform).build();
+        let mut transform = Transform::default();
+        transform.set_xyz(2.0, 0.0, 0.0);
+        world
+            .create_entity()
+            .with(transform)
+            .with(Parent { entity: root })
+            .build();
+
+        let prefab = Prefab::<MyPrefab>::extract(
+            root,
+            &world.entities(),
+            &world.read_storage(),
+            &(world.write_storage(), world.write_storage()),
+        )
+        .unwrap();
+        let serialized = ron::ser::to_string(&prefab).unwrap();
+        fs::write(dir.0.join("extracted.ron"), serialized).unwrap();
+        let handle = world
+            .exec(|loader: PrefabLoader<MyPrefab>| loader.load("extracted.ron", RonFormat, (), ()));
+        let root_entity = instantiate(&mut world, &mut system, handle);
+
+        assert_eq!(1.0, translation_x(&world, root_entity));
+        let children = children(&world, root_entity);
+        assert_eq!(1, children.len(), "Extracted child was not created");
+        assert_eq!(2.0, translation_x(&world, children[0]));
+    }
+
+    #[test]
+    fn test_prefab_extract_partial_data_fails() {
+        let mut world = World::new();
+        world.register::<Transform>();
+        world.register::<GlobalTransform>();
+        world.register::<Named>();
+        world.register::<Parent>();
+        let root = world.create_entity().with(Transform::default()).build();
+        world.create_entity().with(Parent { entity: root }).build();
+
+        let storages = (
+            (world.write_storage(), world.write_storage()),
+            (world.write_storage(),),
+        );
+        let prefab = Prefab::<(Transform, Named)>::extract(
+            root,
+            &world.entities(),
+            &world.read_storage(),
+            &storages,
+        );
+        assert!(prefab.is_err());
+
+        let prefab = Prefab::<(Option<Transform>, Option<Named>)>::extract(
+            root,
+            &world.entities(),
+            &world.read_storage(),
+            &storages,
+        )
+        .unwrap();
+        let entities = prefab.entities().collect::<Vec<_>>();
+        assert!(entities[0].data().unwrap().0.is_some());
+        assert!(entities[0].data().unwrap().1.is_none());
+        assert!(entities[1].data().is_none());
+    }
+
+    #[test]
+    fn test_asset_prefab_extract() {
+        let dir = TestDir::new(
+            "asset_prefab_extract",
+            &[("child.ron", "Prefab(entities: [])")],
+        );
+        let (mut world, _system, _release) = file_world(&dir);
+        let loaded = world.create_entity().build();
+        let from_data = world.create_entity().build();
+        {
+            let mut system_data = <MyAssetPrefab as PrefabData>::SystemData::fetch(&world.res);
+            let mut prefab = AssetPrefab::File("child.ron".to_owned(), RonFormat, ());
+            prefab
+                .load_sub_assets(&mut ProgressCounter::new(), &mut system_data)
+                .unwrap();
+            prefab.add_to_entity(loaded, &mut system_data, &[]).unwrap();
+            let handle = system_data
+                .0
+                .load_from_data(Prefab::new(), (), &system_data.2);
+            system_data.1.insert(from_data, handle).unwrap();
+        }
+
+        let system_data = <MyAssetPrefab as PrefabData>::SystemData::fetch(&world.res);
+        match MyAssetPrefab::extract_from_entity(loaded, &system_data, &[]) {
+            Ok(Some(AssetPrefab::File(ref name, RonFormat, ()))) => assert_eq!("child.ron", name),
+            _ => panic!("Expected the file the asset was loaded from"),
+        }
+        assert!(MyAssetPrefab::extract_from_entity(from_data, &system_data, &[]).is_err());
+    }
+
+    #[test]
+    fn test_prefab_extract() {
+        let mut world = World::new();
+        world.register::<Transform>();
+        world.register::<GlobalTransform>();
+        world.register::<Parent>();
+
+        let mut transform = Transform::default();
+        transform.set_xyz(1.0, 0.0, 0.0);
+        let root = world.create_entity().with(transform).build();
+        let child = world
+            .create_entity()
+            .with(Transform::default())
+            .with(Parent { entity: root })
+            .build();
+        world.create_entity().with(Parent { entity: child }).build();
+        world.create_entity().with(Transform::default()).build();
+
+        let prefab = Prefab::<Option<MyPrefab>>::extract(
+            root,
+            &world.entities(),
+            &world.read_storage(),
+            &(world.write_storage(), world.write_storage()),
+        )
+        .unwrap();
+
+        let entities = prefab.entities().collect::<Vec<_>>();
+        assert_eq!(3, entities.len());
+        assert_eq!(
+            1.0,
+            entities[0]
+                .data()
+                .and_then(Option::as_ref)
+                .unwrap()
+                .translation()
+                .x
+        );
+        assert_eq!(Some(0), entities[1].parent);
+        assert!(entities[1].data().and_then(Option::as_ref).is_some());
+        assert_eq!(Some(1), entities[2].parent);
+        assert!(entities[2].data().and_then(Option::as_ref).is_none());
+    }
 }
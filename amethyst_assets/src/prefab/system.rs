@@ -1,4 +1,6 @@
-use std::{marker::PhantomData, ops::Deref};
+use std::{collections::VecDeque, marker::PhantomData, ops::Deref};
+
+use fnv::FnvHashMap;
 
 use amethyst_core::{
     specs::{
@@ -8,43 +10,77 @@ use amethyst_core::{
     ArcThreadPool, Parent, Time,
 };
 
-use crate::{AssetStorage, Completion, Handle, HotReloadStrategy, ProcessingState, ResultExt};
+use crate::{
+    AssetStorage, Completion, Handle, HotReloadStrategy, Loader, ProcessingState, ProgressCounter,
+    ResultExt,
+};
+
+use super::{Prefab, PrefabData, PrefabReloadPolicy, PrefabTag};
 
-use super::{Prefab, PrefabData, PrefabTag};
+/// Maximum depth of base and nested prefabs, which guards against reference cycles.
+const MAX_PREFAB_DEPTH: usize = 32;
 
 /// System that load `Prefab`s for `PrefabData` `T`.
 ///
+/// Base and nested prefabs referenced by a `Prefab` are loaded the first time the prefab is
+/// instantiated, and the prefab is instantiated once they have all been loaded.
+///
+/// When a prefab, or one of the prefabs it references, is hot-reloaded, all live instances of
+/// it are updated according to the `PrefabReloadPolicy` of the system.
+///
 /// ### Type parameters:
 ///
 /// - `T`: `PrefabData`
 pub struct PrefabLoaderSystem<T> {
     _m: PhantomData<T>,
-    entities: Vec<Entity>,
     finished: Vec<Entity>,
     to_process: BitSet,
     insert_reader: Option<ReaderId<ComponentEvent>>,
     next_tag: u64,
+    instances: FnvHashMap<Entity, PrefabInstance<T>>,
+    reload_policy: PrefabReloadPolicy,
+}
+
+/// The entities created for a prefab on a root `Entity`.
+struct PrefabInstance<T> {
+    /// Tags of the prefab and of all prefabs it references, at the time of instantiation.
+    tags: Vec<u64>,
+    /// All entities created for the prefab except for the root, in creation order.
+    entities: Vec<Entity>,
+    /// The data the prefab added to each entity, extracted right after instantiation. Only
+    /// recorded for `PrefabReloadPolicy::PreserveExisting`, to find what changed at runtime.
+    inserted: FnvHashMap<Entity, T>,
 }
 
 impl<T> Default for PrefabLoaderSystem<T> {
     fn default() -> Self {
         PrefabLoaderSystem {
             _m: PhantomData,
-            entities: Vec::default(),
             finished: Vec::default(),
             to_process: BitSet::default(),
             insert_reader: None,
             next_tag: 0,
+            instances: FnvHashMap::default(),
+            reload_policy: PrefabReloadPolicy::default(),
         }
     }
 }
 
+impl<T> PrefabLoaderSystem<T> {
+    /// Set the policy used to update live instances of hot-reloaded prefabs.
+    pub fn with_reload_policy(mut self, reload_policy: PrefabReloadPolicy) -> Self {
+        self.reload_policy = reload_policy;
+        self
+    }
+}
+
 impl<'a, T> System<'a> for PrefabLoaderSystem<T>
 where
     T: PrefabData<'a> + Send + Sync + 'static,
 {
     type SystemData = (
         Entities<'a>,
+        ReadExpect<'a, Loader>,
         Write<'a, AssetStorage<Prefab<T>>>,
         ReadStorage<'a, Handle<Prefab<T>>>,
         Read<'a, Time>,
@@ -58,6 +94,7 @@ where
     fn run(&mut self, data: Self::SystemData) {
         let (
             entities,
+            loader,
             mut prefab_storage,
             prefab_handles,
             time,
@@ -105,52 +142,133 @@ where
             });
         self.finished.clear();
         for (root_entity, handle, _) in (&*entities, &prefab_handles, &self.to_process).join() {
-            if let Some(prefab) = prefab_storage.get(handle) {
-                self.finished.push(root_entity);
-                // create entities
-                self.entities.clear();
-                self.entities.push(root_entity);
-                for entity_data in prefab.entities.iter().skip(1) {
-                    let new_entity = entities.create();
-                    self.entities.push(new_entity);
-                    if let Some(parent) = entity_data.parent {
-                        parents
-                            .insert(
-                                new_entity,
-                                Parent {
-                                    entity: self.entities[parent],
-                                },
-                            )
-                            .expect("Unable to insert `Parent` for prefab");
-                    }
-                    tags.insert(
-                        new_entity,
-                        PrefabTag::new(
-                            prefab.tag.expect(
-                                "Unreachable: Every loaded prefab should have a `PrefabTag`",
-                            ),
-                        ),
-                    )
-                    .expect("Unable to insert `PrefabTag` for prefab entity");
-                }
-                // create components
-                for (index, entity_data) in prefab.entities.iter().enumerate() {
-                    if let Some(ref prefab_data) = &entity_data.data {
-                        prefab_data
-                            .add_to_entity(
-                                self.entities[index],
-                                &mut prefab_system_data,
-                                &self.entities,
-                            )
-                            .expect("Unable to add prefab system data to entity");
-                    }
+            match resolve_references(handle, &loader, &mut prefab_storage, 0) {
+                Completion::Loading => continue,
+                Completion::Failed => {
+                    error!("Failed loading referenced prefabs, the prefab will not be created");
+                    self.finished.push(root_entity);
+                    continue;
                 }
+                Completion::Complete => self.finished.push(root_entity),
+            }
+            if let Some(prefab) = prefab_storage.get(handle) {
+                let mut instantiation = Instantiation::new(
+                    &entities,
+                    &prefab_storage,
+                    &mut parents,
+                    &mut tags,
+                    &mut prefab_system_data,
+                    prefab,
+                );
+                instantiation.instantiate(prefab, root_entity);
+                let created = instantiation.created;
+                let inserted = if self.reload_policy == PrefabReloadPolicy::PreserveExisting {
+                    extract_instance(root_entity, &created, &prefab_system_data)
+                } else {
+                    FnvHashMap::default()
+                };
+                let instance = PrefabInstance {
+                    tags: collect_tags(prefab, &prefab_storage),
+                    entities: created,
+                    inserted,
+                };
+                self.instances.insert(root_entity, instance);
             }
         }
 
         for entity in &self.finished {
             self.to_process.remove(entity.id());
         }
+
+        self.instances
+            .retain(|root, _| entities.is_alive(*root) && prefab_handles.get(*root).is_some());
+        if self.reload_policy == PrefabReloadPolicy::Ignore {
+            return;
+        }
+        for (root_entity, instance) in &mut self.instances {
+            if self.to_process.contains(root_entity.id()) {
+                continue;
+            }
+            let handle = prefab_handles
+                .get(*root_entity)
+                .expect("Unreachable: Instances without a prefab handle were removed above");
+            match prefab_storage.get(handle) {
+                Some(prefab) if collect_tags(prefab, &prefab_storage) != instance.tags => {}
+                _ => continue,
+            }
+            match resolve_references(handle, &loader, &mut prefab_storage, 0) {
+                Completion::Loading => continue,
+                Completion::Failed => {
+                    error!("Failed loading referenced prefabs, the prefab instance is not updated");
+                    // Only try again once the prefab changes again.
+                    instance.tags = collect_tags(
+                        prefab_storage
+                            .get(handle)
+                            .expect("Unreachable: Checked above"),
+                        &prefab_storage,
+                    );
+                    continue;
+                }
+                Completion::Complete => {}
+            }
+
+            let prefab = prefab_storage
+                .get(handle)
+                .expect("Unreachable: Checked above");
+            instance
+                .entities
+                .retain(|entity| entities.is_alive(*entity));
+            let preserve = self.reload_policy == PrefabReloadPolicy::PreserveExisting;
+            let current = if preserve {
+                extract_instance(*root_entity, &instance.entities, &prefab_system_data)
+            } else {
+                FnvHashMap::default()
+            };
+            let mut instantiation = Instantiation::new(
+                &entities,
+                &prefab_storage,
+                &mut parents,
+                &mut tags,
+                &mut prefab_system_data,
+                prefab,
+            );
+            instantiation.reuse = instance.entities.drain(..).collect();
+            instantiation.instantiate(prefab, *root_entity);
+            let Instantiation { reuse, created, .. } = instantiation;
+            for entity in reuse {
+                if let Err(e) = entities.delete(entity) {
+                    error!("Failed deleting entity removed from prefab: {}", e);
+                }
+            }
+
+            if preserve {
+                let mut prefab_entities = vec![*root_entity];
+                prefab_entities.extend(created.iter().cloned());
+                for (entity, current) in current {
+                    if !prefab_entities.contains(&entity) {
+                        continue;
+                    }
+                    // Without a record of what the prefab added, everything is kept.
+                    let preserved = match instance.inserted.get(&entity) {
+                        Some(inserted) => current.add_changed_to_entity(
+                            inserted,
+                            entity,
+                            &mut prefab_system_data,
+                            &prefab_entities,
+                        ),
+                        None => current
+                            .add_to_entity(entity, &mut prefab_system_data, &prefab_entities)
+                            .map(|_| ()),
+                    };
+                    if let Err(e) = preserved {
+                        error!("Failed preserving prefab data modified at runtime: {}", e);
+                    }
+                }
+                instance.inserted = extract_instance(*root_entity, &created, &prefab_system_data);
+            }
+            instance.tags = collect_tags(prefab, &prefab_storage);
+            instance.entities = created;
+        }
     }
 
     fn setup(&mut self, res: &mut Resources) {
@@ -159,3 +277,255 @@ where
         self.insert_reader = Some(WriteStorage::<Handle<Prefab<T>>>::fetch(&res).register_reader());
     }
 }
+
+/// Triggers loading of the prefabs referenced by the given prefab, and by those prefabs in turn.
+///
+/// Returns the combined completion of all references, or `Completion::Loading` if the prefab
+/// itself is not loaded yet.
+fn resolve_references<T>(
+    handle: &Handle<Prefab<T>>,
+    loader: &Loader,
+    storage: &mut AssetStorage<Prefab<T>>,
+    depth: usize,
+) -> Completion
+where
+    T: Send + Sync + 'static,
+{
+    if depth > MAX_PREFAB_DEPTH {
+        error!(
+            "Prefabs are nested deeper than {} levels, is there a reference cycle?",
+            MAX_PREFAB_DEPTH
+        );
+        return Completion::Failed;
+    }
+
+    let names = match storage.get(handle) {
+        Some(prefab) if prefab.reference_counter.is_none() => Some(prefab.reference_names()),
+        Some(_) => None,
+        None => return Completion::Loading,
+    };
+    if let Some(names) = names {
+        let mut progress = ProgressCounter::default();
+        let references = names
+            .into_iter()
+            .map(|name| {
+                let reference = loader.load_auto(name.as_str(), &mut progress, &*storage);
+                (name, reference)
+            })
+            .collect();
+        let prefab = storage
+            .get_mut(handle)
+            .expect("Unreachable: The prefab was loaded above");
+        prefab.references = references;
+        prefab.reference_counter = Some(progress);
+    }
+
+    let (completion, references) = {
+        let prefab = storage
+            .get(handle)
+            .expect("Unreachable: The prefab was loaded above");
+        let counter = prefab
+            .reference_counter
+            .as_ref()
+            .expect("Unreachable: Reference loading was triggered above");
+        if counter.complete() == Completion::Failed {
+            error!("Failed loading referenced prefab: {:?}", counter.errors());
+        }
+        let references = prefab
+            .references
+            .iter()
+            .map(|(_, reference)| reference.clone())
+            .collect::<Vec<_>>();
+        (counter.complete(), references)
+    };
+    if completion != Completion::Complete {
+        return completion;
+    }
+
+    references
+        .iter()
+        .map(|reference| resolve_references(reference, loader, storage, depth + 1))
+        .fold(Completion::Complete, |acc, completion| {
+            match (acc, completion) {
+                (Completion::Failed, _) | (_, Completion::Failed) => Completion::Failed,
+                (Completion::Loading, _) | (_, Completion::Loading) => Completion::Loading,
+                _ => Completion::Complete,
+            }
+        })
+}
+
+/// Everything needed to create the entities of a prefab and its referenced prefabs.
+struct Instantiation<'r, 'a: 'r, T>
+where
+    T: PrefabData<'a> + Send + Sync + 'static,
+{
+    entities: &'r Entities<'a>,
+    prefabs: &'r AssetStorage<Prefab<T>>,
+    parents: &'r mut WriteStorage<'a, Parent>,
+    tags: &'r mut WriteStorage<'a, PrefabTag<T>>,
+    system_data: &'r mut T::SystemData,
+    tag: u64,
+    /// Entities of a previous instance, reused before new entities are created.
+    reuse: VecDeque<Entity>,
+    /// All entities created or reused, except for the root.
+    created: Vec<Entity>,
+}
+
+impl<'r, 'a: 'r, T> Instantiation<'r, 'a, T>
+where
+    T: PrefabData<'a> + Send + Sync + 'static,
+{
+    fn new(
+        entities: &'r Entities<'a>,
+        prefabs: &'r AssetStorage<Prefab<T>>,
+        parents: &'r mut WriteStorage<'a, Parent>,
+        tags: &'r mut WriteStorage<'a, PrefabTag<T>>,
+        system_data: &'r mut T::SystemData,
+        prefab: &Prefab<T>,
+    ) -> Self {
+        Instantiation {
+            entities,
+            prefabs,
+            parents,
+            tags,
+            system_data,
+            tag: prefab
+                .tag
+                .expect("Unreachable: Every loaded prefab should have a `PrefabTag`"),
+            reuse: VecDeque::new(),
+            created: Vec::new(),
+        }
+    }
+
+    /// Creates the entities of `prefab`, with `root` as its main `Entity`.
+    ///
+    /// Returns the entities in the order of the prefab entries, including those created
+    /// by the base prefab that have no entry in `prefab`.
+    fn instantiate(&mut self, prefab: &Prefab<T>, root: Entity) -> Vec<Entity> {
+        let mut created = match prefab.base {
+            Some(ref base) => {
+                let base = referenced(self.prefabs, prefab, base);
+                self.instantiate(base, root)
+            }
+            None => vec![root],
+        };
+
+        for (index, entity_data) in prefab.entities.iter().enumerate().skip(1) {
+            if index >= created.len() {
+                let new_entity = self.create_entity();
+                if entity_data.parent.is_none() {
+                    self.parents.remove(new_entity);
+                }
+                created.push(new_entity);
+            }
+            if let Some(parent) = entity_data.parent {
+                self.parents
+                    .insert(
+                        created[index],
+                        Parent {
+                            entity: created[parent],
+                        },
+                    )
+                    .expect("Unable to insert `Parent` for prefab");
+            }
+        }
+
+        for (index, entity_data) in prefab.entities.iter().enumerate() {
+            if let Some(ref nested) = entity_data.prefab {
+                let nested = referenced(self.prefabs, prefab, nested);
+                self.instantiate(nested, created[index]);
+            }
+        }
+
+        for (index, entity_data) in prefab.entities.iter().enumerate() {
+            if let Some(ref prefab_data) = entity_data.data {
+                prefab_data
+                    .add_to_entity(created[index], self.system_data, &created)
+                    .expect("Unable to add prefab system data to entity");
+            }
+        }
+
+        created
+    }
+
+    /// Reuses an entity of the previous instance, or creates a new one.
+    fn create_entity(&mut self) -> Entity {
+        let entity = self
+            .reuse
+            .pop_front()
+            .unwrap_or_else(|| self.entities.create());
+        self.tags
+            .insert(entity, PrefabTag::new(self.tag))
+            .expect("Unable to insert `PrefabTag` for prefab entity");
+        self.created.push(entity);
+        entity
+    }
+}
+
+/// Extracts the prefab data of the root and the created entities of a prefab instance.
+///
+/// Entities whose data can not be extracted are left out, and are handled like entities without a
+/// record of their data.
+fn extract_instance<'a, T>(
+    root: Entity,
+    created: &[Entity],
+    system_data: &T::SystemData,
+) -> FnvHashMap<Entity, T>
+where
+    T: PrefabData<'a>,
+{
+    let mut prefab_entities = vec![root];
+    prefab_entities.extend(created.iter().cloned());
+    prefab_entities
+        .iter()
+        .filter_map(
+            |entity| match T::extract_from_entity(*entity, system_data, &prefab_entities) {
+                Ok(data) => data.map(|data| (*entity, data)),
+                Err(e) => {
+                    warn!(
+                        "Failed extracting prefab data from entity {:?}: {}",
+                        entity, e
+                    );
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+/// Collects the tags of the prefab and of all loaded prefabs it references, which change
+/// whenever one of them is reloaded.
+fn collect_tags<T>(prefab: &Prefab<T>, prefabs: &AssetStorage<Prefab<T>>) -> Vec<u64>
+where
+    T: Send + Sync + 'static,
+{
+    let mut tags = Vec::new();
+    let mut pending = vec![prefab];
+    while let Some(prefab) = pending.pop() {
+        tags.extend(prefab.tag);
+        if tags.len() > MAX_PREFAB_DEPTH * MAX_PREFAB_DEPTH {
+            break;
+        }
+        pending.extend(
+            prefab
+                .references
+                .iter()
+                .filter_map(|(_, handle)| prefabs.get(handle)),
+        );
+    }
+    tags
+}
+
+fn referenced<'r, T>(
+    prefabs: &'r AssetStorage<Prefab<T>>,
+    prefab: &Prefab<T>,
+    name: &str,
+) -> &'r Prefab<T>
+where
+    T: Send + Sync + 'static,
+{
+    prefab
+        .reference(name)
+        .and_then(|handle| prefabs.get(handle))
+        .expect("Unreachable: Referenced prefabs are loaded before instantiation")
+}
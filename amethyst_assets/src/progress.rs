@@ -155,11 +155,16 @@ impl Tracker for ProgressCounterTracker {
     }
 }
 
+/// An error that occurred while loading an asset tracked by a `ProgressCounter`.
 #[derive(Debug)]
 pub struct AssetErrorMeta {
+    /// The error
     pub error: Error,
+    /// The id of the handle of the asset
     pub handle_id: u32,
+    /// The `Asset::NAME` of the asset type
     pub asset_type_name: &'static str,
+    /// The name of the asset
     pub asset_name: String,
 }
 
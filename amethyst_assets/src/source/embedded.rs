@@ -0,0 +1,150 @@
+use std::iter::FromIterator;
+
+use fnv::FnvHashMap;
+
+use crate::{source::Source, Error, ErrorKind, Result, ResultExt};
+
+/// Source for assets embedded into the binary.
+///
+/// The assets are usually included with `include_bytes!`, possibly in code generated by a build
+/// script. Embedded assets never change, so they are never hot-reloaded.
+///
+/// ### Example:
+///
+/// ```rust,ignore
+/// let source = EmbeddedSource::new()
+///     .with("mesh/cube.obj", include_bytes!("../assets/mesh/cube.obj"))
+///     .with("texture/logo.png", include_bytes!("../assets/texture/logo.png"));
+/// loader.add_source("embedded", source);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct EmbeddedSource {
+    entries: FnvHashMap<String, &'static [u8]>,
+}
+
+impl EmbeddedSource {
+    /// Creates a new, empty embedded source.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds an asset, replacing any asset with the same path.
+    pub fn insert<P>(&mut self, path: P, bytes: &'static [u8])
+    where
+        P: Into<String>,
+    {
+        self.entries.insert(path.into(), bytes);
+    }
+
+    /// Adds an asset, replacing any asset with the same path.
+    pub fn with<P>(mut self, path: P, bytes: &'static [u8]) -> Self
+    where
+        P: Into<String>,
+    {
+        self.insert(path, bytes);
+        self
+    }
+
+    /// Checks if an asset with the given path exists.
+    pub fn contains(&self, path: &str) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    fn get(&self, path: &str) -> Result<&'static [u8]> {
+        match self.entries.get(path) {
+            Some(bytes) => Ok(bytes),
+            None => Err(Error::from(format!("No embedded asset {:?}", path)))
+                .chain_err(|| ErrorKind::Source),
+        }
+    }
+}
+
+impl<P> FromIterator<(P, &'static [u8])> for EmbeddedSource
+where
+    P: Into<String>,
+{
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (P, &'static [u8])>,
+    {
+        EmbeddedSource {
+            entries: iter
+                .into_iter()
+                .map(|(path, bytes)| (path.into(), bytes))
+                .collect(),
+        }
+    }
+}
+
+impl Source for EmbeddedSource {
+    fn modified(&self, path: &str) -> Result<u64> {
+        self.get(path).map(|_| 0)
+    }
+
+    fn load(&self, path: &str) -> Result<Vec<u8>> {
+        self.get(path).map(<[u8]>::to_vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::specs::prelude::VecStorage;
+
+    use crate::{
+        loader::blocked_pool, source::Source, Asset, AssetStorage, Handle, Loader, ProcessingState,
+        Result, SimpleFormat,
+    };
+
+    use super::EmbeddedSource;
+
+    struct Text(String);
+
+    impl Asset for Text {
+        const NAME: &'static str = "test::Text";
+        type Data = String;
+        type HandleStorage = VecStorage<Handle<Self>>;
+    }
+
+    #[derive(Clone)]
+    struct TextFormat;
+
+    impl SimpleFormat<Text> for TextFormat {
+        const NAME: &'static str = "TEXT";
+        type Options = ();
+
+        fn import(&self, bytes: Vec<u8>, _: ()) -> Result<String> {
+            Ok(String::from_utf8(bytes)?)
+        }
+    }
+
+    #[test]
+    fn missing_assets_are_errors() {
+        let source = EmbeddedSource::new().with("greeting.txt", b"hello");
+        assert!(source.contains("greeting.txt"));
+        assert_eq!(0, source.modified("greeting.txt").unwrap());
+        assert_eq!(b"hello".to_vec(), source.load("greeting.txt").unwrap());
+
+        assert!(!source.contains("missing.txt"));
+        assert!(source.modified("missing.txt").is_err());
+        assert!(source.load("missing.txt").is_err());
+    }
+
+    #[test]
+    fn load_through_the_loader() {
+        let (pool, _release) = blocked_pool();
+        let mut loader = Loader::new(".", pool.clone());
+        let source: EmbeddedSource = vec![("greeting.txt", &b"hello"[..])].into_iter().collect();
+        loader.add_source("embedded", source);
+        let mut storage = AssetStorage::<Text>::new();
+
+        let handle = loader.load_from("greeting.txt", TextFormat, (), "embedded", (), &storage);
+        loader.run_queued_loads();
+        storage.process(
+            |text| Ok(ProcessingState::Loaded(Text(text))),
+            0,
+            &pool,
+            None,
+        );
+        assert_eq!(Some("hello"), storage.get(&handle).map(|text| &*text.0));
+    }
+}
@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use fnv::FnvHashMap;
+use parking_lot::RwLock;
+
+use crate::{source::Source, Error, ErrorKind, Result, ResultExt};
+
+/// Source for assets kept in memory, which can be written at runtime.
+///
+/// Clones of a `MemorySource` share their assets, so a clone can be passed to
+/// `Loader::add_source` while another one is kept to update the assets later.
+/// Replacing an asset marks it as modified, so it is hot-reloaded like a changed file.
+///
+/// ### Example:
+///
+/// ```rust,ignore
+/// let source = MemorySource::new();
+/// source.insert("config/level.ron", level_bytes);
+/// loader.add_source("memory", source.clone());
+/// // Later, trigger a hot-reload of the level
+/// source.insert("config/level.ron", edited_level_bytes);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MemorySource {
+    inner: Arc<RwLock<MemorySourceInner>>,
+}
+
+#[derive(Debug, Default)]
+struct MemorySourceInner {
+    entries: FnvHashMap<String, MemoryEntry>,
+    revision: u64,
+}
+
+#[derive(Debug)]
+struct MemoryEntry {
+    bytes: Arc<[u8]>,
+    revision: u64,
+}
+
+impl MemorySource {
+    /// Creates a new, empty memory source.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds or replaces an asset.
+    pub fn insert<P, B>(&self, path: P, bytes: B)
+    where
+        P: Into<String>,
+        B: Into<Vec<u8>>,
+    {
+        let bytes: Vec<u8> = bytes.into();
+        let mut inner = self.inner.write();
+        inner.revision += 1;
+        let entry = MemoryEntry {
+            bytes: bytes.into(),
+            revision: inner.revision,
+        };
+        inner.entries.insert(path.into(), entry);
+    }
+
+    /// Removes an asset, returning `true` if it existed.
+    pub fn remove(&self, path: &str) -> bool {
+        self.inner.write().entries.remove(path).is_some()
+    }
+
+    /// Checks if an asset with the given path exists.
+    pub fn contains(&self, path: &str) -> bool {
+        self.inner.read().entries.contains_key(path)
+    }
+
+    fn get(&self, path: &str) -> Result<(Arc<[u8]>, u64)> {
+        match self.inner.read().entries.get(path) {
+            Some(entry) => Ok((entry.bytes.clone(), entry.revision)),
+            None => Err(Error::from(format!("No asset {:?} in memory source", path)))
+                .chain_err(|| ErrorKind::Source),
+        }
+    }
+}
+
+impl Source for MemorySource {
+    /// Returns the revision of the asset, which increases whenever it is replaced.
+    fn modified(&self, path: &str) -> Result<u64> {
+        self.get(path).map(|(_, revision)| revision)
+    }
+
+    fn load(&self, path: &str) -> Result<Vec<u8>> {
+        self.get(path).map(|(bytes, _)| bytes.to_vec())
+    }
+
+    fn load_with_metadata(&self, path: &str) -> Result<(Vec<u8>, u64)> {
+        self.get(path)
+            .map(|(bytes, revision)| (bytes.to_vec(), revision))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::source::Source;
+
+    use super::MemorySource;
+
+    #[test]
+    fn replacing_an_asset_marks_it_modified() {
+        let source = MemorySource::new();
+        let loader_source = source.clone();
+        source.insert("level.ron", "old");
+        let modified = loader_source.modified("level.ron").unwrap();
+        assert_ne!(0, modified);
+
+        source.insert("level.ron", "new");
+        assert!(loader_source.modified("level.ron").unwrap() > modified);
+        assert_eq!(b"new".to_vec(), loader_source.load("level.ron").unwrap());
+
+        assert!(source.remove("level.ron"));
+        assert!(loader_source.load("level.ron").is_err());
+    }
+}
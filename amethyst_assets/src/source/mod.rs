@@ -1,8 +1,10 @@
 use crate::Result;
 
-pub use self::dir::Directory;
+pub use self::{dir::Directory, embedded::EmbeddedSource, memory::MemorySource};
 
 mod dir;
+mod embedded;
+mod memory;
 
 /// A trait for asset sources, which provides
 /// methods for loading bytes.
@@ -20,6 +20,7 @@ use amethyst_core::{
 
 use crate::{
     asset::{Asset, FormatValue},
+    budget::{MemoryBudget, Residency},
     error::{Error, ErrorKind, Result, ResultExt},
     progress::Tracker,
     reload::{HotReloadStrategy, Reload},
@@ -49,6 +50,7 @@ pub struct AssetStorage<A: Asset> {
     reloads: Vec<(WeakHandle<A>, Box<dyn Reload<A>>)>,
     unused_handles: MsQueue<Handle<A>>,
     requeue: Mutex<Vec<Processed<A>>>,
+    residency: Residency<A>,
 }
 
 /// Returned by processor systems, describes the loading state of the asset.
@@ -98,6 +100,7 @@ impl<A: Asset> AssetStorage<A> {
             let id = h.id();
             self.bitset.add(id);
             self.handles.push(h.clone());
+            self.residency.insert(id, &asset);
 
             unsafe {
                 self.assets.insert(id, asset);
@@ -109,6 +112,40 @@ impl<A: Asset> AssetStorage<A> {
         }
     }
 
+    /// Sets a memory budget for this storage.
+    ///
+    /// Assets that are only referenced by `WeakHandle`s are kept resident while the storage is
+    /// within the budget, and are evicted least recently used first once it is exceeded.
+    /// See `MemoryBudget` for more information.
+    pub fn set_budget(&mut self, budget: MemoryBudget<A>) {
+        let assets = &self.assets;
+        self.residency.set_budget(
+            budget,
+            self.handles
+                .iter()
+                .map(|h| (h.id(), unsafe { assets.get(h.id()) })),
+        );
+    }
+
+    /// Removes the memory budget of this storage.
+    ///
+    /// Assets which are not referenced by any `Handle` anymore
+    /// will be freed the next time the storage is processed.
+    pub fn clear_budget(&mut self) {
+        self.residency.clear_budget();
+    }
+
+    /// Returns the memory budget of this storage, if any.
+    pub fn budget(&self) -> Option<&MemoryBudget<A>> {
+        self.residency.budget()
+    }
+
+    /// Returns the number of bytes occupied by the assets in this storage,
+    /// as computed by its `MemoryBudget`. This is always `0` if no budget is set.
+    pub fn used_bytes(&self) -> usize {
+        self.residency.used_bytes()
+    }
+
     /// Get an asset from a given asset handle.
     pub fn get(&self, handle: &Handle<A>) -> Option<&A> {
         if self.bitset.contains(handle.id()) {
@@ -163,9 +200,34 @@ impl<A: Asset> AssetStorage<A> {
                 let bitset = &mut self.bitset;
                 let handles = &mut self.handles;
                 let reloads = &mut self.reloads;
+                let residency = &mut self.residency;
 
                 let f = &mut f;
                 let (reload_obj, handle) = match processed {
+                    Processed::Cancelled {
+                        handle_id,
+                        name,
+                        tracker,
+                    } => {
+                        debug!(
+                            "{:?}: Loading of asset {:?} (handle id: {:?}) was cancelled",
+                            A::NAME,
+                            name,
+                            handle_id,
+                        );
+                        tracker.fail(
+                            handle_id,
+                            A::NAME,
+                            name,
+                            Error::from_kind(ErrorKind::Cancelled),
+                        );
+                        self.unused_handles.push(Handle {
+                            id: Arc::new(handle_id),
+                            marker: PhantomData,
+                        });
+
+                        continue;
+                    }
                     Processed::NewAsset {
                         data,
                         handle,
@@ -236,6 +298,7 @@ impl<A: Asset> AssetStorage<A> {
                         let id = handle.id();
                         bitset.add(id);
                         handles.push(handle.clone());
+                        residency.insert(id, &asset);
 
                         // NOTE: the loader has to ensure that a handle will be used
                         // together with a `Data` only once.
@@ -294,6 +357,7 @@ impl<A: Asset> AssetStorage<A> {
                             "Expected handle {:?} to be valid, but the asset storage says otherwise",
                             handle,
                         );
+                        residency.insert(id, &asset);
                         unsafe {
                             let old = assets.get_mut(id);
                             *old = asset;
@@ -315,25 +379,29 @@ impl<A: Asset> AssetStorage<A> {
         }
 
         let mut count = 0;
-        let mut skip = 0;
-        while let Some(i) = self.handles.iter().skip(skip).position(Handle::is_unique) {
-            count += 1;
-            // Re-normalize index
-            let i = skip + i;
-            skip = i;
-            let handle = self.handles.swap_remove(i);
-            let id = handle.id();
-            unsafe {
-                drop_fn(self.assets.remove(id));
+        let mut i = 0;
+        while i < self.handles.len() {
+            let id = self.handles[i].id();
+            if !self.handles[i].is_unique() {
+                self.residency.touch(id);
+                i += 1;
+            } else if self.residency.release(id, frame_number) {
+                i += 1;
+            } else {
+                let handle = self.handles.swap_remove(i);
+                self.free(handle, &mut drop_fn);
+                count += 1;
             }
-            self.bitset.remove(id);
-
-            // Can't reuse old handle here, because otherwise weak handles would still be valid.
-            // TODO: maybe just store u32?
-            self.unused_handles.push(Handle {
-                id: Arc::new(id),
-                marker: PhantomData,
-            });
+        }
+        while let Some(id) = self.residency.evict() {
+            let i = self
+                .handles
+                .iter()
+                .position(|h| h.id() == id)
+                .expect("Unreachable: Evicted asset has no handle in the storage");
+            let handle = self.handles.swap_remove(i);
+            self.free(handle, &mut drop_fn);
+            count += 1;
         }
         if count != 0 {
             debug!("{:?}: Freed {} handle ids", A::NAME, count,);
@@ -348,6 +416,25 @@ impl<A: Asset> AssetStorage<A> {
         }
     }
 
+    fn free<D>(&mut self, handle: Handle<A>, drop_fn: &mut D)
+    where
+        D: FnMut(A),
+    {
+        let id = handle.id();
+        unsafe {
+            drop_fn(self.assets.remove(id));
+        }
+        self.bitset.remove(id);
+        self.residency.remove(id);
+
+        // Can't reuse old handle here, because otherwise weak handles would still be valid.
+        // TODO: maybe just store u32?
+        self.unused_handles.push(Handle {
+            id: Arc::new(id),
+            marker: PhantomData,
+        });
+    }
+
     fn hot_reload(&mut self, pool: &ThreadPool) {
         self.reloads.retain(|&(ref handle, _)| !handle.is_dead());
         while let Some(p) = self
@@ -399,6 +486,7 @@ impl<A: Asset> Default for AssetStorage<A> {
             reloads: Default::default(),
             unused_handles: MsQueue::new(),
             requeue: Mutex::new(Vec::default()),
+            residency: Default::default(),
         }
     }
 }
@@ -513,6 +601,11 @@ pub(crate) enum Processed<A: Asset> {
         name: String,
         old_reload: Box<dyn Reload<A>>,
     },
+    Cancelled {
+        handle_id: u32,
+        name: String,
+        tracker: Box<dyn Tracker>,
+    },
 }
 
 /// A weak handle, which is useful if you don't directly need the asset
@@ -540,3 +633,89 @@ impl<A> WeakHandle<A> {
         self.upgrade().is_none()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rayon::ThreadPoolBuilder;
+
+    use super::*;
+
+    struct Bytes(usize);
+
+    impl Asset for Bytes {
+        const NAME: &'static str = "test::Bytes";
+        type Data = usize;
+        type HandleStorage = VecStorage<Handle<Self>>;
+    }
+
+    fn budget(limit: usize) -> MemoryBudget<Bytes> {
+        MemoryBudget::new(limit).with_size_fn(|bytes: &Bytes| bytes.0)
+    }
+
+    fn insert(storage: &AssetStorage<Bytes>, size: usize) -> Handle<Bytes> {
+        let handle = storage.allocate();
+        storage.processed.push(Processed::NewAsset {
+            data: Ok(FormatValue::data(size)),
+            handle: handle.clone(),
+            name: format!("{} bytes", size),
+            tracker: Box::new(()),
+        });
+        handle
+    }
+
+    fn process(storage: &mut AssetStorage<Bytes>, frame_number: u64, pool: &ThreadPool) {
+        storage.process(
+            |size| Ok(ProcessingState::Loaded(Bytes(size))),
+            frame_number,
+            pool,
+            None,
+        );
+    }
+
+    #[test]
+    fn budget_keeps_released_assets_resident() {
+        let pool = ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let mut storage = AssetStorage::new();
+        storage.set_budget(budget(10));
+
+        let handle = insert(&storage, 4);
+        process(&mut storage, 0, &pool);
+        assert_eq!(4, storage.used_bytes());
+
+        let weak = handle.downgrade();
+        drop(handle);
+        process(&mut storage, 1, &pool);
+        let handle = weak.upgrade().expect("Asset within the budget was freed");
+        assert_eq!(Some(4), storage.get(&handle).map(|bytes| bytes.0));
+
+        drop(handle);
+        storage.clear_budget();
+        process(&mut storage, 2, &pool);
+        assert!(weak.is_dead());
+        assert_eq!(0, storage.used_bytes());
+    }
+
+    #[test]
+    fn budget_evicts_least_recently_used_assets() {
+        let pool = ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let mut storage = AssetStorage::new();
+        storage.set_budget(budget(10));
+
+        let first = insert(&storage, 4);
+        let second = insert(&storage, 4);
+        process(&mut storage, 0, &pool);
+        let (weak_first, weak_second) = (first.downgrade(), second.downgrade());
+        drop(first);
+        process(&mut storage, 1, &pool);
+        drop(second);
+        process(&mut storage, 2, &pool);
+        assert_eq!(8, storage.used_bytes());
+
+        let third = insert(&storage, 4);
+        process(&mut storage, 3, &pool);
+        assert!(weak_first.is_dead());
+        assert!(!weak_second.is_dead());
+        assert!(storage.get(&third).is_some());
+        assert_eq!(8, storage.used_bytes());
+    }
+}
@@ -14,7 +14,8 @@ use super::*;
 ///
 /// You might want to add `"fly_movement"` and `"free_rotation"` as dependencies of the
 /// `TransformSystem` in order to apply changes made by these systems in the same frame.
-/// Adding this bundle will grab the mouse, hide it and keep it centered.
+/// Adding this bundle will grab the mouse, hide it and keep it centered, unless a `CursorState`
+/// resource was added before.
 ///
 /// # Type parameters
 ///
@@ -26,9 +27,8 @@ use super::*;
 /// This bundle adds the following systems:
 ///
 /// * `FlyMovementSystem`
-/// * `FlyRotationSystem`
-/// * `MouseFocusUpdateSystem`
-/// * `CursorHideSystem`
+/// * `FreeRotationSystem`
+/// * `CursorSystem`
 pub struct FlyControlBundle<A, B> {
     sensitivity_x: f32,
     sensitivity_y: f32,
@@ -36,6 +36,7 @@ pub struct FlyControlBundle<A, B> {
     right_input_axis: Option<A>,
     up_input_axis: Option<A>,
     forward_input_axis: Option<A>,
+    rotation_axes: Option<(A, A)>,
     _marker: PhantomData<B>,
 }
 
@@ -53,6 +54,7 @@ impl<A, B> FlyControlBundle<A, B> {
             right_input_axis,
             up_input_axis,
             forward_input_axis,
+            rotation_axes: None,
             _marker: PhantomData,
         }
     }
@@ -69,6 +71,13 @@ impl<A, B> FlyControlBundle<A, B> {
         self.speed = speed;
         self
     }
+
+    /// Rotates the view with the given input axes instead of the raw mouse motion,
+    /// see `FreeRotationSystem::with_axes`.
+    pub fn with_rotation_axes(mut self, horizontal_axis: A, vertical_axis: A) -> Self {
+        self.rotation_axes = Some((horizontal_axis, vertical_axis));
+        self
+    }
 }
 
 impl<'a, 'b, A, B> SystemBundle<'a, 'b> for FlyControlBundle<A, B>
@@ -87,17 +96,17 @@ where
             "fly_movement",
             &[],
         );
+        let mut free_rotation =
+            FreeRotationSystem::<A, B>::new(self.sensitivity_x, self.sensitivity_y);
+        if let Some((horizontal_axis, vertical_axis)) = self.rotation_axes {
+            free_rotation = free_rotation.with_axes(horizontal_axis, vertical_axis);
+        }
+        builder.add(free_rotation, "free_rotation", &[]);
         builder.add(
-            FreeRotationSystem::<A, B>::new(self.sensitivity_x, self.sensitivity_y),
-            "free_rotation",
-            &[],
-        );
-        builder.add(
-            MouseFocusUpdateSystem::new(),
-            "mouse_focus",
+            CursorSystem::new().with_initial_state(CursorState::captured()),
+            "cursor",
             &["free_rotation"],
         );
-        builder.add(CursorHideSystem::new(), "cursor_hide", &["mouse_focus"]);
         Ok(())
     }
 }
@@ -106,12 +115,14 @@ where
 /// Note: Will not actually create a moving entity. It will only register the needed resources and systems.
 /// The generic parameters A and B are the ones used in InputHandler<A,B>.
 /// You might want to add "fly_movement" and "free_rotation" as dependencies of the TransformSystem.
-/// Adding this bundle will grab the mouse, hide it and keep it centered.
+/// Adding this bundle will grab the mouse, hide it and keep it centered, unless a `CursorState`
+/// resource was added before.
 ///
 /// See the `arc_ball_camera` example to see how to use the arc ball camera.
 pub struct ArcBallControlBundle<A, B> {
     sensitivity_x: f32,
     sensitivity_y: f32,
+    rotation_axes: Option<(A, A)>,
     _marker: PhantomData<(A, B)>,
 }
 
@@ -121,6 +132,7 @@ impl<A, B> ArcBallControlBundle<A, B> {
         ArcBallControlBundle {
             sensitivity_x: 1.0,
             sensitivity_y: 1.0,
+            rotation_axes: None,
             _marker: PhantomData,
         }
     }
@@ -131,6 +143,13 @@ impl<A, B> ArcBallControlBundle<A, B> {
         self.sensitivity_y = y;
         self
     }
+
+    /// Rotates the view with the given input axes instead of the raw mouse motion,
+    /// see `FreeRotationSystem::with_axes`.
+    pub fn with_rotation_axes(mut self, horizontal_axis: A, vertical_axis: A) -> Self {
+        self.rotation_axes = Some((horizontal_axis, vertical_axis));
+        self
+    }
 }
 
 impl<'a, 'b, A, B> SystemBundle<'a, 'b> for ArcBallControlBundle<A, B>
@@ -140,17 +159,250 @@ where
 {
     fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<()> {
         builder.add(ArcBallRotationSystem::default(), "arc_ball_rotation", &[]);
+        let mut free_rotation =
+            FreeRotationSystem::<A, B>::new(self.sensitivity_x, self.sensitivity_y);
+        if let Some((horizontal_axis, vertical_axis)) = self.rotation_axes {
+            free_rotation = free_rotation.with_axes(horizontal_axis, vertical_axis);
+        }
+        builder.add(free_rotation, "free_rotation", &[]);
         builder.add(
-            FreeRotationSystem::<A, B>::new(self.sensitivity_x, self.sensitivity_y),
-            "free_rotation",
-            &[],
-        );
-        builder.add(
-            MouseFocusUpdateSystem::new(),
-            "mouse_focus",
+            CursorSystem::new().with_initial_state(CursorState::captured()),
+            "cursor",
             &["free_rotation"],
         );
-        builder.add(CursorHideSystem::new(), "cursor_hide", &["mouse_focus"]);
         Ok(())
     }
 }
+
+/// The bundle that creates an orbit camera system moving `OrbitCamera`s around their targets.
+///
+/// You might want to add `"orbit_camera"` as a dependency of the `TransformSystem` in order to
+/// apply changes made by this system in the same frame.
+///
+/// # Type parameters
+///
+/// * `A`: This is the key the `InputHandler` is using for axes. Often, this is a `String`.
+/// * `B`: This is the key the `InputHandler` is using for actions. Often, this is a `String`.
+/// * `O`: The `CameraObstruction` query, `NoObstruction` by default.
+///
+/// # Systems
+///
+/// This bundle adds the following systems:
+///
+/// * `OrbitCameraSystem`
+pub struct OrbitCameraBundle<A, B, O = NoObstruction> {
+    system: OrbitCameraSystem<A, B, O>,
+}
+
+impl<A, B> OrbitCameraBundle<A, B> {
+    /// Builds a new `OrbitCameraBundle` without input axes and obstruction query.
+    pub fn new() -> Self {
+        OrbitCameraBundle {
+            system: OrbitCameraSystem::new(),
+        }
+    }
+}
+
+impl<A, B, O> OrbitCameraBundle<A, B, O> {
+    /// Sets the rotation in degrees per unit of the rotation axes.
+    pub fn with_sensitivity(self, x: f32, y: f32) -> Self {
+        OrbitCameraBundle {
+            system: self.system.with_sensitivity(x, y),
+        }
+    }
+
+    /// Rotates the cameras with the given input axes, see `OrbitCameraSystem::with_rotation_axes`.
+    pub fn with_rotation_axes(self, horizontal_axis: A, vertical_axis: A) -> Self {
+        OrbitCameraBundle {
+            system: self
+                .system
+                .with_rotation_axes(horizontal_axis, vertical_axis),
+        }
+    }
+
+    /// Zooms the cameras with the given input axis, positive values zoom in.
+    pub fn with_zoom_axis(self, zoom_axis: A) -> Self {
+        OrbitCameraBundle {
+            system: self.system.with_zoom_axis(zoom_axis),
+        }
+    }
+
+    /// Pulls the cameras in front of obstructions found by the given query.
+    pub fn with_obstruction<Q>(self, obstruction: Q) -> OrbitCameraBundle<A, B, Q> {
+        OrbitCameraBundle {
+            system: self.system.with_obstruction(obstruction),
+        }
+    }
+}
+
+impl<'a, 'b, A, B, O> SystemBundle<'a, 'b> for OrbitCameraBundle<A, B, O>
+where
+    A: Send + Sync + Hash + Eq + Clone + 'static,
+    B: Send + Sync + Hash + Eq + Clone + 'static,
+    O: for<'c> CameraObstruction<'c> + Send + 'static,
+{
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<()> {
+        builder.add(self.system, "orbit_camera", &[]);
+        Ok(())
+    }
+}
+
+/// The bundle that creates a character controller system moving `CharacterController`s.
+///
+/// You might want to add `"character_controller"` as a dependency of the `TransformSystem` in
+/// order to apply changes made by this system in the same frame.
+///
+/// # Type parameters
+///
+/// * `A`: This is the key the `InputHandler` is using for axes. Often, this is a `String`.
+/// * `B`: This is the key the `InputHandler` is using for actions. Often, this is a `String`.
+/// * `C`: The `CharacterCollision` query, `NoCollision` by default.
+///
+/// # Systems
+///
+/// This bundle adds the following systems:
+///
+/// * `CharacterControllerSystem`
+pub struct CharacterControllerBundle<A, B, C = NoCollision> {
+    system: CharacterControllerSystem<A, B, C>,
+}
+
+impl<A, B> CharacterControllerBundle<A, B> {
+    /// Builds a new `CharacterControllerBundle` using the provided axes as controls.
+    pub fn new(right_input_axis: Option<A>, forward_input_axis: Option<A>) -> Self {
+        CharacterControllerBundle {
+            system: CharacterControllerSystem::new(right_input_axis, forward_input_axis),
+        }
+    }
+}
+
+impl<A, B, C> CharacterControllerBundle<A, B, C> {
+    /// Jumps when the given action is pressed.
+    pub fn with_jump_action(self, jump_action: B) -> Self {
+        CharacterControllerBundle {
+            system: self.system.with_jump_action(jump_action),
+        }
+    }
+
+    /// Runs while the given action is held.
+    pub fn with_run_action(self, run_action: B) -> Self {
+        CharacterControllerBundle {
+            system: self.system.with_run_action(run_action),
+        }
+    }
+
+    /// Moves the characters with the given collision query.
+    pub fn with_collision<D>(self, collision: D) -> CharacterControllerBundle<A, B, D> {
+        CharacterControllerBundle {
+            system: self.system.with_collision(collision),
+        }
+    }
+}
+
+impl<'a, 'b, A, B, C> SystemBundle<'a, 'b> for CharacterControllerBundle<A, B, C>
+where
+    A: Send + Sync + Hash + Eq + Clone + 'static,
+    B: Send + Sync + Hash + Eq + Clone + 'static,
+    C: for<'c> CharacterCollision<'c> + Send + 'static,
+{
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<()> {
+        builder.add(self.system, "character_controller", &[]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::{
+        nalgebra::UnitQuaternion,
+        specs::prelude::{Builder, Dispatcher, World},
+        transform::Transform,
+    };
+    use amethyst_input::{Axis, InputHandler, MouseAxis, RawInput};
+    use amethyst_renderer::ScreenDimensions;
+
+    use super::*;
+
+    /// Builds and sets up a dispatcher with the systems of the bundle.
+    fn dispatcher<B>(world: &mut World, bundle: B) -> Dispatcher<'static, 'static>
+    where
+        B: SystemBundle<'static, 'static>,
+    {
+        world.add_resource(ScreenDimensions::new(100, 100, 1.0));
+        let mut builder = DispatcherBuilder::new();
+        bundle.build(&mut builder).unwrap();
+        let mut dispatcher = builder.build();
+        dispatcher.setup(&mut world.res);
+        dispatcher
+    }
+
+    #[test]
+    fn fly_control_captures_the_cursor() {
+        let mut world = World::new();
+        let mut dispatcher = dispatcher(
+            &mut world,
+            FlyControlBundle::<String, String>::new(None, None, None)
+                .with_rotation_axes("look_x".to_string(), "look_y".to_string()),
+        );
+        assert_eq!(
+            CursorState::captured(),
+            *world.read_resource::<CursorState>()
+        );
+        assert!(world.read_resource::<WindowFocus>().is_focused);
+
+        // The view rotates right away.
+        {
+            let mut input = world.write_resource::<InputHandler<String, String>>();
+            input.bindings.insert_axis(
+                "look_x",
+                Axis::MouseMotion {
+                    axis: MouseAxis::X,
+                    sensitivity: 1.0,
+                },
+            );
+            input.send_raw_input(
+                &RawInput::MouseMoved {
+                    delta_x: 10.0,
+                    delta_y: 0.0,
+                },
+                &mut Default::default(),
+            );
+        }
+        let entity = world
+            .create_entity()
+            .with(Transform::default())
+            .with(FlyControlTag)
+            .build();
+        dispatcher.dispatch(&world.res);
+        let transforms = world.read_storage::<Transform>();
+        assert_ne!(
+            &UnitQuaternion::identity(),
+            transforms.get(entity).unwrap().rotation()
+        );
+    }
+
+    #[test]
+    fn arc_ball_control_captures_the_cursor() {
+        let mut world = World::new();
+        dispatcher(&mut world, ArcBallControlBundle::<String, String>::new());
+        assert_eq!(
+            CursorState::captured(),
+            *world.read_resource::<CursorState>()
+        );
+        assert!(world.read_resource::<WindowFocus>().is_focused);
+    }
+
+    #[test]
+    fn keep_an_existing_cursor_state() {
+        let mut world = World::new();
+        world.add_resource(CursorState::default());
+        dispatcher(
+            &mut world,
+            FlyControlBundle::<String, String>::new(None, None, None),
+        );
+        assert_eq!(
+            CursorState::default(),
+            *world.read_resource::<CursorState>()
+        );
+    }
+}
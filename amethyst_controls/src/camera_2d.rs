@@ -0,0 +1,494 @@
+//! Camera controller for 2D games.
+
+use std::f32::consts::PI;
+
+use amethyst_core::{
+    nalgebra::{UnitQuaternion, Vector2, Vector3},
+    specs::prelude::{
+        Component, Entity, HashMapStorage, Join, Read, ReadStorage, System, WriteStorage,
+    },
+    timing::Time,
+    transform::{GlobalTransform, Transform},
+};
+use amethyst_renderer::Camera;
+
+use crate::systems::follow_factor;
+
+/// A rectangle in world coordinates the view of a `FollowCamera2D` is kept inside of.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CameraBounds {
+    /// The x coordinate of the left edge.
+    pub left: f32,
+    /// The x coordinate of the right edge.
+    pub right: f32,
+    /// The y coordinate of the bottom edge.
+    pub bottom: f32,
+    /// The y coordinate of the top edge.
+    pub top: f32,
+}
+
+impl CameraBounds {
+    /// Creates bounds from the coordinates of their edges.
+    pub fn new(left: f32, right: f32, bottom: f32, top: f32) -> Self {
+        CameraBounds {
+            left,
+            right,
+            bottom,
+            top,
+        }
+    }
+
+    /// Clamps a camera position so the view, given by its edges relative to the camera, stays
+    /// inside the bounds. Views larger than the bounds are centered on them.
+    fn clamp(&self, position: Vector2<f32>, view: (f32, f32, f32, f32)) -> Vector2<f32> {
+        let (left, right, bottom, top) = view;
+        Vector2::new(
+            clamp_axis(position.x, self.left - left, self.right - right),
+            clamp_axis(position.y, self.bottom - bottom, self.top - top),
+        )
+    }
+}
+
+fn clamp_axis(value: f32, min: f32, max: f32) -> f32 {
+    if min > max {
+        (min + max) / 2.0
+    } else {
+        value.max(min).min(max)
+    }
+}
+
+/// Trauma based screen shake.
+///
+/// Trauma goes from 0 to 1 and decays over time, the shake grows with the square of the trauma
+/// so small hits barely move the camera while large ones shake it hard.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScreenShake {
+    /// The current trauma, from 0 to 1.
+    pub trauma: f32,
+    /// How much trauma is lost per second.
+    pub trauma_decay: f32,
+    /// The largest offset of the camera in world units, reached at full trauma.
+    pub max_offset: Vector2<f32>,
+    /// The largest rotation of the camera in radians, reached at full trauma.
+    pub max_angle: f32,
+    /// How fast the camera moves while shaking.
+    pub frequency: f32,
+}
+
+impl Default for ScreenShake {
+    fn default() -> Self {
+        ScreenShake {
+            trauma: 0.0,
+            trauma_decay: 1.0,
+            max_offset: Vector2::new(10.0, 10.0),
+            max_angle: 5.0f32.to_radians(),
+            frequency: 15.0,
+        }
+    }
+}
+
+impl ScreenShake {
+    /// Adds trauma, the total is kept between 0 and 1.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).max(0.0).min(1.0);
+    }
+
+    /// Returns the offset and rotation of the camera at `time` in seconds.
+    fn offset(&self, time: f32) -> (Vector2<f32>, f32) {
+        let shake = self.trauma * self.trauma;
+        let time = time * self.frequency;
+        (
+            Vector2::new(
+                self.max_offset.x * shake * noise(0.0, time),
+                self.max_offset.y * shake * noise(PI / 3.0, time),
+            ),
+            self.max_angle * shake * noise(PI / 7.0, time),
+        )
+    }
+}
+
+/// Smooth pseudo random noise between -1 and 1, differing between seeds.
+fn noise(seed: f32, time: f32) -> f32 {
+    (time + seed).sin() * 0.5
+        + (time * 2.3 + seed * 1.7).sin() * 0.3
+        + (time * 5.1 + seed * 3.1).sin() * 0.2
+}
+
+/// Add this to a 2D camera to follow a target entity.
+///
+/// The camera only moves once the target leaves the dead zone, a rectangle centered on the
+/// camera, and then catches up with it smoothly. The view can be kept inside world bounds,
+/// snapped to whole pixels, and shaken by adding trauma.
+///
+/// The `FollowCamera2DSystem` controls the x and y position and the rotation of the camera, the
+/// z position is left alone.
+///
+/// ### Example:
+///
+/// ```rust,ignore
+/// world
+///     .create_entity()
+///     .with(Camera::standard_2d())
+///     .with(Transform::default())
+///     .with(
+///         FollowCamera2D::new(player)
+///             .with_dead_zone(64.0, 32.0)
+///             .with_bounds(CameraBounds::new(0.0, 2048.0, 0.0, 1024.0)),
+///     )
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct FollowCamera2D {
+    /// The entity the camera follows, if any.
+    pub target: Option<Entity>,
+    /// Offset from the origin of the target to the point the camera follows.
+    pub target_offset: Vector2<f32>,
+    /// How quickly the camera catches up with the target, per second. Higher values follow
+    /// more tightly, `std::f32::INFINITY` follows without any delay.
+    pub follow_speed: f32,
+    /// The width and height of the dead zone in world units.
+    pub dead_zone: Vector2<f32>,
+    /// The world bounds the view is kept inside of.
+    ///
+    /// The view is taken from the orthographic projection of the `Camera` on the same entity,
+    /// without one only the camera position is kept inside the bounds.
+    pub bounds: Option<CameraBounds>,
+    /// Snaps the camera to whole pixels, given the number of pixels per world unit, to avoid
+    /// shimmering pixel art.
+    pub pixels_per_unit: Option<f32>,
+    /// The screen shake of the camera.
+    pub shake: ScreenShake,
+    focus: Option<Vector2<f32>>,
+}
+
+impl FollowCamera2D {
+    /// Creates a camera following `target` without dead zone, bounds or pixel snapping.
+    pub fn new(target: Entity) -> Self {
+        FollowCamera2D {
+            target: Some(target),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the width and height of the dead zone.
+    pub fn with_dead_zone(mut self, width: f32, height: f32) -> Self {
+        self.dead_zone = Vector2::new(width, height);
+        self
+    }
+
+    /// Keeps the view inside the given world bounds.
+    pub fn with_bounds(mut self, bounds: CameraBounds) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Snaps the camera position to whole pixels.
+    pub fn with_pixels_per_unit(mut self, pixels_per_unit: f32) -> Self {
+        self.pixels_per_unit = Some(pixels_per_unit);
+        self
+    }
+
+    /// Sets the point the camera follows relative to the origin of the target.
+    pub fn with_target_offset(mut self, x: f32, y: f32) -> Self {
+        self.target_offset = Vector2::new(x, y);
+        self
+    }
+
+    /// Sets how quickly the camera catches up with the target, see `follow_speed`.
+    pub fn with_follow_speed(mut self, follow_speed: f32) -> Self {
+        self.follow_speed = follow_speed;
+        self
+    }
+
+    /// Shakes the camera by adding trauma, see `ScreenShake`.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.shake.add_trauma(amount);
+    }
+
+    /// Returns the position of the camera without screen shake and pixel snapping.
+    pub fn focus(&self) -> Option<Vector2<f32>> {
+        self.focus
+    }
+}
+
+impl Default for FollowCamera2D {
+    fn default() -> Self {
+        FollowCamera2D {
+            target: None,
+            target_offset: Vector2::zeros(),
+            follow_speed: 5.0,
+            dead_zone: Vector2::zeros(),
+            bounds: None,
+            pixels_per_unit: None,
+            shake: ScreenShake::default(),
+            focus: None,
+        }
+    }
+}
+
+impl Component for FollowCamera2D {
+    type Storage = HashMapStorage<FollowCamera2D>;
+}
+
+/// Moves the camera just enough to bring the target back within `half_dead_zone` of it.
+fn follow_axis(camera: f32, target: f32, half_dead_zone: f32) -> f32 {
+    let distance = target - camera;
+    target - distance.max(-half_dead_zone).min(half_dead_zone)
+}
+
+/// Returns the edges of the view of an orthographic camera relative to its position.
+fn view_edges(camera: Option<&Camera>) -> (f32, f32, f32, f32) {
+    match camera {
+        Some(camera) => {
+            let proj = &camera.proj;
+            (
+                (-1.0 - proj[(0, 3)]) / proj[(0, 0)],
+                (1.0 - proj[(0, 3)]) / proj[(0, 0)],
+                (-1.0 - proj[(1, 3)]) / proj[(1, 1)],
+                (1.0 - proj[(1, 3)]) / proj[(1, 1)],
+            )
+        }
+        None => (0.0, 0.0, 0.0, 0.0),
+    }
+}
+
+/// The system moving `FollowCamera2D`s.
+///
+/// The targets are read from their `GlobalTransform`, the cameras are expected to have no parent.
+#[derive(Default)]
+pub struct FollowCamera2DSystem;
+
+impl<'a> System<'a> for FollowCamera2DSystem {
+    type SystemData = (
+        Read<'a, Time>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, Camera>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, FollowCamera2D>,
+    );
+
+    fn run(&mut self, (time, globals, projections, mut transforms, mut cameras): Self::SystemData) {
+        let delta = time.delta_seconds();
+        let elapsed = time.absolute_time_seconds() as f32;
+
+        for (camera, transform, projection) in
+            (&mut cameras, &mut transforms, projections.maybe()).join()
+        {
+            let target = camera
+                .target
+                .and_then(|target| globals.get(target))
+                .map(|global| {
+                    Vector2::new(global.0[(0, 3)], global.0[(1, 3)]) + camera.target_offset
+                });
+            let current = match (camera.focus, target) {
+                (Some(focus), _) => focus,
+                (None, Some(target)) => target,
+                (None, None) => Vector2::new(transform.translation().x, transform.translation().y),
+            };
+
+            let desired = match target {
+                Some(target) => Vector2::new(
+                    follow_axis(current.x, target.x, camera.dead_zone.x / 2.0),
+                    follow_axis(current.y, target.y, camera.dead_zone.y / 2.0),
+                ),
+                None => current,
+            };
+            let follow = follow_factor(camera.follow_speed, delta);
+            let mut focus = current + (desired - current) * follow;
+            if let Some(bounds) = camera.bounds {
+                focus = bounds.clamp(focus, view_edges(projection));
+            }
+            camera.focus = Some(focus);
+
+            let (offset, angle) = camera.shake.offset(elapsed);
+            camera.shake.trauma =
+                (camera.shake.trauma - camera.shake.trauma_decay * delta).max(0.0);
+            let mut position = focus + offset;
+            if let Some(pixels_per_unit) = camera.pixels_per_unit {
+                position =
+                    position.map(|value| (value * pixels_per_unit).round() / pixels_per_unit);
+            }
+
+            transform.set_x(position.x);
+            transform.set_y(position.y);
+            transform.set_rotation(UnitQuaternion::from_axis_angle(&Vector3::z_axis(), angle));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::{
+        nalgebra::Matrix4,
+        specs::{Builder, RunNow, World},
+    };
+    use amethyst_renderer::Projection;
+
+    use super::*;
+
+    fn translation(x: f32, y: f32) -> GlobalTransform {
+        GlobalTransform(Matrix4::new_translation(&Vector3::new(x, y, 0.0)))
+    }
+
+    /// Creates a target at `x`, `y` and a camera following it.
+    fn follow(world: &mut World, x: f32, y: f32, mut camera: FollowCamera2D) -> (Entity, Entity) {
+        let target = world.create_entity().with(translation(x, y)).build();
+        camera.target = Some(target);
+        let camera = world
+            .create_entity()
+            .with(Transform::default())
+            .with(camera)
+            .build();
+        (target, camera)
+    }
+
+    fn setup() -> (World, FollowCamera2DSystem) {
+        let mut world = World::new();
+        let mut system = FollowCamera2DSystem;
+        RunNow::setup(&mut system, &mut world.res);
+        (world, system)
+    }
+
+    fn set_delta(world: &World, delta: f32) {
+        world.write_resource::<Time>().set_delta_seconds(delta);
+    }
+
+    fn focus(world: &World, camera: Entity) -> Vector2<f32> {
+        world
+            .read_storage::<FollowCamera2D>()
+            .get(camera)
+            .unwrap()
+            .focus()
+            .unwrap()
+    }
+
+    fn position(world: &World, camera: Entity) -> Vector2<f32> {
+        let transforms = world.read_storage::<Transform>();
+        let translation = transforms.get(camera).unwrap().translation();
+        Vector2::new(translation.x, translation.y)
+    }
+
+    #[test]
+    fn infinite_follow_speed_while_paused() {
+        let (mut world, mut system) = setup();
+        let camera = FollowCamera2D::default().with_follow_speed(std::f32::INFINITY);
+        let (target, camera) = follow(&mut world, 1.0, 2.0, camera);
+        set_delta(&world, 0.0);
+        system.run_now(&world.res);
+        assert_eq!(Vector2::new(1.0, 2.0), position(&world, camera));
+
+        world
+            .write_storage()
+            .insert(target, translation(3.0, -1.0))
+            .unwrap();
+        system.run_now(&world.res);
+        assert_eq!(Vector2::new(3.0, -1.0), position(&world, camera));
+    }
+
+    #[test]
+    fn follow_target_smoothly() {
+        let (mut world, mut system) = setup();
+        let camera = FollowCamera2D::default().with_follow_speed(10.0);
+        let (target, camera) = follow(&mut world, 0.0, 0.0, camera);
+        set_delta(&world, 0.1);
+        system.run_now(&world.res);
+
+        world
+            .write_storage()
+            .insert(target, translation(1.0, 0.0))
+            .unwrap();
+        system.run_now(&world.res);
+        let focus = focus(&world, camera);
+        assert!((focus.x - (1.0 - (-1.0f32).exp())).abs() < 1e-5);
+        assert_eq!(0.0, focus.y);
+    }
+
+    #[test]
+    fn stay_still_inside_dead_zone() {
+        let (mut world, mut system) = setup();
+        let camera = FollowCamera2D::default()
+            .with_follow_speed(std::f32::INFINITY)
+            .with_dead_zone(4.0, 2.0);
+        let (target, camera) = follow(&mut world, 0.0, 0.0, camera);
+        system.run_now(&world.res);
+
+        world
+            .write_storage()
+            .insert(target, translation(1.5, 0.5))
+            .unwrap();
+        system.run_now(&world.res);
+        assert_eq!(Vector2::new(0.0, 0.0), focus(&world, camera));
+
+        // Leaving the dead zone only moves the camera enough to bring the target back inside.
+        world
+            .write_storage()
+            .insert(target, translation(3.0, -2.0))
+            .unwrap();
+        system.run_now(&world.res);
+        assert_eq!(Vector2::new(1.0, -1.0), focus(&world, camera));
+    }
+
+    #[test]
+    fn keep_view_inside_bounds() {
+        let (mut world, mut system) = setup();
+        let camera = FollowCamera2D::default()
+            .with_follow_speed(std::f32::INFINITY)
+            .with_bounds(CameraBounds::new(0.0, 100.0, 0.0, 50.0));
+        let (target, camera) = follow(&mut world, 2.0, 2.0, camera);
+        world
+            .write_storage()
+            .insert(
+                camera,
+                Camera::from(Projection::orthographic(-10.0, 10.0, -5.0, 5.0)),
+            )
+            .unwrap();
+        system.run_now(&world.res);
+        assert_eq!(Vector2::new(10.0, 5.0), focus(&world, camera));
+
+        world
+            .write_storage()
+            .insert(target, translation(200.0, 30.0))
+            .unwrap();
+        system.run_now(&world.res);
+        assert_eq!(Vector2::new(90.0, 30.0), focus(&world, camera));
+
+        // A view wider than the bounds is centered on them.
+        world
+            .write_storage::<FollowCamera2D>()
+            .get_mut(camera)
+            .unwrap()
+            .bounds = Some(CameraBounds::new(0.0, 10.0, 0.0, 50.0));
+        system.run_now(&world.res);
+        assert_eq!(Vector2::new(5.0, 30.0), focus(&world, camera));
+    }
+
+    #[test]
+    fn shake_decays_with_trauma() {
+        let (mut world, mut system) = setup();
+        let mut camera = FollowCamera2D::default().with_follow_speed(std::f32::INFINITY);
+        camera.add_trauma(1.5);
+        assert_eq!(1.0, camera.shake.trauma);
+        let (_, camera) = follow(&mut world, 0.0, 0.0, camera);
+        set_delta(&world, 0.5);
+        system.run_now(&world.res);
+        {
+            let cameras = world.read_storage::<FollowCamera2D>();
+            let shake = &cameras.get(camera).unwrap().shake;
+            assert_eq!(0.5, shake.trauma);
+            let offset = position(&world, camera);
+            assert!(offset.x.abs() <= shake.max_offset.x && offset.y.abs() <= shake.max_offset.y);
+            assert_ne!(Vector2::new(0.0, 0.0), offset);
+        }
+
+        // Without trauma the camera is back at its focus.
+        system.run_now(&world.res);
+        system.run_now(&world.res);
+        assert_eq!(Vector2::new(0.0, 0.0), position(&world, camera));
+        let transforms = world.read_storage::<Transform>();
+        assert_eq!(
+            &UnitQuaternion::identity(),
+            transforms.get(camera).unwrap().rotation()
+        );
+    }
+}
@@ -0,0 +1,756 @@
+//! Kinematic character controller walking on the ground.
+
+use std::hash::Hash;
+
+use amethyst_core::{
+    nalgebra::{Unit, Vector3},
+    specs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, System, SystemData, WriteStorage,
+    },
+    timing::Time,
+    transform::Transform,
+};
+use amethyst_input::{get_input_axis_simple, InputHandler};
+
+/// The gap kept between the character and the surfaces it touches.
+const SKIN: f32 = 0.01;
+
+/// The number of surfaces the character slides along in a single frame.
+const MAX_SLIDES: usize = 3;
+
+/// Add this to an entity to move it as a character with the `CharacterControllerSystem`.
+///
+/// The character walks on the ground, using `+Y` as up. It accelerates towards the walk or run
+/// speed, jumps, falls with gravity, steps up onto ledges up to `step_height` and can't walk up
+/// slopes steeper than `max_slope`. The character is kinematic, it is moved by sweeping its
+/// shape through the world with a `CharacterCollision` query rather than by a physics engine.
+#[derive(Debug, Clone)]
+pub struct CharacterController {
+    /// The walking speed in units per second.
+    pub walk_speed: f32,
+    /// The running speed in units per second.
+    pub run_speed: f32,
+    /// How quickly the character reaches its speed or stops, in units per second squared.
+    pub acceleration: f32,
+    /// The part of the acceleration available while in the air, from 0 to 1.
+    pub air_control: f32,
+    /// The upward speed of a jump in units per second.
+    pub jump_speed: f32,
+    /// The downward acceleration in units per second squared.
+    pub gravity: f32,
+    /// The highest falling speed in units per second.
+    pub max_fall_speed: f32,
+    /// The height of the highest ledge the character steps up onto while walking.
+    pub step_height: f32,
+    /// The steepest slope the character can walk on and stand on, in radians.
+    pub max_slope: f32,
+    /// The current velocity in units per second.
+    pub velocity: Vector3<f32>,
+    grounded: bool,
+}
+
+impl Default for CharacterController {
+    fn default() -> Self {
+        CharacterController {
+            walk_speed: 4.0,
+            run_speed: 8.0,
+            acceleration: 30.0,
+            air_control: 0.3,
+            jump_speed: 5.0,
+            gravity: 9.81,
+            max_fall_speed: 50.0,
+            step_height: 0.3,
+            max_slope: 45.0f32.to_radians(),
+            velocity: Vector3::zeros(),
+            grounded: false,
+        }
+    }
+}
+
+impl CharacterController {
+    /// Sets the walking and running speeds.
+    pub fn with_speeds(mut self, walk_speed: f32, run_speed: f32) -> Self {
+        self.walk_speed = walk_speed;
+        self.run_speed = run_speed;
+        self
+    }
+
+    /// Sets the upward speed of a jump.
+    pub fn with_jump_speed(mut self, jump_speed: f32) -> Self {
+        self.jump_speed = jump_speed;
+        self
+    }
+
+    /// Sets the downward acceleration.
+    pub fn with_gravity(mut self, gravity: f32) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    /// Sets the height of the highest ledge the character steps up onto.
+    pub fn with_step_height(mut self, step_height: f32) -> Self {
+        self.step_height = step_height;
+        self
+    }
+
+    /// Sets the steepest walkable slope in radians.
+    pub fn with_max_slope(mut self, max_slope: f32) -> Self {
+        self.max_slope = max_slope;
+        self
+    }
+
+    /// Returns true if the character stands on the ground.
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+
+    /// Returns true if the character can walk on a surface with the given normal.
+    pub fn is_walkable(&self, normal: &Unit<Vector3<f32>>) -> bool {
+        normal.y >= self.max_slope.cos()
+    }
+}
+
+impl Component for CharacterController {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// A surface hit by a `CharacterCollision` sweep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CharacterHit {
+    /// The distance the shape moved before touching the surface.
+    pub distance: f32,
+    /// The normal of the surface, pointing towards the character.
+    pub normal: Unit<Vector3<f32>>,
+}
+
+/// Sweeps the shape of a character through the world to find what it would hit when moving.
+///
+/// Implement this on top of the shape casts of your physics or collision library, fetching the
+/// data you need with `SystemData`. The system data must not write to `Transform` or
+/// `CharacterController` storages, which the `CharacterControllerSystem` already writes to.
+pub trait CharacterCollision<'a> {
+    /// The data needed to sweep shapes.
+    type SystemData: SystemData<'a>;
+
+    /// Sweeps the shape of `entity` from `position` along `direction` and returns the first
+    /// surface hit closer than `max_distance`. The character itself must be ignored.
+    fn sweep(
+        &self,
+        data: &Self::SystemData,
+        entity: Entity,
+        position: &Vector3<f32>,
+        direction: &Unit<Vector3<f32>>,
+        max_distance: f32,
+    ) -> Option<CharacterHit>;
+}
+
+/// A `CharacterCollision` that never hits anything, characters fall forever.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoCollision;
+
+impl<'a> CharacterCollision<'a> for NoCollision {
+    type SystemData = ();
+
+    fn sweep(
+        &self,
+        _: &(),
+        _: Entity,
+        _: &Vector3<f32>,
+        _: &Unit<Vector3<f32>>,
+        _: f32,
+    ) -> Option<CharacterHit> {
+        None
+    }
+}
+
+/// A `CharacterCollision` with flat ground at the given height and nothing else, useful for
+/// prototyping.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GroundPlane {
+    /// The height of the ground.
+    pub height: f32,
+}
+
+impl<'a> CharacterCollision<'a> for GroundPlane {
+    type SystemData = ();
+
+    fn sweep(
+        &self,
+        _: &(),
+        _: Entity,
+        position: &Vector3<f32>,
+        direction: &Unit<Vector3<f32>>,
+        max_distance: f32,
+    ) -> Option<CharacterHit> {
+        if direction.y >= 0.0 {
+            return None;
+        }
+        let distance = ((position.y - self.height) / -direction.y).max(0.0);
+        if distance > max_distance {
+            return None;
+        }
+        Some(CharacterHit {
+            distance,
+            normal: Vector3::y_axis(),
+        })
+    }
+}
+
+/// The system moving `CharacterController`s.
+///
+/// The right and forward input axes move the characters relative to the direction they face,
+/// forward being `-Z` like `Transform::move_forward`. The jump action jumps when pressed while
+/// standing on the ground and the run action switches to the running speed while held.
+///
+/// # Type parameters
+///
+/// * `A`: This is the key the `InputHandler` is using for axes. Often, this is a `String`.
+/// * `B`: This is the key the `InputHandler` is using for actions. Often, this is a `String`.
+/// * `C`: The `CharacterCollision` query, `NoCollision` by default.
+pub struct CharacterControllerSystem<A, B, C = NoCollision> {
+    right_input_axis: Option<A>,
+    forward_input_axis: Option<A>,
+    jump_action: Option<B>,
+    run_action: Option<B>,
+    collision: C,
+}
+
+impl<A, B> CharacterControllerSystem<A, B> {
+    /// Builds a new `CharacterControllerSystem` using the provided axes as controls.
+    pub fn new(right_input_axis: Option<A>, forward_input_axis: Option<A>) -> Self {
+        CharacterControllerSystem {
+            right_input_axis,
+            forward_input_axis,
+            jump_action: None,
+            run_action: None,
+            collision: NoCollision,
+        }
+    }
+}
+
+impl<A, B, C> CharacterControllerSystem<A, B, C> {
+    /// Jumps when the given action is pressed.
+    pub fn with_jump_action(mut self, jump_action: B) -> Self {
+        self.jump_action = Some(jump_action);
+        self
+    }
+
+    /// Runs while the given action is held.
+    pub fn with_run_action(mut self, run_action: B) -> Self {
+        self.run_action = Some(run_action);
+        self
+    }
+
+    /// Moves the characters with the given collision query.
+    pub fn with_collision<D>(self, collision: D) -> CharacterControllerSystem<A, B, D> {
+        CharacterControllerSystem {
+            right_input_axis: self.right_input_axis,
+            forward_input_axis: self.forward_input_axis,
+            jump_action: self.jump_action,
+            run_action: self.run_action,
+            collision,
+        }
+    }
+}
+
+impl<'a, A, B, C> System<'a> for CharacterControllerSystem<A, B, C>
+where
+    A: Send + Sync + Hash + Eq + Clone + 'static,
+    B: Send + Sync + Hash + Eq + Clone + 'static,
+    C: CharacterCollision<'a>,
+{
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
+        Read<'a, InputHandler<A, B>>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, CharacterController>,
+        C::SystemData,
+    );
+
+    fn run(
+        &mut self,
+        (entities, time, input, mut transforms, mut controllers, collision_data): Self::SystemData,
+    ) {
+        let x = get_input_axis_simple(&self.right_input_axis, &input);
+        let z = get_input_axis_simple(&self.forward_input_axis, &input);
+        let running = self
+            .run_action
+            .as_ref()
+            .and_then(|action| input.action_is_down(action))
+            .unwrap_or(false);
+        let jump = self
+            .jump_action
+            .as_ref()
+            .map_or(false, |action| input.action_just_pressed(action));
+        let delta = time.delta_seconds();
+
+        let mut wish = Vector3::new(x, 0.0, -z);
+        if wish.norm() > 1.0 {
+            wish = wish.normalize();
+        }
+
+        for (entity, controller, transform) in
+            (&*entities, &mut controllers, &mut transforms).join()
+        {
+            // The input relative to the facing of the character, flattened onto the ground.
+            let mut direction = transform.rotation() * wish;
+            direction.y = 0.0;
+            let length = direction.norm();
+            if length > 1.0e-6 {
+                direction *= wish.norm() / length;
+            }
+            let speed = if running {
+                controller.run_speed
+            } else {
+                controller.walk_speed
+            };
+            let control = if controller.grounded {
+                1.0
+            } else {
+                controller.air_control
+            };
+            let horizontal = Vector3::new(controller.velocity.x, 0.0, controller.velocity.z);
+            let change = direction * speed - horizontal;
+            let max_change = controller.acceleration * control * delta;
+            let horizontal = if change.norm() > max_change {
+                horizontal + change.normalize() * max_change
+            } else {
+                horizontal + change
+            };
+            controller.velocity.x = horizontal.x;
+            controller.velocity.z = horizontal.z;
+
+            if jump && controller.grounded {
+                controller.velocity.y = controller.jump_speed;
+                controller.grounded = false;
+            } else if controller.grounded {
+                controller.velocity.y = 0.0;
+            } else {
+                controller.velocity.y = (controller.velocity.y - controller.gravity * delta)
+                    .max(-controller.max_fall_speed);
+            }
+
+            let position = *transform.translation();
+            let position = slide(
+                &self.collision,
+                &collision_data,
+                entity,
+                controller,
+                position,
+                horizontal * delta,
+            );
+            let vertical = controller.velocity.y * delta;
+            let position = move_vertically(
+                &self.collision,
+                &collision_data,
+                entity,
+                controller,
+                position,
+                vertical,
+            );
+            transform.set_position(position);
+        }
+    }
+}
+
+/// Moves along `motion`, sliding along the surfaces hit and stepping up onto ledges.
+fn slide<'a, C: CharacterCollision<'a>>(
+    collision: &C,
+    data: &C::SystemData,
+    entity: Entity,
+    controller: &CharacterController,
+    mut position: Vector3<f32>,
+    mut motion: Vector3<f32>,
+) -> Vector3<f32> {
+    for _ in 0..MAX_SLIDES {
+        let distance = motion.norm();
+        let direction = match Unit::try_new(motion, 1.0e-6) {
+            Some(direction) => direction,
+            None => break,
+        };
+        let hit = match collision.sweep(data, entity, &position, &direction, distance + SKIN) {
+            Some(hit) => hit,
+            None => {
+                position += motion;
+                break;
+            }
+        };
+        let travel = (hit.distance - SKIN).max(0.0).min(distance);
+        position += direction.into_inner() * travel;
+        let remaining = direction.into_inner() * (distance - travel);
+
+        if controller.is_walkable(&hit.normal) {
+            // Walk up the slope.
+            let normal = hit.normal.into_inner();
+            motion = remaining - normal * remaining.dot(&normal);
+            continue;
+        }
+        if controller.grounded {
+            if let Some(stepped) = step_up(collision, data, entity, controller, position, remaining)
+            {
+                return stepped;
+            }
+        }
+        // Slide along the wall without climbing it.
+        let wall = Vector3::new(hit.normal.x, 0.0, hit.normal.z);
+        motion = match wall.try_normalize(1.0e-6) {
+            Some(wall) => remaining - wall * remaining.dot(&wall),
+            None => Vector3::zeros(),
+        };
+    }
+    position
+}
+
+/// Tries to step up onto a ledge blocking `motion`, returning the position on top of it.
+fn step_up<'a, C: CharacterCollision<'a>>(
+    collision: &C,
+    data: &C::SystemData,
+    entity: Entity,
+    controller: &CharacterController,
+    position: Vector3<f32>,
+    motion: Vector3<f32>,
+) -> Option<Vector3<f32>> {
+    let direction = Unit::try_new(motion, 1.0e-6)?;
+    let rise = match collision.sweep(
+        data,
+        entity,
+        &position,
+        &Vector3::y_axis(),
+        controller.step_height,
+    ) {
+        Some(hit) => (hit.distance - SKIN).max(0.0),
+        None => controller.step_height,
+    };
+    if rise <= 0.0 {
+        return None;
+    }
+    let raised = position + Vector3::y() * rise;
+    if collision
+        .sweep(data, entity, &raised, &direction, motion.norm() + SKIN)
+        .is_some()
+    {
+        return None;
+    }
+    let forward = raised + motion;
+    let down = Unit::new_unchecked(-Vector3::y());
+    let hit = collision.sweep(data, entity, &forward, &down, rise + SKIN)?;
+    if !controller.is_walkable(&hit.normal) {
+        return None;
+    }
+    Some(forward - Vector3::y() * (hit.distance - SKIN).max(0.0))
+}
+
+/// Moves up or down by `motion`, landing on walkable ground and sliding down steep slopes.
+///
+/// Characters on the ground are kept on it when walking down slopes and steps up to
+/// `step_height`.
+fn move_vertically<'a, C: CharacterCollision<'a>>(
+    collision: &C,
+    data: &C::SystemData,
+    entity: Entity,
+    controller: &mut CharacterController,
+    mut position: Vector3<f32>,
+    motion: f32,
+) -> Vector3<f32> {
+    if motion > 0.0 {
+        match collision.sweep(data, entity, &position, &Vector3::y_axis(), motion + SKIN) {
+            Some(hit) => {
+                position.y += (hit.distance - SKIN).max(0.0).min(motion);
+                // Bumped into a ceiling.
+                controller.velocity.y = 0.0;
+            }
+            None => position.y += motion,
+        }
+        controller.grounded = false;
+        return position;
+    }
+
+    let fall = -motion;
+    let snap = if controller.grounded {
+        controller.step_height
+    } else {
+        0.0
+    };
+    let down = Unit::new_unchecked(-Vector3::y());
+    match collision.sweep(data, entity, &position, &down, fall + snap + SKIN) {
+        Some(ref hit) if controller.is_walkable(&hit.normal) => {
+            position.y -= (hit.distance - SKIN).max(0.0);
+            controller.grounded = true;
+            controller.velocity.y = 0.0;
+        }
+        Some(hit) => {
+            // Too steep to stand on, slide down the slope.
+            let travel = (hit.distance - SKIN).max(0.0).min(fall);
+            position.y -= travel;
+            controller.grounded = false;
+            let remaining = Vector3::new(0.0, travel - fall, 0.0);
+            let normal = hit.normal.into_inner();
+            let motion = remaining - normal * remaining.dot(&normal);
+            position = slide(collision, data, entity, controller, position, motion);
+        }
+        None => {
+            position.y -= fall;
+            controller.grounded = false;
+        }
+    }
+    position
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::specs::{Builder, RunNow, World};
+    use amethyst_input::{Axis, Button, ControllerButton, ControllerEvent, InputSystem, RawInput};
+    use amethyst_renderer::ScreenDimensions;
+    use winit::VirtualKeyCode;
+
+    use super::*;
+
+    /// Flat ground at height 0 with a step of the given height for `x >= 1`.
+    struct Step(f32);
+
+    impl<'a> CharacterCollision<'a> for Step {
+        type SystemData = ();
+
+        fn sweep(
+            &self,
+            _: &(),
+            _: Entity,
+            position: &Vector3<f32>,
+            direction: &Unit<Vector3<f32>>,
+            max_distance: f32,
+        ) -> Option<CharacterHit> {
+            let mut hits = Vec::new();
+            if direction.x > 0.0 && position.x < 1.0 {
+                let distance = (1.0 - position.x) / direction.x;
+                if position.y + direction.y * distance < self.0 {
+                    hits.push((distance, Unit::new_unchecked(-Vector3::x())));
+                }
+            }
+            if direction.y < 0.0 {
+                for &(height, on_step) in &[(0.0, false), (self.0, true)] {
+                    let distance = ((position.y - height) / -direction.y).max(0.0);
+                    if (position.x + direction.x * distance >= 1.0) == on_step {
+                        hits.push((distance, Vector3::y_axis()));
+                    }
+                }
+            }
+            hits.into_iter()
+                .filter(|&(distance, _)| distance <= max_distance)
+                .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+                .map(|(distance, normal)| CharacterHit { distance, normal })
+        }
+    }
+
+    /// A plane through the origin rising towards `+X` at the given angle in radians.
+    struct Slope(f32);
+
+    impl Slope {
+        fn normal(&self) -> Unit<Vector3<f32>> {
+            Unit::new_normalize(Vector3::new(-self.0.sin(), self.0.cos(), 0.0))
+        }
+    }
+
+    impl<'a> CharacterCollision<'a> for Slope {
+        type SystemData = ();
+
+        fn sweep(
+            &self,
+            _: &(),
+            _: Entity,
+            position: &Vector3<f32>,
+            direction: &Unit<Vector3<f32>>,
+            max_distance: f32,
+        ) -> Option<CharacterHit> {
+            let normal = self.normal();
+            let approach = direction.dot(&*normal);
+            if approach >= 0.0 {
+                return None;
+            }
+            let distance = (position.dot(&*normal) / -approach).max(0.0);
+            if distance > max_distance {
+                return None;
+            }
+            Some(CharacterHit { distance, normal })
+        }
+    }
+
+    fn setup<C>(collision: C) -> (World, CharacterControllerSystem<String, String, C>)
+    where
+        C: for<'c> CharacterCollision<'c>,
+    {
+        let mut world = World::new();
+        let mut system = CharacterControllerSystem::new(Some("right".to_string()), None)
+            .with_jump_action("jump".to_string())
+            .with_collision(collision);
+        RunNow::setup(&mut system, &mut world.res);
+        {
+            let mut input = world.write_resource::<InputHandler<String, String>>();
+            input.bindings.insert_axis(
+                "right",
+                Axis::Emulated {
+                    pos: Button::Key(VirtualKeyCode::D),
+                    neg: Button::Key(VirtualKeyCode::A),
+                    smoothing: None,
+                },
+            );
+            input.bindings.insert_action_binding(
+                "jump".to_string(),
+                vec![Button::Controller(0, ControllerButton::A)],
+            );
+        }
+        world.write_resource::<Time>().set_delta_seconds(0.1);
+        (world, system)
+    }
+
+    fn character(world: &mut World, x: f32, y: f32) -> Entity {
+        let mut transform = Transform::default();
+        transform.set_xyz(x, y, 0.0);
+        world
+            .create_entity()
+            .with(transform)
+            .with(CharacterController {
+                acceleration: 1000.0,
+                ..Default::default()
+            })
+            .build()
+    }
+
+    fn walk_right(world: &World) {
+        world
+            .write_resource::<InputHandler<String, String>>()
+            .send_raw_input(
+                &RawInput::KeyPressed {
+                    key_code: VirtualKeyCode::D,
+                    scancode: 0,
+                },
+                &mut Default::default(),
+            );
+    }
+
+    fn position(world: &World, entity: Entity) -> Vector3<f32> {
+        *world
+            .read_storage::<Transform>()
+            .get(entity)
+            .unwrap()
+            .translation()
+    }
+
+    fn is_grounded(world: &World, entity: Entity) -> bool {
+        world
+            .read_storage::<CharacterController>()
+            .get(entity)
+            .unwrap()
+            .is_grounded()
+    }
+
+    #[test]
+    fn fall_and_land_on_the_ground() {
+        let (mut world, mut system) = setup(GroundPlane { height: 0.0 });
+        let entity = character(&mut world, 0.0, 2.0);
+        system.run_now(&world.res);
+        assert!(!is_grounded(&world, entity));
+        assert!(position(&world, entity).y < 2.0);
+
+        for _ in 0..20 {
+            system.run_now(&world.res);
+        }
+        assert!(is_grounded(&world, entity));
+        assert!((position(&world, entity).y - SKIN).abs() < 1e-5);
+        let controllers = world.read_storage::<CharacterController>();
+        assert_eq!(0.0, controllers.get(entity).unwrap().velocity.y);
+    }
+
+    #[test]
+    fn step_up_onto_low_ledges() {
+        let (mut world, mut system) = setup(Step(0.2));
+        let entity = character(&mut world, 0.0, SKIN);
+        walk_right(&world);
+        for _ in 0..5 {
+            system.run_now(&world.res);
+        }
+        let position = position(&world, entity);
+        assert!(position.x > 1.0);
+        assert!((position.y - (0.2 + SKIN)).abs() < 1e-5);
+        assert!(is_grounded(&world, entity));
+    }
+
+    #[test]
+    fn stop_at_high_ledges() {
+        let (mut world, mut system) = setup(Step(0.5));
+        let entity = character(&mut world, 0.0, SKIN);
+        walk_right(&world);
+        for _ in 0..5 {
+            system.run_now(&world.res);
+        }
+        let position = position(&world, entity);
+        assert!(position.x < 1.0 && position.x > 0.9);
+        assert!((position.y - SKIN).abs() < 1e-5);
+    }
+
+    #[test]
+    fn walk_up_gentle_slopes() {
+        let slope = Slope(30.0f32.to_radians());
+        let start = slope.normal().into_inner() * SKIN;
+        let (mut world, mut system) = setup(slope);
+        let entity = character(&mut world, start.x, start.y);
+        walk_right(&world);
+        for _ in 0..3 {
+            system.run_now(&world.res);
+        }
+        assert!(position(&world, entity).y > 0.25);
+        assert!(is_grounded(&world, entity));
+    }
+
+    #[test]
+    fn slide_down_steep_slopes() {
+        let slope = Slope(60.0f32.to_radians());
+        let start = slope.normal().into_inner() * SKIN;
+        let (mut world, mut system) = setup(slope);
+        let entity = character(&mut world, start.x, start.y);
+        walk_right(&world);
+        for _ in 0..3 {
+            system.run_now(&world.res);
+        }
+        assert!(position(&world, entity).y < start.y);
+        assert!(!is_grounded(&world, entity));
+    }
+
+    #[test]
+    fn jump_with_a_gamepad() {
+        let (mut world, mut system) = setup(GroundPlane { height: 0.0 });
+        world.add_resource(ScreenDimensions::new(100, 100, 1.0));
+        let mut input_system = InputSystem::<String, String>::new(None);
+        RunNow::setup(&mut input_system, &mut world.res);
+        let entity = character(&mut world, 0.0, SKIN);
+        let mut frame = |world: &World| {
+            input_system.run_now(&world.res);
+            system.run_now(&world.res);
+        };
+        frame(&world);
+        assert!(is_grounded(&world, entity));
+
+        // Controller input is queued by the `SdlEventsSystem` at the end of a frame.
+        for event in vec![
+            ControllerEvent::ControllerConnected { which: 0 },
+            ControllerEvent::ControllerButtonPressed {
+                which: 0,
+                button: ControllerButton::A,
+            },
+        ] {
+            world
+                .write_resource::<InputHandler<String, String>>()
+                .queue_raw_input(RawInput::Controller(event));
+        }
+        frame(&world);
+        assert!(!is_grounded(&world, entity));
+        assert!(position(&world, entity).y > 0.4);
+
+        // Holding the button doesn't jump again after landing.
+        for _ in 0..20 {
+            frame(&world);
+        }
+        assert!(is_grounded(&world, entity));
+        assert!((position(&world, entity).y - SKIN).abs() < 1e-5);
+    }
+}
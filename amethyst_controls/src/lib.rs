@@ -7,16 +7,24 @@ use amethyst_core;
 extern crate serde;
 
 mod bundles;
+mod camera_2d;
+mod character;
 mod components;
+mod orbit;
 mod resources;
 mod systems;
 
 pub use self::{
-    bundles::{ArcBallControlBundle, FlyControlBundle},
-    components::{ArcBallControlTag, ControlTagPrefab, FlyControlTag},
-    resources::{HideCursor, WindowFocus},
-    systems::{
-        ArcBallRotationSystem, CursorHideSystem, FlyMovementSystem, FreeRotationSystem,
-        MouseFocusUpdateSystem,
+    bundles::{
+        ArcBallControlBundle, CharacterControllerBundle, FlyControlBundle, OrbitCameraBundle,
+    },
+    camera_2d::{CameraBounds, FollowCamera2D, FollowCamera2DSystem, ScreenShake},
+    character::{
+        CharacterCollision, CharacterController, CharacterControllerSystem, CharacterHit,
+        GroundPlane, NoCollision,
     },
+    components::{ArcBallControlTag, ControlTagPrefab, FlyControlTag},
+    orbit::{CameraObstruction, NoObstruction, OrbitCamera, OrbitCameraSystem},
+    resources::{CursorGrab, CursorRestore, CursorState, WindowFocus},
+    systems::{ArcBallRotationSystem, CursorSystem, FlyMovementSystem, FreeRotationSystem},
 };
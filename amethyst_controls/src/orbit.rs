@@ -0,0 +1,519 @@
+//! Third-person orbit camera following a target entity.
+
+use std::{f32::consts::FRAC_PI_2, hash::Hash, marker::PhantomData};
+
+use amethyst_core::{
+    nalgebra::{Unit, UnitQuaternion, Vector3},
+    specs::prelude::{
+        Component, Entities, Entity, HashMapStorage, Join, Read, ReadStorage, System, SystemData,
+        WriteStorage,
+    },
+    timing::Time,
+    transform::{GlobalTransform, Transform},
+};
+use amethyst_input::InputHandler;
+
+use crate::systems::{follow_factor, frame_axis_value};
+
+/// Add this to a camera to orbit it around a target entity, following the target as it moves.
+///
+/// The camera looks at the target from `distance` away, turned by `yaw` around the up axis and
+/// tilted by `pitch`. It needs the `OrbitCameraSystem`, added by the `OrbitCameraBundle`.
+///
+/// ### Example:
+///
+/// ```rust,ignore
+/// world
+///     .create_entity()
+///     .with(Camera::standard_3d(1024.0, 768.0))
+///     .with(Transform::default())
+///     .with(OrbitCamera::new(player).with_distance(6.0, 2.0, 15.0))
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct OrbitCamera {
+    /// The entity the camera orbits around.
+    pub target: Entity,
+    /// Offset from the origin of the target to the point the camera looks at, for example to
+    /// look at the head of a character instead of its feet.
+    pub target_offset: Vector3<f32>,
+    /// Rotation around the up axis in radians.
+    pub yaw: f32,
+    /// Tilt in radians, negative values look down at the target.
+    pub pitch: f32,
+    /// The lowest allowed pitch in radians.
+    pub min_pitch: f32,
+    /// The highest allowed pitch in radians.
+    pub max_pitch: f32,
+    /// The distance the camera keeps from the target when nothing is in between.
+    pub distance: f32,
+    /// The closest the camera can be zoomed in.
+    pub min_distance: f32,
+    /// The furthest the camera can be zoomed out.
+    pub max_distance: f32,
+    /// How much the distance changes per unit of the zoom axis, or per second at full deflection
+    /// for axes which are not relative, see `Axis::is_relative`.
+    pub zoom_speed: f32,
+    /// How quickly the camera catches up with the target, per second. Higher values follow
+    /// more tightly, `std::f32::INFINITY` follows without any delay.
+    pub follow_speed: f32,
+    /// The space kept between the camera and an obstruction it is pulled in front of.
+    pub obstruction_margin: f32,
+    focus: Option<Vector3<f32>>,
+    current_distance: Option<f32>,
+}
+
+impl OrbitCamera {
+    /// Creates an orbit camera looking down at `target` from 5 units away.
+    pub fn new(target: Entity) -> Self {
+        OrbitCamera {
+            target,
+            target_offset: Vector3::zeros(),
+            yaw: 0.0,
+            pitch: -20.0f32.to_radians(),
+            min_pitch: -80.0f32.to_radians(),
+            max_pitch: 80.0f32.to_radians(),
+            distance: 5.0,
+            min_distance: 1.0,
+            max_distance: 20.0,
+            zoom_speed: 1.0,
+            follow_speed: 10.0,
+            obstruction_margin: 0.2,
+            focus: None,
+            current_distance: None,
+        }
+    }
+
+    /// Sets the distance to the target and the range it can be zoomed in.
+    pub fn with_distance(mut self, distance: f32, min_distance: f32, max_distance: f32) -> Self {
+        self.min_distance = min_distance;
+        self.max_distance = max_distance;
+        self.distance = distance.max(min_distance).min(max_distance);
+        self
+    }
+
+    /// Sets the range the pitch is clamped to, in radians.
+    pub fn with_pitch_limits(mut self, min_pitch: f32, max_pitch: f32) -> Self {
+        self.min_pitch = min_pitch.max(-FRAC_PI_2);
+        self.max_pitch = max_pitch.min(FRAC_PI_2);
+        self.pitch = self.pitch.max(self.min_pitch).min(self.max_pitch);
+        self
+    }
+
+    /// Sets the point the camera looks at relative to the origin of the target.
+    pub fn with_target_offset(mut self, offset: Vector3<f32>) -> Self {
+        self.target_offset = offset;
+        self
+    }
+
+    /// Sets how quickly the camera catches up with the target, see `follow_speed`.
+    pub fn with_follow_speed(mut self, follow_speed: f32) -> Self {
+        self.follow_speed = follow_speed;
+        self
+    }
+
+    /// Returns the distance of the camera to the target after pulling in for obstructions.
+    pub fn current_distance(&self) -> f32 {
+        self.current_distance.unwrap_or(self.distance)
+    }
+
+    /// Returns the orientation of the camera given by its yaw and pitch.
+    pub fn rotation(&self) -> UnitQuaternion<f32> {
+        UnitQuaternion::from_axis_angle(&Vector3::y_axis(), self.yaw)
+            * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), self.pitch)
+    }
+}
+
+impl Component for OrbitCamera {
+    type Storage = HashMapStorage<OrbitCamera>;
+}
+
+/// Finds obstructions between an orbit camera and its target, so the camera can be pulled in
+/// front of them instead of clipping through walls.
+///
+/// Implement this on top of the ray casts of your physics or collision library, fetching the
+/// data you need with `SystemData`. The system data must not write to `Transform` or
+/// `OrbitCamera` storages, which the `OrbitCameraSystem` already writes to.
+pub trait CameraObstruction<'a> {
+    /// The data needed to cast rays.
+    type SystemData: SystemData<'a>;
+
+    /// Casts a ray from `origin` along `direction` and returns the distance to the first
+    /// obstruction closer than `max_distance`.
+    ///
+    /// `ignore` holds the camera and its target, which are never obstructions.
+    fn cast(
+        &self,
+        data: &Self::SystemData,
+        origin: &Vector3<f32>,
+        direction: &Unit<Vector3<f32>>,
+        max_distance: f32,
+        ignore: &[Entity],
+    ) -> Option<f32>;
+}
+
+/// A `CameraObstruction` that never finds any obstruction.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoObstruction;
+
+impl<'a> CameraObstruction<'a> for NoObstruction {
+    type SystemData = ();
+
+    fn cast(
+        &self,
+        _: &(),
+        _: &Vector3<f32>,
+        _: &Unit<Vector3<f32>>,
+        _: f32,
+        _: &[Entity],
+    ) -> Option<f32> {
+        None
+    }
+}
+
+/// The system moving `OrbitCamera`s around their targets.
+///
+/// The cameras are rotated with a pair of input axes set with `with_rotation_axes` and zoomed
+/// with an axis set with `with_zoom_axis`, usually bound to `Axis::MouseWheel`. When the
+/// obstruction query finds something between a camera and its target, the camera is pulled in
+/// front of it right away, and eases back out once it is gone.
+///
+/// The targets are read from their `GlobalTransform`, the cameras are expected to have no parent.
+///
+/// # Type parameters
+///
+/// * `A`: This is the key the `InputHandler` is using for axes. Often, this is a `String`.
+/// * `B`: This is the key the `InputHandler` is using for actions. Often, this is a `String`.
+/// * `O`: The `CameraObstruction` query, `NoObstruction` by default.
+pub struct OrbitCameraSystem<A, B, O = NoObstruction> {
+    sensitivity_x: f32,
+    sensitivity_y: f32,
+    rotation_axes: Option<(A, A)>,
+    zoom_axis: Option<A>,
+    obstruction: O,
+    _marker: PhantomData<B>,
+}
+
+impl<A, B> OrbitCameraSystem<A, B> {
+    /// Builds a new `OrbitCameraSystem` without input axes and obstruction query.
+    pub fn new() -> Self {
+        OrbitCameraSystem {
+            sensitivity_x: 1.0,
+            sensitivity_y: 1.0,
+            rotation_axes: None,
+            zoom_axis: None,
+            obstruction: NoObstruction,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<A, B, O> OrbitCameraSystem<A, B, O> {
+    /// Sets the rotation in degrees per unit of the rotation axes.
+    ///
+    /// Axes which are not relative, like controller sticks, rotate by the sensitivity in degrees
+    /// per second at full deflection, see `Axis::is_relative`.
+    pub fn with_sensitivity(mut self, x: f32, y: f32) -> Self {
+        self.sensitivity_x = x;
+        self.sensitivity_y = y;
+        self
+    }
+
+    /// Rotates the cameras with the values of the given input axes.
+    ///
+    /// Bind the axes to `Axis::MouseMotion` for mouse look, or to a controller stick.
+    pub fn with_rotation_axes(mut self, horizontal_axis: A, vertical_axis: A) -> Self {
+        self.rotation_axes = Some((horizontal_axis, vertical_axis));
+        self
+    }
+
+    /// Zooms the cameras with the value of the given input axis, positive values zoom in.
+    pub fn with_zoom_axis(mut self, zoom_axis: A) -> Self {
+        self.zoom_axis = Some(zoom_axis);
+        self
+    }
+
+    /// Pulls the cameras in front of obstructions found by the given query.
+    pub fn with_obstruction<Q>(self, obstruction: Q) -> OrbitCameraSystem<A, B, Q> {
+        OrbitCameraSystem {
+            sensitivity_x: self.sensitivity_x,
+            sensitivity_y: self.sensitivity_y,
+            rotation_axes: self.rotation_axes,
+            zoom_axis: self.zoom_axis,
+            obstruction,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, A, B, O> System<'a> for OrbitCameraSystem<A, B, O>
+where
+    A: Send + Sync + Hash + Eq + Clone + 'static,
+    B: Send + Sync + Hash + Eq + Clone + 'static,
+    O: CameraObstruction<'a>,
+{
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
+        Read<'a, InputHandler<A, B>>,
+        ReadStorage<'a, GlobalTransform>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, OrbitCamera>,
+        O::SystemData,
+    );
+
+    fn run(
+        &mut self,
+        (entities, time, input, globals, mut transforms, mut cameras, obstruction_data): Self::SystemData,
+    ) {
+        let delta = time.delta_seconds();
+        let (x, y) = match self.rotation_axes {
+            Some((ref horizontal_axis, ref vertical_axis)) => (
+                frame_axis_value(&input, horizontal_axis, delta),
+                frame_axis_value(&input, vertical_axis, delta),
+            ),
+            _ => (0.0, 0.0),
+        };
+        let zoom = self
+            .zoom_axis
+            .as_ref()
+            .map_or(0.0, |axis| frame_axis_value(&input, axis, delta));
+
+        for (entity, camera, transform) in (&*entities, &mut cameras, &mut transforms).join() {
+            let target = match globals.get(camera.target) {
+                Some(global) => {
+                    Vector3::new(global.0[(0, 3)], global.0[(1, 3)], global.0[(2, 3)])
+                        + camera.target_offset
+                }
+                None => continue,
+            };
+
+            camera.yaw -= (x * self.sensitivity_x).to_radians();
+            camera.pitch = (camera.pitch - (y * self.sensitivity_y).to_radians())
+                .max(camera.min_pitch)
+                .min(camera.max_pitch);
+            camera.distance = (camera.distance - zoom * camera.zoom_speed)
+                .max(camera.min_distance)
+                .min(camera.max_distance);
+
+            let follow = follow_factor(camera.follow_speed, delta);
+            let focus_point = match camera.focus {
+                Some(previous) => previous + (target - previous) * follow,
+                None => target,
+            };
+            camera.focus = Some(focus_point);
+
+            let rotation = camera.rotation();
+            let direction = Unit::new_unchecked(rotation * Vector3::z());
+            let wanted = match self.obstruction.cast(
+                &obstruction_data,
+                &focus_point,
+                &direction,
+                camera.distance,
+                &[entity, camera.target],
+            ) {
+                Some(hit) => (hit - camera.obstruction_margin)
+                    .max(0.0)
+                    .min(camera.distance),
+                None => camera.distance,
+            };
+            let distance = match camera.current_distance {
+                Some(current) if current < wanted => current + (wanted - current) * follow,
+                _ => wanted,
+            };
+            camera.current_distance = Some(distance);
+
+            transform.set_position(focus_point + direction.into_inner() * distance);
+            transform.set_rotation(rotation);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::{
+        nalgebra::Matrix4,
+        shrev::EventChannel,
+        specs::{Builder, RunNow, World},
+    };
+    use amethyst_input::{Axis, Button, RawInput};
+    use winit::VirtualKeyCode;
+
+    use super::*;
+
+    /// An obstruction at the distance stored in the `Wall` resource.
+    #[derive(Default)]
+    struct Wall(Option<f32>);
+
+    struct WallObstruction;
+
+    impl<'a> CameraObstruction<'a> for WallObstruction {
+        type SystemData = Read<'a, Wall>;
+
+        fn cast(
+            &self,
+            wall: &Read<'a, Wall>,
+            _: &Vector3<f32>,
+            _: &Unit<Vector3<f32>>,
+            max_distance: f32,
+            _: &[Entity],
+        ) -> Option<f32> {
+            wall.0.filter(|distance| *distance < max_distance)
+        }
+    }
+
+    fn translation(x: f32, y: f32, z: f32) -> GlobalTransform {
+        GlobalTransform(Matrix4::new_translation(&Vector3::new(x, y, z)))
+    }
+
+    /// Creates a target at `x` along the x axis and a camera orbiting it.
+    fn orbit(
+        world: &mut World,
+        x: f32,
+        camera: impl Fn(Entity) -> OrbitCamera,
+    ) -> (Entity, Entity) {
+        let target = world.create_entity().with(translation(x, 0.0, 0.0)).build();
+        let camera = world
+            .create_entity()
+            .with(Transform::default())
+            .with(camera(target))
+            .build();
+        (target, camera)
+    }
+
+    fn set_delta(world: &World, delta: f32) {
+        world.write_resource::<Time>().set_delta_seconds(delta);
+    }
+
+    fn camera(world: &World, camera: Entity) -> OrbitCamera {
+        world
+            .read_storage::<OrbitCamera>()
+            .get(camera)
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn infinite_follow_speed_while_paused() {
+        let mut world = World::new();
+        let mut system = OrbitCameraSystem::<String, String>::new();
+        RunNow::setup(&mut system, &mut world.res);
+        let (target, camera_entity) = orbit(&mut world, 1.0, |target| {
+            OrbitCamera::new(target).with_follow_speed(std::f32::INFINITY)
+        });
+        set_delta(&world, 0.0);
+        system.run_now(&world.res);
+        assert_eq!(
+            Some(Vector3::new(1.0, 0.0, 0.0)),
+            camera(&world, camera_entity).focus
+        );
+
+        world
+            .write_storage()
+            .insert(target, translation(3.0, 0.0, 0.0))
+            .unwrap();
+        system.run_now(&world.res);
+        assert_eq!(
+            Some(Vector3::new(3.0, 0.0, 0.0)),
+            camera(&world, camera_entity).focus
+        );
+    }
+
+    #[test]
+    fn follow_target_smoothly() {
+        let mut world = World::new();
+        let mut system = OrbitCameraSystem::<String, String>::new();
+        RunNow::setup(&mut system, &mut world.res);
+        let (target, camera_entity) = orbit(&mut world, 0.0, |target| {
+            OrbitCamera::new(target).with_follow_speed(10.0)
+        });
+        set_delta(&world, 0.1);
+        system.run_now(&world.res);
+
+        world
+            .write_storage()
+            .insert(target, translation(1.0, 0.0, 0.0))
+            .unwrap();
+        system.run_now(&world.res);
+        let camera = camera(&world, camera_entity);
+        let focus = camera.focus.unwrap();
+        assert!((focus.x - (1.0 - (-1.0f32).exp())).abs() < 1e-5);
+
+        // The camera keeps its distance to the point it looks at.
+        let transforms = world.read_storage::<Transform>();
+        let position = transforms.get(camera_entity).unwrap().translation();
+        assert!(((position - focus).norm() - camera.distance).abs() < 1e-4);
+    }
+
+    #[test]
+    fn clamp_pitch_and_zoom() {
+        let mut world = World::new();
+        let mut system = OrbitCameraSystem::<String, String>::new()
+            .with_rotation_axes("yaw".to_string(), "pitch".to_string())
+            .with_zoom_axis("zoom".to_string())
+            .with_sensitivity(1.0, 1000.0);
+        RunNow::setup(&mut system, &mut world.res);
+        {
+            let mut input = world.write_resource::<InputHandler<String, String>>();
+            input.bindings.insert_axis(
+                "pitch",
+                Axis::Emulated {
+                    pos: Button::Key(VirtualKeyCode::W),
+                    neg: Button::Key(VirtualKeyCode::S),
+                    smoothing: None,
+                },
+            );
+            input.bindings.insert_axis(
+                "zoom",
+                Axis::MouseWheel {
+                    horizontal: false,
+                    sensitivity: 1.0,
+                },
+            );
+            let mut events = EventChannel::new();
+            input.send_raw_input(
+                &RawInput::KeyPressed {
+                    key_code: VirtualKeyCode::W,
+                    scancode: 0,
+                },
+                &mut events,
+            );
+            input.send_raw_input(
+                &RawInput::MouseWheelMoved {
+                    delta_x: 0.0,
+                    delta_y: 100.0,
+                },
+                &mut events,
+            );
+        }
+        let (_, camera_entity) = orbit(&mut world, 0.0, OrbitCamera::new);
+        set_delta(&world, 1.0);
+        system.run_now(&world.res);
+
+        let camera = camera(&world, camera_entity);
+        assert_eq!(camera.min_pitch, camera.pitch);
+        assert_eq!(camera.min_distance, camera.distance);
+    }
+
+    #[test]
+    fn pull_in_front_of_obstructions() {
+        let mut world = World::new();
+        let mut system =
+            OrbitCameraSystem::<String, String>::new().with_obstruction(WallObstruction);
+        RunNow::setup(&mut system, &mut world.res);
+        let (_, camera_entity) = orbit(&mut world, 0.0, |target| {
+            OrbitCamera::new(target).with_follow_speed(10.0)
+        });
+        set_delta(&world, 0.1);
+        world.write_resource::<Wall>().0 = Some(2.0);
+        system.run_now(&world.res);
+        let pulled_in = camera(&world, camera_entity).current_distance();
+        assert!((pulled_in - 1.8).abs() < 1e-5);
+
+        // Eases back out once the obstruction is gone.
+        world.write_resource::<Wall>().0 = None;
+        system.run_now(&world.res);
+        let distance = camera(&world, camera_entity).current_distance();
+        assert!(distance > pulled_in && distance < 5.0);
+    }
+}
@@ -1,5 +1,9 @@
+use winit::MouseCursor;
+
+use amethyst_core::specs::prelude::Entity;
+
 /// Struct which holds information about whether the window is focused.
-/// Written to by MouseFocusUpdateSystem
+/// Written to by the CursorSystem.
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct WindowFocus {
     /// If true then the window is actively focused.
@@ -13,16 +17,105 @@ impl WindowFocus {
     }
 }
 
-/// Resource indicating if the mouse should be grabbed and hidden by the CursorHideSystem
-/// when the window is focused.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-pub struct HideCursor {
-    /// If true this system will take control of the cursor.
-    pub hide: bool,
+/// How the cursor is held by the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CursorGrab {
+    /// The cursor moves freely in and out of the window.
+    Free,
+    /// The cursor is confined to the window.
+    Confined,
+    /// The cursor is confined to the window and kept at its center, for reading relative mouse
+    /// motion with `DeviceEvent::MouseMotion` or `Axis::MouseMotion`.
+    Relative,
 }
 
-impl Default for HideCursor {
+/// When a cursor released because the window lost focus is applied again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CursorRestore {
+    /// As soon as the window regains focus.
+    OnFocus,
+    /// When the window is clicked after regaining focus, so switching back to the game doesn't
+    /// capture the cursor right away.
+    OnClick,
+    /// When `CursorState::resume` is called, for example when leaving a pause menu.
+    Manual,
+}
+
+/// Resource describing how the cursor looks and behaves while the window is focused, applied by
+/// the `CursorSystem`.
+///
+/// Visibility and grab are independent: a hidden cursor can move freely, and a visible one can
+/// be confined to the window. When the window loses focus the cursor is released, shown and
+/// freed, until `restore` applies the state again.
+///
+/// ### Example:
+///
+/// ```rust,ignore
+/// // Show the cursor in the pause menu, from any state.
+/// let mut cursor = world.write_resource::<CursorState>();
+/// cursor.visible = true;
+/// cursor.grab = CursorGrab::Free;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CursorState {
+    /// If true the cursor is shown.
+    pub visible: bool,
+    /// How the cursor is held by the window.
+    pub grab: CursorGrab,
+    /// The icon of the system cursor.
+    pub icon: MouseCursor,
+    /// An entity drawn in place of the system cursor, which is hidden while an image is set.
+    ///
+    /// The `Transform` of the entity is moved to the cursor position in screen pixels, with the
+    /// origin at the bottom left, and the entity is `Hidden` while the cursor is hidden. Draw it
+    /// with a camera projecting the screen in pixels, for example
+    /// `Projection::orthographic(0.0, width, 0.0, height)`.
+    pub image: Option<Entity>,
+    /// When the cursor is applied again after the window lost focus.
+    pub restore: CursorRestore,
+    released: bool,
+}
+
+impl Default for CursorState {
     fn default() -> Self {
-        HideCursor { hide: true }
+        CursorState {
+            visible: true,
+            grab: CursorGrab::Free,
+            icon: MouseCursor::Default,
+            image: None,
+            restore: CursorRestore::OnClick,
+            released: false,
+        }
+    }
+}
+
+impl CursorState {
+    /// A hidden cursor held at the center of the window, for mouse look.
+    pub fn captured() -> Self {
+        CursorState {
+            visible: false,
+            grab: CursorGrab::Relative,
+            ..Default::default()
+        }
+    }
+
+    /// Returns true if the cursor was released because the window lost focus, and was not
+    /// applied again yet.
+    pub fn is_released(&self) -> bool {
+        self.released
+    }
+
+    /// Applies the state again after the window lost focus, see `CursorRestore::Manual`.
+    pub fn resume(&mut self) {
+        self.released = false;
+    }
+
+    /// Returns true if the cursor is currently hidden or grabbed.
+    pub(crate) fn is_held(&self) -> bool {
+        !self.visible || self.image.is_some() || self.grab != CursorGrab::Free
+    }
+
+    pub(crate) fn release(&mut self) {
+        self.released = true;
     }
 }
@@ -1,20 +1,22 @@
 use std::{hash::Hash, marker::PhantomData};
 
-use winit::{DeviceEvent, Event, WindowEvent};
+use winit::{DeviceEvent, ElementState, Event, MouseCursor, WindowEvent};
 
 use amethyst_core::{
     nalgebra::{Unit, Vector3},
     shrev::{EventChannel, ReaderId},
-    specs::prelude::{Join, Read, ReadStorage, Resources, System, Write, WriteStorage},
+    specs::prelude::{
+        Entity, Join, Read, ReadExpect, ReadStorage, Resources, System, Write, WriteStorage,
+    },
     timing::Time,
     transform::Transform,
 };
 use amethyst_input::{get_input_axis_simple, InputHandler};
-use amethyst_renderer::WindowMessages;
+use amethyst_renderer::{Hidden, ScreenDimensions, WindowMessages};
 
 use crate::{
     components::{ArcBallControlTag, FlyControlTag},
-    resources::{HideCursor, WindowFocus},
+    resources::{CursorGrab, CursorRestore, CursorState, WindowFocus},
 };
 
 /// The system that manages the fly movement.
@@ -114,11 +116,12 @@ impl<'a> System<'a> for ArcBallRotationSystem {
 }
 
 /// The system that manages the view rotation.
-/// Controlled by the mouse.
+/// Controlled by the mouse, or by a pair of input axes set with `with_axes`.
 /// Goes into an inactive state if the window is not focused (`WindowFocus` resource).
 ///
-/// Can be manually disabled by making the mouse visible using the `HideCursor` resource:
-/// `HideCursor.hide = false`
+/// Can be manually disabled by making the cursor visible using the `CursorState` resource:
+/// `CursorState.visible = true`. Without the `WindowFocus` and `CursorState` resources the view
+/// always rotates.
 ///
 /// # Type parameters
 ///
@@ -127,6 +130,8 @@ impl<'a> System<'a> for ArcBallRotationSystem {
 pub struct FreeRotationSystem<A, B> {
     sensitivity_x: f32,
     sensitivity_y: f32,
+    /// The input axes rotating the view horizontally and vertically, instead of mouse motion.
+    input_axes: Option<(A, A)>,
     _marker1: PhantomData<A>,
     _marker2: PhantomData<B>,
     event_reader: Option<ReaderId<Event>>,
@@ -138,11 +143,23 @@ impl<A, B> FreeRotationSystem<A, B> {
         FreeRotationSystem {
             sensitivity_x,
             sensitivity_y,
+            input_axes: None,
             _marker1: PhantomData,
             _marker2: PhantomData,
             event_reader: None,
         }
     }
+
+    /// Rotates the view with the values of the given input axes instead of the raw mouse motion.
+    ///
+    /// Bind the axes to `Axis::MouseMotion` for mouse look, or to a controller stick. The values
+    /// of mouse axes are multiplied by the sensitivity to get the rotation in degrees, like the
+    /// raw mouse motion. Other axes rotate by the sensitivity in degrees per second at full
+    /// deflection, independent of the frame rate.
+    pub fn with_axes(mut self, horizontal_axis: A, vertical_axis: A) -> Self {
+        self.input_axes = Some((horizontal_axis, vertical_axis));
+        self
+    }
 }
 
 impl<'a, A, B> System<'a> for FreeRotationSystem<A, B>
@@ -154,18 +171,34 @@ where
         Read<'a, EventChannel<Event>>,
         WriteStorage<'a, Transform>,
         ReadStorage<'a, FlyControlTag>,
-        Read<'a, WindowFocus>,
-        Read<'a, HideCursor>,
+        // Optional, so the setup of this system doesn't insert the defaults before the
+        // `CursorSystem` inserts its initial state.
+        Option<Read<'a, WindowFocus>>,
+        Option<Read<'a, CursorState>>,
+        Read<'a, InputHandler<A, B>>,
+        Read<'a, Time>,
     );
 
-    fn run(&mut self, (events, mut transform, tag, focus, hide): Self::SystemData) {
-        let focused = focus.is_focused;
-        for event in
+    fn run(&mut self, (events, mut transform, tag, focus, cursor, input, time): Self::SystemData) {
+        let active = focus.map_or(true, |focus| focus.is_focused)
+            && cursor.map_or(true, |cursor| !cursor.visible && !cursor.is_released());
+        let events =
             events.read(&mut self.event_reader.as_mut().expect(
                 "`FreeRotationSystem::setup` was not called before `FreeRotationSystem::run`",
-            ))
-        {
-            if focused && hide.hide {
+            ));
+        if let Some((ref horizontal_axis, ref vertical_axis)) = self.input_axes {
+            let x = frame_axis_value(&input, horizontal_axis, time.delta_seconds());
+            let y = frame_axis_value(&input, vertical_axis, time.delta_seconds());
+            if active && (x != 0.0 || y != 0.0) {
+                for (transform, _) in (&mut transform, &tag).join() {
+                    transform.pitch_local((-y * self.sensitivity_y).to_radians());
+                    transform.yaw_global((-x * self.sensitivity_x).to_radians());
+                }
+            }
+            return;
+        }
+        for event in events {
+            if active {
                 if let Event::DeviceEvent { ref event, .. } = *event {
                     if let DeviceEvent::MouseMotion { delta: (x, y) } = *event {
                         for (transform, _) in (&mut transform, &tag).join() {
@@ -186,88 +219,224 @@ where
     }
 }
 
-/// A system which reads Events and saves if a window has lost focus in a WindowFocus resource
-pub struct MouseFocusUpdateSystem {
-    event_reader: Option<ReaderId<Event>>,
+/// Returns the value of an input axis for the current frame.
+///
+/// Relative axes, like mouse motion, are used as they are. All other axes, like controller
+/// sticks, hold their value across frames, so they are scaled by the frame time.
+pub(crate) fn frame_axis_value<A, B>(
+    input: &InputHandler<A, B>,
+    axis: &A,
+    delta_seconds: f32,
+) -> f32
+where
+    A: Send + Sync + Hash + Eq + Clone + 'static,
+    B: Send + Sync + Hash + Eq + Clone + 'static,
+{
+    let value = input.axis_value(axis).unwrap_or(0.0) as f32;
+    if input.axis_is_relative(axis).unwrap_or(true) {
+        value
+    } else {
+        value * delta_seconds
+    }
 }
 
-impl MouseFocusUpdateSystem {
-    /// Builds a new MouseFocusUpdateSystem.
-    pub fn new() -> MouseFocusUpdateSystem {
-        MouseFocusUpdateSystem { event_reader: None }
+/// Returns how far a value following a target with exponential smoothing moves towards it during
+/// a frame, independent of the frame rate.
+///
+/// A `speed` of `std::f32::INFINITY` reaches the target right away, even when no time passed
+/// because the game is paused.
+pub(crate) fn follow_factor(speed: f32, delta_seconds: f32) -> f32 {
+    if speed == std::f32::INFINITY {
+        1.0
+    } else {
+        1.0 - (-speed * delta_seconds).exp()
     }
 }
 
-impl<'a> System<'a> for MouseFocusUpdateSystem {
-    type SystemData = (Read<'a, EventChannel<Event>>, Write<'a, WindowFocus>);
+/// The system applying the `CursorState` resource to the window, and keeping the `WindowFocus`
+/// resource up to date.
+///
+/// When the window loses focus the cursor is released, and it is applied again following
+/// `CursorState::restore`. Window commands are only sent when the applied state changes.
+pub struct CursorSystem {
+    event_reader: Option<ReaderId<Event>>,
+    initial_state: Option<CursorState>,
+    applied: Option<(bool, CursorGrab, MouseCursor)>,
+    position: Option<(f64, f64)>,
+    shown_image: Option<Entity>,
+}
 
-    fn run(&mut self, (events, mut focus): Self::SystemData) {
-        for event in events.read(&mut self.event_reader.as_mut().expect(
-            "`MouseFocusUpdateSystem::setup` was not called before `MouseFocusUpdateSystem::run`",
-        )) {
-            if let Event::WindowEvent { ref event, .. } = *event {
-                if let WindowEvent::Focused(focused) = *event {
-                    focus.is_focused = focused;
-                }
-            }
+impl CursorSystem {
+    /// Builds a new `CursorSystem`.
+    pub fn new() -> CursorSystem {
+        CursorSystem {
+            event_reader: None,
+            initial_state: None,
+            applied: None,
+            position: None,
+            shown_image: None,
         }
     }
 
-    fn setup(&mut self, res: &mut Resources) {
-        use amethyst_core::specs::prelude::SystemData;
-        Self::SystemData::setup(res);
-        self.event_reader = Some(res.fetch_mut::<EventChannel<Event>>().register_reader());
+    /// Inserts the given `CursorState` during setup, unless the resource already exists.
+    pub fn with_initial_state(mut self, state: CursorState) -> CursorSystem {
+        self.initial_state = Some(state);
+        self
     }
 }
 
-/// System which hides the cursor when the window is focused.
-/// Requires the usage MouseFocusUpdateSystem at the same time.
-pub struct CursorHideSystem {
-    is_hidden: bool,
-}
-
-impl CursorHideSystem {
-    /// Constructs a new CursorHideSystem
-    pub fn new() -> CursorHideSystem {
-        CursorHideSystem { is_hidden: false }
-    }
-}
-
-impl<'a> System<'a> for CursorHideSystem {
+impl<'a> System<'a> for CursorSystem {
     type SystemData = (
+        Read<'a, EventChannel<Event>>,
+        Write<'a, CursorState>,
+        Write<'a, WindowFocus>,
         Write<'a, WindowMessages>,
-        Read<'a, HideCursor>,
-        Read<'a, WindowFocus>,
+        ReadExpect<'a, ScreenDimensions>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, Hidden>,
     );
 
-    fn run(&mut self, (mut msg, hide, focus): Self::SystemData) {
+    fn run(
+        &mut self,
+        (events, mut cursor, mut focus, mut msg, screen, mut transforms, mut hidden): Self::SystemData,
+    ) {
         use amethyst_renderer::mouse::*;
-        if focus.is_focused {
-            if !self.is_hidden && hide.hide {
-                grab_cursor(&mut msg);
-                hide_cursor(&mut msg);
-                self.is_hidden = true;
-            } else if self.is_hidden && !hide.hide {
-                release_cursor(&mut msg);
-                self.is_hidden = false;
+
+        for event in events.read(
+            &mut self
+                .event_reader
+                .as_mut()
+                .expect("`CursorSystem::setup` was not called before `CursorSystem::run`"),
+        ) {
+            if let Event::WindowEvent { ref event, .. } = *event {
+                match *event {
+                    WindowEvent::Focused(focused) => {
+                        focus.is_focused = focused;
+                        if !focused && cursor.is_held() {
+                            cursor.release();
+                        } else if focused && cursor.restore == CursorRestore::OnFocus {
+                            cursor.resume();
+                        }
+                    }
+                    WindowEvent::MouseInput {
+                        state: ElementState::Pressed,
+                        ..
+                    } => {
+                        if focus.is_focused && cursor.restore == CursorRestore::OnClick {
+                            cursor.resume();
+                        }
+                    }
+                    WindowEvent::CursorMoved { position, .. } => {
+                        let hidpi = screen.hidpi_factor();
+                        self.position = Some((position.x * hidpi, position.y * hidpi));
+                    }
+                    WindowEvent::CursorLeft { .. } => self.position = None,
+                    _ => {}
+                }
             }
+        }
+
+        // A released cursor is shown and freed.
+        let active = focus.is_focused && !cursor.is_released();
+        let applied = if active {
+            (
+                cursor.visible && cursor.image.is_none(),
+                cursor.grab,
+                cursor.icon,
+            )
         } else {
-            if self.is_hidden {
-                release_cursor(&mut msg);
-                self.is_hidden = false;
+            (true, CursorGrab::Free, cursor.icon)
+        };
+        if self.applied != Some(applied) {
+            let (visible, grab, icon) = applied;
+            set_mouse_cursor(&mut msg, icon);
+            set_cursor_visible(&mut msg, visible);
+            set_cursor_grabbed(&mut msg, grab != CursorGrab::Free);
+            self.applied = Some(applied);
+        }
+        if active && cursor.grab == CursorGrab::Relative {
+            center_cursor(&mut msg);
+        }
+
+        if self.shown_image != cursor.image {
+            if let Some(image) = self.shown_image.take() {
+                // The previous image may have been deleted already.
+                let _ = hidden.insert(image, Hidden);
+            }
+        }
+        if let Some(image) = cursor.image {
+            if active && cursor.visible {
+                hidden.remove(image);
+                if let (Some((x, y)), Some(transform)) = (self.position, transforms.get_mut(image))
+                {
+                    transform.set_x(x as f32);
+                    transform.set_y(screen.height() - y as f32);
+                }
+                self.shown_image = Some(image);
+            } else {
+                let _ = hidden.insert(image, Hidden);
+                self.shown_image = None;
             }
         }
     }
 
     fn setup(&mut self, res: &mut Resources) {
         use amethyst_core::specs::prelude::SystemData;
-        use amethyst_renderer::mouse::*;
 
+        res.entry::<WindowFocus>().or_insert_with(WindowFocus::new);
+        if let Some(state) = self.initial_state.take() {
+            res.entry::<CursorState>().or_insert_with(|| state);
+        }
         Self::SystemData::setup(res);
+        self.event_reader = Some(res.fetch_mut::<EventChannel<Event>>().register_reader());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_input::{Axis, Button, MouseAxis, RawInput};
+    use winit::VirtualKeyCode;
+
+    use super::*;
+
+    #[test]
+    fn frame_axis_values_scale_held_axes() {
+        let mut input = InputHandler::<String, String>::new();
+        input.bindings.insert_axis(
+            "stick",
+            Axis::Emulated {
+                pos: Button::Key(VirtualKeyCode::D),
+                neg: Button::Key(VirtualKeyCode::A),
+                smoothing: None,
+            },
+        );
+        input.bindings.insert_axis(
+            "mouse",
+            Axis::MouseMotion {
+                axis: MouseAxis::X,
+                sensitivity: 1.0,
+            },
+        );
+        let mut events = EventChannel::new();
+        input.send_raw_input(
+            &RawInput::KeyPressed {
+                key_code: VirtualKeyCode::D,
+                scancode: 0,
+            },
+            &mut events,
+        );
+        input.send_raw_input(
+            &RawInput::MouseMoved {
+                delta_x: 3.0,
+                delta_y: 0.0,
+            },
+            &mut events,
+        );
 
-        let mut msg = res.fetch_mut::<WindowMessages>();
-        grab_cursor(&mut msg);
-        hide_cursor(&mut msg);
-        self.is_hidden = true;
+        let stick = "stick".to_string();
+        let mouse = "mouse".to_string();
+        assert_eq!(0.5, frame_axis_value(&input, &stick, 0.5));
+        assert_eq!(3.0, frame_axis_value(&input, &mouse, 0.5));
+        assert_eq!(0.0, frame_axis_value(&input, &"missing".to_string(), 0.5));
     }
 }
@@ -26,6 +26,12 @@ fn impl_prefab_data_component(ast: &DeriveInput) -> TokenStream {
                              _: &[Entity]) -> ::std::result::Result<(), PrefabError> {
                 system_data.insert(entity, self.clone()).map(|_| ())
             }
+
+            fn extract_from_entity(entity: Entity,
+                                   system_data: &Self::SystemData,
+                                   _: &[Entity]) -> ::std::result::Result<Option<Self>, PrefabError> {
+                Ok(system_data.get(entity).cloned())
+            }
         }
     }
 }
@@ -69,6 +75,52 @@ fn impl_prefab_data_aggregate(ast: &DeriveInput) -> TokenStream {
             })
         }
     });
+    let extracts = (0..data.len()).map(|n| {
+        let (ty, _, is_component) = &data[n];
+        if *is_component {
+            quote! {
+                system_data.#n.get(entity).cloned(),
+            }
+        } else {
+            quote! {
+                <#ty as PrefabData<'pfd>>::extract_from_entity(entity, &system_data.#n, entities)?,
+            }
+        }
+    });
+    let missing = (0..data.len()).map(|n| {
+        quote! {
+            extracted.#n.is_none(),
+        }
+    });
+    let changes = (0..data.len()).map(|n| {
+        let (_, name, is_component) = &data[n];
+        if *is_component {
+            // Plain components can not be compared, so they keep their runtime value.
+            quote! {
+                system_data.#n.insert(entity, self.#name.clone())?;
+            }
+        } else {
+            quote! {
+                self.#name.add_changed_to_entity(&inserted.#name, entity, &mut system_data.#n, entities)?;
+            }
+        }
+    });
+    let fields = (0..data.len()).map(|n| {
+        let (ty, name, is_component) = &data[n];
+        let extracted = if *is_component {
+            quote! { extracted.#n }
+        } else {
+            quote! { extracted.#n.or_else(<#ty as PrefabData<'pfd>>::missing) }
+        };
+        quote! {
+            #name: match #extracted {
+                Some(data) => data,
+                None => {
+                    return Err(<Self as PrefabData<'pfd>>::missing_field_error(entity, stringify!(#name)));
+                }
+            },
+        }
+    });
 
     let (_, ty_generics, where_clause) = ast.generics.split_for_impl();
     let lf_tokens = gen_def_lt_tokens(&ast.generics);
@@ -96,6 +148,32 @@ fn impl_prefab_data_aggregate(ast: &DeriveInput) -> TokenStream {
                 #(#subs)*
                 Ok(ret)
             }
+
+            fn extract_from_entity(entity: Entity,
+                                   system_data: &Self::SystemData,
+                                   entities: &[Entity]) -> ::std::result::Result<Option<Self>, PrefabError> {
+                let extracted = (
+                    #(#extracts)*
+                );
+                // Entities without any of the data are left out, entities with only part of it
+                // can not be extracted.
+                if [#(#missing)*].iter().all(|missing| *missing) {
+                    return Ok(None);
+                }
+                Ok(Some(#base {
+                    #(#fields)*
+                }))
+            }
+
+            #[allow(unused_variables)]
+            fn add_changed_to_entity(&self,
+                                     inserted: &Self,
+                                     entity: Entity,
+                                     system_data: &mut Self::SystemData,
+                                     entities: &[Entity]) -> ::std::result::Result<(), PrefabError> {
+                #(#changes)*
+                Ok(())
+            }
         }
     }
 }
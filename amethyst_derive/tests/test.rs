@@ -4,7 +4,10 @@ extern crate amethyst_derive;
 use amethyst_assets::{PrefabData, PrefabError, ProgressCounter};
 use amethyst_core::{
     shrev::{EventChannel, ReaderId},
-    specs::{Component, DenseVecStorage, Entity, Read, Resources, SystemData, WriteStorage},
+    specs::{
+        Builder, Component, DenseVecStorage, Entity, Read, Resources, SystemData, World,
+        WriteStorage,
+    },
     EventReader,
 };
 
@@ -59,3 +62,39 @@ pub struct Outer {
     #[prefab(Component)]
     external: External,
 }
+
+#[derive(PrefabData, Clone)]
+pub struct Aggregate {
+    #[prefab(Component)]
+    external: External,
+    stuff: Option<Stuff<u64>>,
+}
+
+fn extract(world: &World, entity: Entity) -> Result<Option<Aggregate>, PrefabError> {
+    let system_data = SystemData::fetch(&world.res);
+    Aggregate::extract_from_entity(entity, &system_data, &[entity])
+}
+
+#[test]
+fn aggregate_extraction_requires_fields_which_are_not_options() {
+    let mut world = World::new();
+    world.register::<External>();
+    world.register::<Stuff<u64>>();
+    let empty = world.create_entity().build();
+    let stuff_only = world.create_entity().with(Stuff { inner: 1u64 }).build();
+    let external_only = world.create_entity().with(External { inner: 2 }).build();
+    let both = world
+        .create_entity()
+        .with(External { inner: 3 })
+        .with(Stuff { inner: 4u64 })
+        .build();
+
+    assert!(extract(&world, empty).unwrap().is_none());
+    assert!(extract(&world, stuff_only).is_err());
+    let aggregate = extract(&world, external_only).unwrap().unwrap();
+    assert_eq!(2, aggregate.external.inner);
+    assert!(aggregate.stuff.is_none());
+    let aggregate = extract(&world, both).unwrap().unwrap();
+    assert_eq!(3, aggregate.external.inner);
+    assert_eq!(4, aggregate.stuff.unwrap().inner);
+}
@@ -7,7 +7,8 @@ use gltf::{self, Gltf};
 use crate::{
     animation::AnimationHierarchyPrefab,
     assets::{
-        Error as AssetError, Format, FormatValue, Prefab, Result as AssetResult, ResultExt, Source,
+        Error as AssetError, Format, FormatValue, Importer, Prefab, Result as AssetResult,
+        ResultExt, Source,
     },
     core::{
         nalgebra::{Quaternion, Unit},
@@ -138,6 +139,15 @@ impl Format<Prefab<GltfPrefab>> for GltfSceneFormat {
     }
 }
 
+/// Caches the imported scene in an `ArtifactCache` when loaded with `Loader::load_cached`.
+///
+/// Only the glTF file itself is checked for changes. Artifacts of binary glTF files (`.glb`) and of
+/// files embedding their buffers and images are kept up to date, after changing external buffers
+/// or images remove the artifact to rebuild it.
+impl Importer<Prefab<GltfPrefab>> for GltfSceneFormat {
+    const VERSION: u32 = 1;
+}
+
 fn load_gltf(
     source: Arc<dyn Source>,
     name: &str,
@@ -47,7 +47,7 @@ pub type GltfSceneLoaderSystem = PrefabLoaderSystem<GltfPrefab>;
 pub type GltfSceneAsset = Prefab<GltfPrefab>;
 
 /// `PrefabData` for loading Gltf files.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct GltfPrefab {
     /// `Transform` will almost always be placed, the only exception is for the main `Entity` for
     /// certain scenarios (based on the data in the Gltf file)
@@ -55,6 +55,7 @@ pub struct GltfPrefab {
     /// `MeshData` is placed on all `Entity`s with graphics primitives
     pub mesh: Option<MeshData>,
     /// Mesh handle after sub asset loading is done
+    #[serde(skip)]
     pub mesh_handle: Option<Handle<Mesh>>,
     /// `Material` is placed on all `Entity`s with graphics primitives with material
     pub material: Option<MaterialPrefab<TextureFormat>>,
@@ -96,7 +97,7 @@ impl GltfPrefab {
 }
 
 /// A GLTF node extent
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GltfNodeExtent {
     /// The beginning of this extent
     pub start: Point3<f32>,
@@ -173,13 +174,13 @@ impl Component for GltfNodeExtent {
 }
 
 /// Used during gltf loading to contain the materials used from scenes in the file
-#[derive(Default, Clone, Debug)]
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct GltfMaterialSet {
     pub(crate) materials: HashMap<usize, MaterialPrefab<TextureFormat>>,
 }
 
 /// Options used when loading a GLTF file
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 #[serde(default)]
 pub struct GltfSceneOptions {
     /// Generate texture coordinates if none exist in the Gltf file
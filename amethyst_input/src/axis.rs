@@ -11,6 +11,9 @@ pub enum Axis {
         pos: Button,
         /// Negative button, when pressed down axis value will return -1 if `pos` is not pressed down.
         neg: Button,
+        /// Moves the axis value gradually instead of snapping it to -1, 0 or 1.
+        #[serde(default)]
+        smoothing: Option<AxisSmoothing>,
     },
     /// Represents an analogue axis of a controller.
     Controller {
@@ -23,5 +26,297 @@ pub enum Axis {
         /// Treat input values from -dead_zone to dead_zone as 0,
         /// linearly interpolate remaining ranges.
         dead_zone: f64,
+        /// Shapes the value after the dead zone is removed.
+        #[serde(default)]
+        curve: ResponseCurve,
+        /// Scales the value after the response curve is applied.
+        #[serde(default = "default_sensitivity")]
+        sensitivity: f64,
     },
+    /// Represents the motion of the mouse during the current frame.
+    ///
+    /// Unlike other axes the value is not limited to -1 to 1, it is the raw motion multiplied by
+    /// `sensitivity`. Positive values are to the right and downwards.
+    MouseMotion {
+        /// The direction of the motion.
+        axis: MouseAxis,
+        /// Scales the raw motion.
+        #[serde(default = "default_sensitivity")]
+        sensitivity: f64,
+    },
+    /// Represents the scrolling of the mouse wheel during the current frame.
+    ///
+    /// Unlike other axes the value is not limited to -1 to 1, it is the scrolled amount
+    /// multiplied by `sensitivity`.
+    MouseWheel {
+        /// Whether to use horizontal rather than vertical scrolling.
+        #[serde(default)]
+        horizontal: bool,
+        /// Scales the scrolled amount.
+        #[serde(default = "default_sensitivity")]
+        sensitivity: f64,
+    },
+    /// Represents two fingers pinching on a touch screen during the current frame.
+    ///
+    /// The value is the relative change of the distance between the fingers multiplied by
+    /// `sensitivity`, positive when the fingers move apart.
+    Pinch {
+        /// Scales the relative change of distance.
+        #[serde(default = "default_sensitivity")]
+        sensitivity: f64,
+    },
+}
+
+impl Axis {
+    /// Returns true if the value of the axis is a change during the current frame, like mouse
+    /// motion, rather than a state held across frames, like the position of a controller stick.
+    ///
+    /// The values of relative axes already depend on the frame time, so they should not be
+    /// multiplied by it when they are used as a speed.
+    pub fn is_relative(&self) -> bool {
+        match *self {
+            Axis::MouseMotion { .. } | Axis::MouseWheel { .. } | Axis::Pinch { .. } => true,
+            Axis::Emulated { .. } | Axis::Controller { .. } => false,
+        }
+    }
+}
+
+/// A direction of mouse motion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MouseAxis {
+    /// Horizontal motion.
+    X,
+    /// Vertical motion.
+    Y,
+}
+
+/// Represents a two dimensional input, like a stick, with each component from -1 to 1.
+/// Retrieve the value of this with
+/// [axis_2d_value](struct.InputHandler.html#method.axis_2d_value).
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub enum Axis2D {
+    /// Represents an emulated stick made up of four digital inputs, like the WASD keys.
+    ///
+    /// Diagonals are normalized, so the length of the value never exceeds 1.
+    Emulated {
+        /// Button moving the value up, towards positive y.
+        up: Button,
+        /// Button moving the value down, towards negative y.
+        down: Button,
+        /// Button moving the value left, towards negative x.
+        left: Button,
+        /// Button moving the value right, towards positive x.
+        right: Button,
+        /// Moves each component gradually instead of snapping it to -1, 0 or 1.
+        #[serde(default)]
+        smoothing: Option<AxisSmoothing>,
+    },
+    /// Represents a stick of a controller.
+    Controller {
+        /// A number representing specific controller, assigned and reused in order of connection.
+        controller_id: u32,
+        /// The controller axis of the x component.
+        x: ControllerAxis,
+        /// The controller axis of the y component.
+        y: ControllerAxis,
+        /// Whether or not to multiply the x component by -1.
+        #[serde(default)]
+        invert_x: bool,
+        /// Whether or not to multiply the y component by -1.
+        #[serde(default)]
+        invert_y: bool,
+        /// Treat sticks moved less than `dead_zone` from the center as centered,
+        /// regardless of the direction they are moved in.
+        #[serde(default)]
+        dead_zone: f64,
+        /// Shapes the length of the value after the dead zone is removed.
+        #[serde(default)]
+        curve: ResponseCurve,
+        /// Scales the value after the response curve is applied.
+        #[serde(default = "default_sensitivity")]
+        sensitivity: f64,
+    },
+}
+
+fn default_sensitivity() -> f64 {
+    1.0
+}
+
+/// Maps the magnitude of an analog input, from 0 to 1, to the magnitude of the axis value.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub enum ResponseCurve {
+    /// The magnitude is not changed.
+    Linear,
+    /// The magnitude is raised to the given power, making small movements more precise
+    /// for exponents above 1.
+    Exponential(f64),
+    /// The magnitude is linearly interpolated between `(input, output)` points,
+    /// sorted by input. The curve implicitly starts at `(0, 0)` and ends at `(1, 1)`.
+    Points(Vec<(f64, f64)>),
+}
+
+impl Default for ResponseCurve {
+    fn default() -> Self {
+        ResponseCurve::Linear
+    }
+}
+
+impl ResponseCurve {
+    /// Applies the curve to a magnitude from 0 to 1.
+    pub fn apply(&self, magnitude: f64) -> f64 {
+        let magnitude = magnitude.max(0.0).min(1.0);
+        match *self {
+            ResponseCurve::Linear => magnitude,
+            ResponseCurve::Exponential(exponent) => magnitude.powf(exponent),
+            ResponseCurve::Points(ref points) => {
+                let mut prev = (0.0, 0.0);
+                for &point in points.iter().chain(Some(&(1.0, 1.0))) {
+                    if magnitude <= point.0 {
+                        let range = point.0 - prev.0;
+                        if range <= 0.0 {
+                            return point.1;
+                        }
+                        let t = (magnitude - prev.0) / range;
+                        return prev.1 + (point.1 - prev.1) * t;
+                    }
+                    prev = point;
+                }
+                prev.1
+            }
+        }
+    }
+
+    /// Removes the dead zone from a signed value, then applies the curve and sensitivity.
+    pub(crate) fn process(&self, value: f64, dead_zone: f64, sensitivity: f64) -> f64 {
+        let magnitude = value.abs().min(1.0);
+        if magnitude <= dead_zone {
+            return 0.0;
+        }
+        let magnitude = (magnitude - dead_zone) / (1.0 - dead_zone);
+        value.signum() * self.apply(magnitude) * sensitivity
+    }
+}
+
+/// Gravity and snap settings moving emulated axis values gradually.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AxisSmoothing {
+    /// Speed in units per second at which the value moves towards a pressed direction.
+    pub sensitivity: f64,
+    /// Speed in units per second at which the value falls back to 0 while no direction
+    /// is pressed.
+    pub gravity: f64,
+    /// Whether the value jumps to 0 when the opposite direction is pressed.
+    #[serde(default)]
+    pub snap: bool,
+}
+
+impl AxisSmoothing {
+    /// Moves a value towards the target value over `delta` seconds.
+    pub fn step(&self, value: f64, target: f64, delta: f64) -> f64 {
+        let value = if self.snap && value * target < 0.0 {
+            0.0
+        } else {
+            value
+        };
+        let speed = if target == 0.0 {
+            self.gravity
+        } else {
+            self.sensitivity
+        };
+        let max_step = speed * delta;
+        if (target - value).abs() <= max_step {
+            target
+        } else {
+            value + max_step * (target - value).signum()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::shrev::EventChannel;
+
+    use crate::{InputHandler, RawInput};
+
+    use super::*;
+
+    #[test]
+    fn response_curve_points() {
+        let curve = ResponseCurve::Points(vec![(0.5, 0.25)]);
+        assert_eq!(0.0, curve.apply(0.0));
+        assert_eq!(0.125, curve.apply(0.25));
+        assert_eq!(0.625, curve.apply(0.75));
+        assert_eq!(1.0, curve.apply(2.0));
+    }
+
+    #[test]
+    fn process_dead_zone() {
+        let curve = ResponseCurve::Exponential(2.0);
+        assert_eq!(0.0, curve.process(-0.25, 0.25, 1.0));
+        assert_eq!(-0.25, curve.process(-0.625, 0.25, 1.0));
+        assert_eq!(0.5, curve.process(0.625, 0.25, 2.0));
+    }
+
+    #[test]
+    fn smoothing_snaps_and_falls_back() {
+        let smoothing = AxisSmoothing {
+            sensitivity: 4.0,
+            gravity: 2.0,
+            snap: true,
+        };
+        assert_eq!(0.5, smoothing.step(0.0, 1.0, 0.125));
+        assert_eq!(1.0, smoothing.step(0.5, 1.0, 1.0));
+        assert_eq!(0.75, smoothing.step(1.0, 0.0, 0.125));
+        assert_eq!(-0.5, smoothing.step(0.75, -1.0, 0.125));
+    }
+
+    #[test]
+    fn mouse_axes_last_one_frame() {
+        let mut handler = InputHandler::<String, String>::new();
+        handler.bindings.insert_axis(
+            "look".to_string(),
+            Axis::MouseMotion {
+                axis: MouseAxis::Y,
+                sensitivity: 2.0,
+            },
+        );
+        handler.bindings.insert_axis(
+            "zoom".to_string(),
+            Axis::MouseWheel {
+                horizontal: false,
+                sensitivity: 0.5,
+            },
+        );
+        let mut events = EventChannel::new();
+
+        handler.start_frame(0.0, &mut events);
+        handler.send_raw_input(
+            &RawInput::MouseMoved {
+                delta_x: 1.0,
+                delta_y: 3.0,
+            },
+            &mut events,
+        );
+        handler.send_raw_input(
+            &RawInput::MouseMoved {
+                delta_x: 0.0,
+                delta_y: -1.0,
+            },
+            &mut events,
+        );
+        handler.send_raw_input(
+            &RawInput::MouseWheelMoved {
+                delta_x: 4.0,
+                delta_y: 2.0,
+            },
+            &mut events,
+        );
+        assert_eq!(Some(4.0), handler.axis_value("look"));
+        assert_eq!(Some(1.0), handler.axis_value("zoom"));
+        assert_eq!(Some(true), handler.axis_is_relative("look"));
+
+        handler.start_frame(0.1, &mut events);
+        assert_eq!(Some(0.0), handler.axis_value("look"));
+        assert_eq!(Some(0.0), handler.axis_value("zoom"));
+    }
 }
@@ -5,11 +5,12 @@ use std::{borrow::Borrow, hash::Hash};
 use fnv::FnvHashMap as HashMap;
 use smallvec::SmallVec;
 
-use super::{Axis, Button};
+use super::{ActionTrigger, Axis, Axis2D, Button};
 
 /// Used for saving and loading input settings.
 ///
 /// An action can either be a single button or a combination of them.
+/// Triggers fire when actions are held, tapped or pressed in sequence, see `ActionTrigger`.
 #[derive(Derivative, Serialize, Deserialize, Clone)]
 #[derivative(Default(bound = ""))]
 pub struct Bindings<AX, AC>
@@ -18,7 +19,11 @@ where
     AC: Hash + Eq,
 {
     pub(super) axes: HashMap<AX, Axis>,
+    #[serde(default)]
+    pub(super) axes_2d: HashMap<AX, Axis2D>,
     pub(super) actions: HashMap<AC, SmallVec<[SmallVec<[Button; 2]>; 4]>>,
+    #[serde(default)]
+    pub(super) triggers: HashMap<AC, ActionTrigger<AC>>,
 }
 
 impl<AX, AC> Bindings<AX, AC>
@@ -66,6 +71,35 @@ where
         self.axes.keys().cloned().collect::<Vec<AX>>()
     }
 
+    /// Assign a 2D axis to an ID value
+    ///
+    /// This will insert a new 2D axis if no entry for this id exists.
+    /// If one does exist this will replace the 2D axis at that id and return it.
+    pub fn insert_axis_2d<A: Into<AX>>(&mut self, id: A, axis: Axis2D) -> Option<Axis2D> {
+        self.axes_2d.insert(id.into(), axis)
+    }
+
+    /// Removes a 2D axis, this will return the removed 2D axis if successful.
+    pub fn remove_axis_2d<A: Hash + Eq + ?Sized>(&mut self, id: &A) -> Option<Axis2D>
+    where
+        AX: Borrow<A>,
+    {
+        self.axes_2d.remove(id)
+    }
+
+    /// Returns a reference to a 2D axis.
+    pub fn axis_2d<A: Hash + Eq + ?Sized>(&self, id: &A) -> Option<&Axis2D>
+    where
+        AX: Borrow<A>,
+    {
+        self.axes_2d.get(id)
+    }
+
+    /// Gets a list of all 2D axes
+    pub fn axes_2d(&self) -> Vec<AX> {
+        self.axes_2d.keys().cloned().collect::<Vec<AX>>()
+    }
+
     /// Add a button or button combination to an action.
     ///
     /// This will insert a new binding between this action and the button(s).
@@ -127,4 +161,37 @@ where
     pub fn actions(&self) -> Vec<AC> {
         self.actions.keys().cloned().collect::<Vec<AC>>()
     }
+
+    /// Assign a trigger to an ID value.
+    ///
+    /// This will insert a new trigger if no entry for this id exists.
+    /// If one does exist this will replace the trigger at that id and return it.
+    pub fn insert_trigger<A: Into<AC>>(
+        &mut self,
+        id: A,
+        trigger: ActionTrigger<AC>,
+    ) -> Option<ActionTrigger<AC>> {
+        self.triggers.insert(id.into(), trigger)
+    }
+
+    /// Removes a trigger, this will return the removed trigger if successful.
+    pub fn remove_trigger<T: Hash + Eq + ?Sized>(&mut self, id: &T) -> Option<ActionTrigger<AC>>
+    where
+        AC: Borrow<T>,
+    {
+        self.triggers.remove(id)
+    }
+
+    /// Returns a reference to a trigger.
+    pub fn trigger<T: Hash + Eq + ?Sized>(&self, id: &T) -> Option<&ActionTrigger<AC>>
+    where
+        AC: Borrow<T>,
+    {
+        self.triggers.get(id)
+    }
+
+    /// Gets a list of all triggers
+    pub fn triggers(&self) -> Vec<AC> {
+        self.triggers.keys().cloned().collect::<Vec<AC>>()
+    }
 }
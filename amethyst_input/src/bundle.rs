@@ -9,7 +9,7 @@ use amethyst_core::{
     specs::prelude::DispatcherBuilder,
 };
 
-use crate::{Bindings, InputSystem};
+use crate::{Bindings, InputRebindSystem, InputSystem, PlayerInputSystem, PlayerInputs};
 
 #[cfg(feature = "sdl_controller")]
 use crate::sdl_events_system::ControllerMappings;
@@ -38,6 +38,8 @@ where
     AC: Hash + Eq,
 {
     bindings: Option<Bindings<AX, AC>>,
+    players: Option<PlayerInputs<AX, AC>>,
+    rebinding: bool,
     #[cfg(feature = "sdl_controller")]
     controller_mappings: Option<ControllerMappings>,
 }
@@ -67,6 +69,18 @@ where
         Ok(self.with_bindings(Bindings::load_no_fallback(file)?))
     }
 
+    /// Use the provided local players, and add the `PlayerInputSystem` assigning devices to them
+    pub fn with_player_inputs(mut self, players: PlayerInputs<AX, AC>) -> Self {
+        self.players = Some(players);
+        self
+    }
+
+    /// Add the `InputRebindSystem` capturing input for the `InputRebinder`
+    pub fn with_rebinding(mut self) -> Self {
+        self.rebinding = true;
+        self
+    }
+
     /// Load SDL controller mappings from file
     #[cfg(feature = "sdl_controller")]
     pub fn with_sdl_controller_mappings(mut self, mappings: String) -> Self {
@@ -108,6 +122,20 @@ where
             "input_system",
             &[],
         );
+        if self.players.is_some() {
+            builder.add(
+                PlayerInputSystem::<AX, AC>::new(self.players),
+                "player_input_system",
+                &["input_system"],
+            );
+        }
+        if self.rebinding {
+            builder.add(
+                InputRebindSystem::<AX, AC>::new(),
+                "input_rebind_system",
+                &["input_system"],
+            );
+        }
         Ok(())
     }
 }
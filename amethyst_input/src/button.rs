@@ -1,6 +1,6 @@
 use winit::{MouseButton, VirtualKeyCode};
 
-use super::{controller::ControllerButton, scroll_direction::ScrollDirection};
+use super::{controller::ControllerButton, scroll_direction::ScrollDirection, touch::Gesture};
 
 /// A Button is any kind of digital input that the engine supports.
 #[derive(Eq, PartialEq, Debug, Copy, Clone, Serialize, Deserialize)]
@@ -23,6 +23,9 @@ pub enum Button {
     /// A tuple of sequential controller_id in order of connection
     /// and specific type of used controller button.
     Controller(u32, ControllerButton),
+
+    /// Touch gesture, down during the frame it is recognized in.
+    Gesture(Gesture),
 }
 
 impl From<VirtualKeyCode> for Button {
@@ -0,0 +1,266 @@
+//! Named input contexts, stacked on top of the bindings of the `InputHandler`.
+
+use std::hash::Hash;
+
+use smallvec::SmallVec;
+
+use crate::{Axis, Axis2D, Bindings, Button, ControllerAxis, MouseAxis};
+
+/// How an `InputContext` hides input from the contexts below it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InputConsumption {
+    /// All input passes through to lower contexts.
+    None,
+    /// Buttons and controller axes bound in the context are hidden from lower contexts.
+    Bound,
+    /// All input is hidden from lower contexts, e.g. while typing into a text field.
+    All,
+}
+
+impl Default for InputConsumption {
+    fn default() -> Self {
+        InputConsumption::Bound
+    }
+}
+
+/// A named set of bindings which can be pushed onto and popped from the context stack of the
+/// `InputHandler` at runtime.
+///
+/// Actions and axes are resolved from the top of the stack down: the highest context binding an
+/// action or axis decides its value, and the bindings of the `InputHandler` form the bottom of
+/// the stack. Contexts with a higher `priority` are always above contexts with a lower one,
+/// contexts of equal priority are ordered by when they were pushed.
+///
+/// ### Example:
+///
+/// ```rust,ignore
+/// let menu = InputContext::new(menu_bindings)
+///     .with_priority(10)
+///     .with_consumption(InputConsumption::All);
+/// input_handler.insert_context("menu", menu);
+///
+/// // in `State::on_start` of the menu state
+/// data.world
+///     .write_resource::<InputHandler<String, String>>()
+///     .push_context("menu");
+/// ```
+#[derive(Derivative, Serialize, Deserialize, Clone)]
+#[derivative(Default(bound = ""))]
+pub struct InputContext<AX, AC>
+where
+    AX: Hash + Eq,
+    AC: Hash + Eq,
+{
+    /// The bindings of the context.
+    #[serde(default)]
+    pub bindings: Bindings<AX, AC>,
+    /// The priority of the context, higher priority contexts are above lower ones.
+    #[serde(default)]
+    pub priority: i32,
+    /// How the context hides input from the contexts below it.
+    #[serde(default)]
+    pub consumption: InputConsumption,
+}
+
+impl<AX, AC> InputContext<AX, AC>
+where
+    AX: Hash + Eq,
+    AC: Hash + Eq,
+{
+    /// Creates a context with the given bindings, priority `0`, consuming bound input.
+    pub fn new(bindings: Bindings<AX, AC>) -> Self {
+        InputContext {
+            bindings,
+            priority: 0,
+            consumption: InputConsumption::default(),
+        }
+    }
+
+    /// Sets the priority of the context.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Sets how the context hides input from the contexts below it.
+    pub fn with_consumption(mut self, consumption: InputConsumption) -> Self {
+        self.consumption = consumption;
+        self
+    }
+}
+
+/// Input consumed by the contexts above the one being evaluated.
+#[derive(Debug, Default)]
+pub(crate) struct Consumed {
+    all: bool,
+    buttons: SmallVec<[Button; 16]>,
+    axes: SmallVec<[(u32, ControllerAxis); 8]>,
+    mouse_motion: SmallVec<[MouseAxis; 2]>,
+    /// Whether horizontal scrolling is consumed, for each consumed direction.
+    mouse_wheel: SmallVec<[bool; 2]>,
+    pinch: bool,
+}
+
+impl Consumed {
+    /// Hides the input consumed by a context from the contexts below it.
+    pub(crate) fn consume<AX, AC>(&mut self, context: &InputContext<AX, AC>)
+    where
+        AX: Hash + Eq,
+        AC: Hash + Eq,
+    {
+        match context.consumption {
+            InputConsumption::None => {}
+            InputConsumption::All => self.all = true,
+            InputConsumption::Bound => {
+                let bindings = &context.bindings;
+                for combinations in bindings.actions.values() {
+                    self.buttons
+                        .extend(combinations.iter().flat_map(|c| c.iter().cloned()));
+                }
+                for axis in bindings.axes.values() {
+                    match *axis {
+                        Axis::Emulated { pos, neg, .. } => {
+                            self.buttons.push(pos);
+                            self.buttons.push(neg);
+                        }
+                        Axis::Controller {
+                            controller_id,
+                            axis,
+                            ..
+                        } => self.axes.push((controller_id, axis)),
+                        Axis::MouseMotion { axis, .. } => self.mouse_motion.push(axis),
+                        Axis::MouseWheel { horizontal, .. } => self.mouse_wheel.push(horizontal),
+                        Axis::Pinch { .. } => self.pinch = true,
+                    }
+                }
+                for axis in bindings.axes_2d.values() {
+                    match *axis {
+                        Axis2D::Emulated {
+                            up,
+                            down,
+                            left,
+                            right,
+                            ..
+                        } => self.buttons.extend_from_slice(&[up, down, left, right]),
+                        Axis2D::Controller {
+                            controller_id,
+                            x,
+                            y,
+                            ..
+                        } => {
+                            self.axes.push((controller_id, x));
+                            self.axes.push((controller_id, y));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns true if the button is hidden.
+    pub(crate) fn hides_button(&self, button: Button) -> bool {
+        self.all || self.buttons.contains(&button)
+    }
+
+    /// Returns true if the controller axis is hidden.
+    pub(crate) fn hides_axis(&self, controller_id: u32, axis: ControllerAxis) -> bool {
+        self.all || self.axes.contains(&(controller_id, axis))
+    }
+
+    /// Returns true if the mouse motion in the direction is hidden.
+    pub(crate) fn hides_mouse_motion(&self, axis: MouseAxis) -> bool {
+        self.all || self.mouse_motion.contains(&axis)
+    }
+
+    /// Returns true if horizontal or vertical scrolling is hidden.
+    pub(crate) fn hides_mouse_wheel(&self, horizontal: bool) -> bool {
+        self.all || self.mouse_wheel.contains(&horizontal)
+    }
+
+    /// Returns true if pinching is hidden.
+    pub(crate) fn hides_pinch(&self) -> bool {
+        self.all || self.pinch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::VirtualKeyCode;
+
+    use amethyst_core::shrev::EventChannel;
+
+    use crate::{InputHandler, RawInput};
+
+    use super::*;
+
+    fn press(handler: &mut InputHandler<String, String>, key_code: VirtualKeyCode) {
+        let mut events = EventChannel::new();
+        handler.send_raw_input(
+            &RawInput::KeyPressed {
+                key_code,
+                scancode: 0,
+            },
+            &mut events,
+        );
+    }
+
+    #[test]
+    fn resolve_actions_from_top_context() {
+        let mut handler = InputHandler::<String, String>::new();
+        handler
+            .bindings
+            .insert_action_binding("jump".to_string(), vec![Button::Key(VirtualKeyCode::Space)]);
+        handler
+            .bindings
+            .insert_action_binding("fire".to_string(), vec![Button::Key(VirtualKeyCode::F)]);
+        let mut bindings = Bindings::new();
+        bindings.insert_action_binding(
+            "confirm".to_string(),
+            vec![Button::Key(VirtualKeyCode::Space)],
+        );
+        handler.insert_context("menu", InputContext::new(bindings));
+
+        press(&mut handler, VirtualKeyCode::Space);
+        press(&mut handler, VirtualKeyCode::F);
+        assert_eq!(Some(true), handler.action_is_down("jump"));
+        assert_eq!(None, handler.action_is_down("confirm"));
+
+        assert!(handler.push_context("menu"));
+        assert_eq!(Some(false), handler.action_is_down("jump"));
+        assert_eq!(Some(true), handler.action_is_down("confirm"));
+        assert_eq!(Some(true), handler.action_is_down("fire"));
+
+        handler.context_mut("menu").unwrap().consumption = InputConsumption::All;
+        assert_eq!(Some(false), handler.action_is_down("fire"));
+
+        assert_eq!(Some("menu".to_string()), handler.pop_context());
+        assert_eq!(Some(true), handler.action_is_down("jump"));
+    }
+
+    #[test]
+    fn order_contexts_by_priority() {
+        let mut handler = InputHandler::<String, String>::new();
+        handler.insert_context("hud", InputContext::new(Bindings::new()).with_priority(10));
+        handler.insert_context("menu", InputContext::new(Bindings::new()));
+        handler.insert_context("dialog", InputContext::new(Bindings::new()));
+        assert!(handler.push_context("hud"));
+        assert!(handler.push_context("menu"));
+        assert!(handler.push_context("dialog"));
+        assert_eq!(
+            vec!["hud", "dialog", "menu"],
+            handler.active_contexts().collect::<Vec<_>>()
+        );
+
+        handler.context_mut("dialog").unwrap().priority = -1;
+        handler.start_frame(0.0, &mut EventChannel::new());
+        assert_eq!(
+            vec!["hud", "menu", "dialog"],
+            handler.active_contexts().collect::<Vec<_>>()
+        );
+
+        assert_eq!(Some("hud".to_string()), handler.pop_context());
+        assert_eq!(Some("menu".to_string()), handler.pop_context());
+        assert_eq!(Some("dialog".to_string()), handler.pop_context());
+        assert_eq!(None, handler.pop_context());
+    }
+}
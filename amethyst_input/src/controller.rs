@@ -56,25 +56,43 @@ pub enum ControllerButton {
     Guide,
 }
 
+/// Raw controller events, fed into the `InputHandler` with `send_controller_event`.
+///
+/// `which` is the index of the controller used by the controller implementation, which the
+/// `InputHandler` maps to its own controller ids.
 #[derive(PartialEq, Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum ControllerEvent {
+    /// A controller axis moved to a new value, in the range `[-1.0, 1.0]`.
     ControllerAxisMoved {
+        /// The index of the controller.
         which: u32,
+        /// The axis that moved.
         axis: ControllerAxis,
+        /// The new value of the axis.
         value: f64,
     },
+    /// A controller button was pressed.
     ControllerButtonPressed {
+        /// The index of the controller.
         which: u32,
+        /// The button that was pressed.
         button: ControllerButton,
     },
+    /// A controller button was released.
     ControllerButtonReleased {
+        /// The index of the controller.
         which: u32,
+        /// The button that was released.
         button: ControllerButton,
     },
+    /// A controller was disconnected.
     ControllerDisconnected {
+        /// The index of the controller.
         which: u32,
     },
+    /// A controller was connected.
     ControllerConnected {
+        /// The index of the controller.
         which: u32,
     },
 }
@@ -4,6 +4,7 @@ use super::{
     button::Button,
     controller::{ControllerAxis, ControllerButton},
     scroll_direction::ScrollDirection,
+    touch::Gesture,
 };
 
 /// Events generated by the input system
@@ -52,6 +53,39 @@ pub enum InputEvent<T> {
     },
     /// The mousewheel was moved in either direction
     MouseWheelMoved(ScrollDirection),
+    /// A finger touched the screen.
+    TouchStarted {
+        /// The id of the touch.
+        id: u64,
+        /// The horizontal position of the touch in pixels.
+        x: f64,
+        /// The vertical position of the touch in pixels.
+        y: f64,
+    },
+    /// A finger moved on the screen.
+    TouchMoved {
+        /// The id of the touch.
+        id: u64,
+        /// The horizontal position of the touch in pixels.
+        x: f64,
+        /// The vertical position of the touch in pixels.
+        y: f64,
+        /// The amount the touch moved horizontally in pixels.
+        delta_x: f64,
+        /// The amount the touch moved vertically in pixels.
+        delta_y: f64,
+    },
+    /// A finger was lifted from the screen, or the touch was cancelled.
+    TouchEnded {
+        /// The id of the touch.
+        id: u64,
+        /// The horizontal position of the touch in pixels.
+        x: f64,
+        /// The vertical position of the touch in pixels.
+        y: f64,
+    },
+    /// A touch gesture was recognized.
+    GestureRecognized(Gesture),
     /// A controller Axis was moved.
     ControllerAxisMoved {
         /// The id for the controller whose axis moved.
@@ -85,16 +119,19 @@ pub enum InputEvent<T> {
         /// The id for the controller disconnected.
         which: u32,
     },
-    /// The associated action had any related button or combination pressed.
+    /// The associated action was pressed, sent once when any of its bindings goes down while
+    /// none was down before.
     ///
     /// If a combination is bound to an action, it will be pressed
     /// if all buttons within are pressed.
     ActionPressed(T),
-    /// The associated action had any related button or combination released.
+    /// The associated action was released, sent once when none of its bindings is down anymore.
     ///
     /// If a combination is bound to an action, it will be released
     /// if any of the buttons within is released while all others are pressed.
     ActionReleased(T),
     /// The associated action has its mouse wheel moved.
     ActionWheelMoved(T),
+    /// The `ActionTrigger` with the associated id fired.
+    ActionTriggered(T),
 }
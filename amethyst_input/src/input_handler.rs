@@ -2,18 +2,21 @@
 
 use std::{borrow::Borrow, hash::Hash};
 
+use fnv::{FnvHashMap as HashMap, FnvHashSet as HashSet};
 use smallvec::SmallVec;
-use winit::{
-    dpi::LogicalPosition, DeviceEvent, ElementState, Event, KeyboardInput, MouseButton,
-    MouseScrollDelta, VirtualKeyCode, WindowEvent,
-};
+use winit::{Event, MouseButton, TouchPhase, VirtualKeyCode};
 
-use amethyst_core::shrev::EventChannel;
+use amethyst_core::{nalgebra::Vector2, shrev::EventChannel};
 
 use super::{
+    context::{Consumed, InputContext},
     controller::{ControllerButton, ControllerEvent},
     event::InputEvent::{self, *},
+    player::{self, InputDevice},
+    recording::RawInput,
     scroll_direction::ScrollDirection,
+    touch::{self, Gesture, GestureSettings, TouchPoint},
+    trigger::TriggerState,
     *,
 };
 
@@ -30,6 +33,8 @@ where
 {
     /// Maps inputs to actions and axes.
     pub bindings: Bindings<AX, AC>,
+    /// Thresholds used to recognize touch gestures.
+    pub gesture_settings: GestureSettings,
     /// Encodes the VirtualKeyCode and corresponding scancode.
     pressed_keys: SmallVec<[(VirtualKeyCode, u32); 12]>,
     pressed_mouse_buttons: SmallVec<[MouseButton; 12]>,
@@ -41,6 +46,40 @@ where
     /// while second is the ID used by incoming events.
     connected_controllers: SmallVec<[(u32, u32); 8]>,
     mouse_position: Option<(f64, f64)>,
+    /// Mouse motion during the current frame.
+    mouse_motion: (f64, f64),
+    /// Mouse wheel scrolling during the current frame.
+    mouse_wheel: (f64, f64),
+    /// Touches currently on the screen, in the order they started.
+    touches: SmallVec<[TouchPoint; 10]>,
+    /// Whether more than one touch was on the screen since the screen was last untouched.
+    multi_touch: bool,
+    /// Gestures recognized during the current frame.
+    gestures: SmallVec<[Gesture; 4]>,
+    /// Relative change of the distance between two pinching touches during the current frame.
+    pinch: f64,
+    /// The time of the current frame in seconds, used to time action triggers.
+    time: f64,
+    /// Actions that are down, and the time they were pressed at.
+    actions_down: HashMap<AC, f64>,
+    /// Actions pressed during the current frame.
+    actions_pressed: Vec<AC>,
+    /// Actions released during the current frame.
+    actions_released: Vec<AC>,
+    /// Triggers fired during the current frame.
+    triggered: Vec<AC>,
+    trigger_states: HashMap<AC, TriggerState>,
+    /// Registered input contexts.
+    contexts: HashMap<String, InputContext<AX, AC>>,
+    /// Names of the active input contexts from the bottom of the stack up, ordered by priority
+    /// and then by the order they were pushed in.
+    active_contexts: Vec<String>,
+    /// Current values of axes with smoothing.
+    smoothed_axes: HashMap<AX, f64>,
+    /// Current values of 2D axes with smoothing.
+    smoothed_axes_2d: HashMap<AX, Vector2<f64>>,
+    /// Input received after the `InputSystem` ran, processed at the start of the next frame.
+    queued_input: Vec<RawInput>,
 }
 
 impl<AX, AC> InputHandler<AX, AC>
@@ -59,6 +98,52 @@ where
     AX: Hash + Eq + Clone + Send + Sync + 'static,
     AC: Hash + Eq + Clone + Send + Sync + 'static,
 {
+    /// Starts a new frame at `time` seconds.
+    ///
+    /// This clears the actions pressed and released during the previous frame, detects actions
+    /// changed by pushing or popping input contexts, fires the triggers of held actions and moves
+    /// smoothed axes towards their current input.
+    /// The `InputSystem` calls this once per frame, before sending the events of the frame.
+    pub fn start_frame(&mut self, time: f64, event_handler: &mut EventChannel<InputEvent<AC>>) {
+        let delta = (time - self.time).max(0.0);
+        self.time = time;
+        self.mouse_motion = (0.0, 0.0);
+        self.mouse_wheel = (0.0, 0.0);
+        self.pinch = 0.0;
+        event_handler.iter_write(
+            self.gestures
+                .drain()
+                .map(|gesture| ButtonReleased(Button::Gesture(gesture))),
+        );
+        self.update_smoothing(delta);
+        self.sort_contexts();
+        self.actions_pressed.clear();
+        self.actions_released.clear();
+        self.triggered.clear();
+        self.update_actions(event_handler);
+        for (id, trigger) in self.bindings.triggers.iter() {
+            let state = self.trigger_states.entry(id.clone()).or_default();
+            if trigger.on_frame(&self.actions_down, time, state) {
+                self.triggered.push(id.clone());
+                event_handler.single_write(ActionTriggered(id.clone()));
+            }
+        }
+    }
+
+    /// Queues input to be processed by the `InputSystem` at the start of the next frame.
+    ///
+    /// Systems running after the `InputSystem` should queue the input they receive instead of
+    /// sending it, such as the thread local `SdlEventsSystem`. Otherwise the next `start_frame`
+    /// clears the actions the input pressed or released, before any system saw them.
+    pub fn queue_raw_input(&mut self, input: RawInput) {
+        self.queued_input.push(input);
+    }
+
+    /// Takes the input queued with `queue_raw_input`.
+    pub(crate) fn take_queued_input(&mut self) -> Vec<RawInput> {
+        ::std::mem::replace(&mut self.queued_input, Vec::new())
+    }
+
     /// Updates the input handler with a new engine event.
     ///
     /// The Amethyst game engine will automatically call this if the InputHandler is attached to
@@ -69,198 +154,127 @@ where
         event_handler: &mut EventChannel<InputEvent<AC>>,
         hidpi: f64,
     ) {
-        match *event {
-            Event::WindowEvent { ref event, .. } => match *event {
-                WindowEvent::ReceivedCharacter(c) => {
-                    event_handler.single_write(KeyTyped(c));
-                }
-                WindowEvent::KeyboardInput {
-                    input:
-                        KeyboardInput {
-                            state: ElementState::Pressed,
-                            virtual_keycode: Some(key_code),
-                            scancode,
-                            ..
-                        },
-                    ..
-                } => {
-                    if self.pressed_keys.iter().all(|&k| k.0 != key_code) {
-                        self.pressed_keys.push((key_code, scancode));
-                        event_handler.iter_write(
-                            [
-                                KeyPressed { key_code, scancode },
-                                ButtonPressed(Button::Key(key_code)),
-                                ButtonPressed(Button::ScanCode(scancode)),
-                            ]
-                            .iter()
-                            .cloned(),
-                        );
-                        for (action, combinations) in self.bindings.actions.iter() {
-                            for combination in combinations.iter().filter(|c| {
-                                c.contains(&Button::Key(key_code))
-                                    || c.contains(&Button::ScanCode(scancode))
-                            }) {
-                                if combination
-                                    .iter()
-                                    .all(|button| self.button_is_down(*button))
-                                {
-                                    event_handler.single_write(ActionPressed(action.clone()));
-                                }
-                            }
-                        }
-                    }
-                }
-                WindowEvent::KeyboardInput {
-                    input:
-                        KeyboardInput {
-                            state: ElementState::Released,
-                            virtual_keycode: Some(key_code),
-                            scancode,
-                            ..
-                        },
-                    ..
-                } => {
-                    let index = self.pressed_keys.iter().position(|&k| k.0 == key_code);
-                    if let Some(i) = index {
-                        self.pressed_keys.swap_remove(i);
-                        event_handler.iter_write(
-                            [
-                                KeyReleased { key_code, scancode },
-                                ButtonReleased(Button::Key(key_code)),
-                                ButtonReleased(Button::ScanCode(scancode)),
-                            ]
-                            .iter()
-                            .cloned(),
-                        );
-                        for (action, combinations) in self.bindings.actions.iter() {
-                            for combination in combinations {
-                                if combination.contains(&Button::Key(key_code))
-                                    && combination
-                                        .iter()
-                                        .filter(|b| b != &&Button::Key(key_code))
-                                        .all(|b| self.button_is_down(*b))
-                                {
-                                    event_handler.single_write(ActionReleased(action.clone()));
-                                }
-                                if combination.contains(&Button::ScanCode(scancode))
-                                    && combination
-                                        .iter()
-                                        .filter(|b| b != &&Button::ScanCode(scancode))
-                                        .all(|b| self.button_is_down(*b))
-                                {
-                                    event_handler.single_write(ActionReleased(action.clone()));
-                                }
-                            }
-                        }
-                    }
-                }
-                WindowEvent::MouseInput {
-                    state: ElementState::Pressed,
-                    button,
-                    ..
-                } => {
-                    let mouse_button = button;
-                    if self
-                        .pressed_mouse_buttons
+        if let Some(input) = RawInput::from_event(event, hidpi) {
+            self.send_raw_input(&input, event_handler);
+        }
+    }
+
+    /// Updates the input handler with a raw input, e.g. one played back from an
+    /// `InputRecording`.
+    pub fn send_raw_input(
+        &mut self,
+        input: &RawInput,
+        event_handler: &mut EventChannel<InputEvent<AC>>,
+    ) {
+        match *input {
+            RawInput::KeyTyped(c) => {
+                event_handler.single_write(KeyTyped(c));
+            }
+            RawInput::KeyPressed { key_code, scancode } => {
+                if self.pressed_keys.iter().all(|&k| k.0 != key_code) {
+                    self.pressed_keys.push((key_code, scancode));
+                    event_handler.iter_write(
+                        [
+                            KeyPressed { key_code, scancode },
+                            ButtonPressed(Button::Key(key_code)),
+                            ButtonPressed(Button::ScanCode(scancode)),
+                        ]
                         .iter()
-                        .all(|&b| b != mouse_button)
-                    {
-                        self.pressed_mouse_buttons.push(mouse_button);
-                        event_handler.iter_write(
-                            [
-                                MouseButtonPressed(mouse_button),
-                                ButtonPressed(Button::Mouse(mouse_button)),
-                            ]
-                            .iter()
-                            .cloned(),
-                        );
-                        for (action, combinations) in self.bindings.actions.iter() {
-                            for combination in combinations
-                                .iter()
-                                .filter(|c| c.contains(&Button::Mouse(mouse_button)))
-                            {
-                                if combination
-                                    .iter()
-                                    .all(|button| self.button_is_down(*button))
-                                {
-                                    event_handler.single_write(ActionPressed(action.clone()));
-                                }
-                            }
-                        }
-                    }
+                        .cloned(),
+                    );
                 }
-                WindowEvent::MouseInput {
-                    state: ElementState::Released,
-                    button,
-                    ..
-                } => {
-                    let mouse_button = button;
-                    let index = self
-                        .pressed_mouse_buttons
+            }
+            RawInput::KeyReleased { key_code, scancode } => {
+                let index = self.pressed_keys.iter().position(|&k| k.0 == key_code);
+                if let Some(i) = index {
+                    self.pressed_keys.swap_remove(i);
+                    event_handler.iter_write(
+                        [
+                            KeyReleased { key_code, scancode },
+                            ButtonReleased(Button::Key(key_code)),
+                            ButtonReleased(Button::ScanCode(scancode)),
+                        ]
                         .iter()
-                        .position(|&b| b == mouse_button);
-                    if let Some(i) = index {
-                        self.pressed_mouse_buttons.swap_remove(i);
-                        event_handler.iter_write(
-                            [
-                                MouseButtonReleased(mouse_button),
-                                ButtonReleased(Button::Mouse(mouse_button)),
-                            ]
-                            .iter()
-                            .cloned(),
-                        );
-                        for (action, combinations) in self.bindings.actions.iter() {
-                            for combination in combinations {
-                                if combination.contains(&Button::Mouse(mouse_button))
-                                    && combination
-                                        .iter()
-                                        .filter(|b| b != &&Button::Mouse(mouse_button))
-                                        .all(|b| self.button_is_down(*b))
-                                {
-                                    event_handler.single_write(ActionReleased(action.clone()));
-                                }
-                            }
-                        }
-                    }
-                }
-                WindowEvent::CursorMoved {
-                    position: LogicalPosition { x, y },
-                    ..
-                } => {
-                    if let Some((old_x, old_y)) = self.mouse_position {
-                        event_handler.single_write(CursorMoved {
-                            delta_x: x * hidpi - old_x,
-                            delta_y: y * hidpi - old_y,
-                        });
-                    }
-                    self.mouse_position = Some((x * hidpi, y * hidpi));
+                        .cloned(),
+                    );
                 }
-                WindowEvent::Focused(false) => {
-                    self.pressed_keys.clear();
-                    self.pressed_mouse_buttons.clear();
-                    self.mouse_position = None;
-                }
-                _ => {}
-            },
-            Event::DeviceEvent { ref event, .. } => match *event {
-                DeviceEvent::MouseMotion {
-                    delta: (delta_x, delta_y),
-                } => {
-                    event_handler.single_write(MouseMoved { delta_x, delta_y });
+            }
+            RawInput::MouseButtonPressed(mouse_button) => {
+                if self
+                    .pressed_mouse_buttons
+                    .iter()
+                    .all(|&b| b != mouse_button)
+                {
+                    self.pressed_mouse_buttons.push(mouse_button);
+                    event_handler.iter_write(
+                        [
+                            MouseButtonPressed(mouse_button),
+                            ButtonPressed(Button::Mouse(mouse_button)),
+                        ]
+                        .iter()
+                        .cloned(),
+                    );
                 }
-                DeviceEvent::MouseWheel {
-                    delta: MouseScrollDelta::LineDelta(delta_x, delta_y),
-                } => {
-                    self.invoke_wheel_moved(delta_x.into(), delta_y.into(), event_handler);
+            }
+            RawInput::MouseButtonReleased(mouse_button) => {
+                let index = self
+                    .pressed_mouse_buttons
+                    .iter()
+                    .position(|&b| b == mouse_button);
+                if let Some(i) = index {
+                    self.pressed_mouse_buttons.swap_remove(i);
+                    event_handler.iter_write(
+                        [
+                            MouseButtonReleased(mouse_button),
+                            ButtonReleased(Button::Mouse(mouse_button)),
+                        ]
+                        .iter()
+                        .cloned(),
+                    );
                 }
-                DeviceEvent::MouseWheel {
-                    delta: MouseScrollDelta::PixelDelta(LogicalPosition { x, y }),
-                } => {
-                    self.invoke_wheel_moved(x, y, event_handler);
+            }
+            RawInput::CursorMoved { x, y } => {
+                if let Some((old_x, old_y)) = self.mouse_position {
+                    event_handler.single_write(CursorMoved {
+                        delta_x: x - old_x,
+                        delta_y: y - old_y,
+                    });
                 }
-                _ => {}
-            },
-            _ => {}
+                self.mouse_position = Some((x, y));
+            }
+            RawInput::Touch { id, phase, x, y } => {
+                self.send_touch(id, phase, (x, y), event_handler);
+            }
+            RawInput::FocusLost => {
+                self.pressed_keys.clear();
+                self.pressed_mouse_buttons.clear();
+                self.mouse_position = None;
+                self.touches.clear();
+                self.multi_touch = false;
+            }
+            RawInput::MouseMoved { delta_x, delta_y } => {
+                self.mouse_motion.0 += delta_x;
+                self.mouse_motion.1 += delta_y;
+                event_handler.single_write(MouseMoved { delta_x, delta_y });
+            }
+            RawInput::MouseWheelMoved { delta_x, delta_y } => {
+                self.mouse_wheel.0 += delta_x;
+                self.mouse_wheel.1 += delta_y;
+                self.invoke_wheel_moved(delta_x, delta_y, event_handler);
+            }
+            RawInput::Controller(ref event) => {
+                self.send_controller_event(event, event_handler);
+            }
+        }
+        match *input {
+            // Text, motion and scrolling do not change which buttons are down, and controller
+            // events update the actions themselves.
+            RawInput::KeyTyped(_)
+            | RawInput::CursorMoved { .. }
+            | RawInput::MouseMoved { .. }
+            | RawInput::MouseWheelMoved { .. }
+            | RawInput::Controller(_) => {}
+            _ => self.update_actions(event_handler),
         }
     }
 
@@ -306,19 +320,6 @@ where
                             .iter()
                             .cloned(),
                         );
-                        for (action, combinations) in self.bindings.actions.iter() {
-                            for combination in combinations
-                                .iter()
-                                .filter(|c| c.contains(&Button::Controller(controller_id, button)))
-                            {
-                                if combination
-                                    .iter()
-                                    .all(|button| self.button_is_down(*button))
-                                {
-                                    event_handler.single_write(ActionPressed(action.clone()));
-                                }
-                            }
-                        }
                     }
                 }
             }
@@ -338,20 +339,6 @@ where
                             .iter()
                             .cloned(),
                         );
-                        for (action, combinations) in self.bindings.actions.iter() {
-                            for combination in combinations {
-                                if combination.contains(&Button::Controller(controller_id, button))
-                                    && combination
-                                        .iter()
-                                        .filter(|b| {
-                                            b != &&Button::Controller(controller_id, button)
-                                        })
-                                        .all(|b| self.button_is_down(*b))
-                                {
-                                    event_handler.single_write(ActionReleased(action.clone()));
-                                }
-                            }
-                        }
                     }
                 }
             }
@@ -382,6 +369,10 @@ where
                 }
             }
         }
+        if let ControllerAxisMoved { .. } = *event {
+            return;
+        }
+        self.update_actions(event_handler);
     }
 
     /// Returns an iterator over all keys that are down.
@@ -456,6 +447,27 @@ where
         self.mouse_position
     }
 
+    /// Returns an iterator over the touches currently on the screen, in the order they started.
+    pub fn touches(&self) -> impl Iterator<Item = &TouchPoint> {
+        self.touches.iter()
+    }
+
+    /// Returns the touch with the given id, if it is on the screen.
+    pub fn touch(&self, id: u64) -> Option<&TouchPoint> {
+        self.touches.iter().find(|touch| touch.id == id)
+    }
+
+    /// Returns the gestures recognized during the current frame.
+    pub fn gestures(&self) -> impl Iterator<Item = Gesture> + '_ {
+        self.gestures.iter().cloned()
+    }
+
+    /// Returns the relative change of the distance between two pinching touches during the
+    /// current frame, positive when they move apart.
+    pub fn pinch_delta(&self) -> f64 {
+        self.pinch
+    }
+
     /// Returns an iterator over all buttons that are down.
     pub fn buttons_that_are_down<'a>(&self) -> impl Iterator<Item = Button> + '_ {
         let mouse_buttons = self
@@ -478,19 +490,109 @@ where
             Button::Mouse(b) => self.mouse_button_is_down(b),
             Button::ScanCode(s) => self.scan_code_is_down(s),
             Button::Controller(g, b) => self.controller_button_is_down(g, b),
+            Button::Gesture(g) => self.gestures.contains(&g),
             _ => false,
         }
     }
 
     /// Returns the value of an axis by the string id, if the id doesn't exist this returns None.
+    ///
+    /// The axis is resolved from the top of the context stack down, see `InputContext`.
     pub fn axis_value<T: Hash + Eq + ?Sized>(&self, id: &T) -> Option<f64>
     where
         AX: Borrow<T>,
     {
-        self.bindings.axes.get(id).map(|a| match *a {
+        let (axis, consumed) = self.resolve_axis(id)?;
+        match *axis {
+            Axis::Emulated {
+                smoothing: Some(_), ..
+            } => Some(self.smoothed_axes.get(id).cloned().unwrap_or(0.0)),
+            _ => Some(self.evaluate_axis(axis, None, Some(&consumed))),
+        }
+    }
+
+    /// Returns true if the axis is relative, see `Axis::is_relative`. If the id doesn't exist
+    /// this returns None.
+    ///
+    /// The axis is resolved from the top of the context stack down, see `InputContext`.
+    pub fn axis_is_relative<T: Hash + Eq + ?Sized>(&self, id: &T) -> Option<bool>
+    where
+        AX: Borrow<T>,
+    {
+        self.resolve_axis(id).map(|(axis, _)| axis.is_relative())
+    }
+
+    /// Returns the value of a 2D axis by its id, if the id doesn't exist this returns None.
+    ///
+    /// The axis is resolved from the top of the context stack down, see `InputContext`.
+    pub fn axis_2d_value<T: Hash + Eq + ?Sized>(&self, id: &T) -> Option<Vector2<f64>>
+    where
+        AX: Borrow<T>,
+    {
+        let (axis, consumed) = self.resolve_axis_2d(id)?;
+        match *axis {
+            Axis2D::Emulated {
+                smoothing: Some(_), ..
+            } => Some(
+                self.smoothed_axes_2d
+                    .get(id)
+                    .cloned()
+                    .unwrap_or_else(Vector2::zeros),
+            ),
+            _ => Some(self.evaluate_axis_2d(axis, None, Some(&consumed))),
+        }
+    }
+
+    /// Finds the binding of an axis on the context stack, with the input consumed above it.
+    fn resolve_axis<T: Hash + Eq + ?Sized>(&self, id: &T) -> Option<(&Axis, Consumed)>
+    where
+        AX: Borrow<T>,
+    {
+        let mut consumed = Consumed::default();
+        for context in self.context_stack() {
+            if let Some(axis) = context.bindings.axes.get(id) {
+                return Some((axis, consumed));
+            }
+            consumed.consume(context);
+        }
+        self.bindings.axes.get(id).map(|axis| (axis, consumed))
+    }
+
+    /// Finds the binding of a 2D axis on the context stack, with the input consumed above it.
+    fn resolve_axis_2d<T: Hash + Eq + ?Sized>(&self, id: &T) -> Option<(&Axis2D, Consumed)>
+    where
+        AX: Borrow<T>,
+    {
+        let mut consumed = Consumed::default();
+        for context in self.context_stack() {
+            if let Some(axis) = context.bindings.axes_2d.get(id) {
+                return Some((axis, consumed));
+            }
+            consumed.consume(context);
+        }
+        self.bindings.axes_2d.get(id).map(|axis| (axis, consumed))
+    }
+
+    /// Returns the current value of a controller axis, if the controller is connected.
+    pub fn controller_axis_value(&self, controller_id: u32, axis: ControllerAxis) -> Option<f64> {
+        self.controller_axes
+            .iter()
+            .find(|&&(id, a, _)| id == controller_id && a == axis)
+            .map(|&(_, _, val)| val)
+    }
+
+    /// Evaluates an axis, remapping its buttons and controllers to the given player devices
+    /// and ignoring input consumed by higher contexts.
+    pub(crate) fn evaluate_axis(
+        &self,
+        axis: &Axis,
+        devices: Option<&[InputDevice]>,
+        consumed: Option<&Consumed>,
+    ) -> f64 {
+        match *axis {
             Axis::Emulated { pos, neg, .. } => {
-                let pos = self.button_is_down(pos);
-                let neg = self.button_is_down(neg);
+                let pos = self.mapped_button_is_down(pos, devices, consumed);
+                let neg = self.mapped_button_is_down(neg, devices, consumed);
                 if pos == neg {
                     0.0
                 } else if pos {
@@ -504,41 +606,509 @@ where
                 axis,
                 invert,
                 dead_zone,
+                ref curve,
+                sensitivity,
+            } => {
+                let controller_id = match devices {
+                    Some(devices) => match player::controller_id(controller_id, devices) {
+                        Some(id) => id,
+                        None => return 0.0,
+                    },
+                    None => controller_id,
+                };
+                if consumed.map_or(false, |c| c.hides_axis(controller_id, axis)) {
+                    return 0.0;
+                }
+                self.controller_axis_value(controller_id, axis)
+                    .map(|val| if invert { -val } else { val })
+                    .map(|val| curve.process(val, dead_zone, sensitivity))
+                    .unwrap_or(0.0)
+            }
+            Axis::MouseMotion { axis, sensitivity } => {
+                if !Self::uses_mouse(devices)
+                    || consumed.map_or(false, |c| c.hides_mouse_motion(axis))
+                {
+                    return 0.0;
+                }
+                match axis {
+                    MouseAxis::X => self.mouse_motion.0 * sensitivity,
+                    MouseAxis::Y => self.mouse_motion.1 * sensitivity,
+                }
+            }
+            Axis::MouseWheel {
+                horizontal,
+                sensitivity,
+            } => {
+                if !Self::uses_mouse(devices)
+                    || consumed.map_or(false, |c| c.hides_mouse_wheel(horizontal))
+                {
+                    return 0.0;
+                }
+                if horizontal {
+                    self.mouse_wheel.0 * sensitivity
+                } else {
+                    self.mouse_wheel.1 * sensitivity
+                }
+            }
+            Axis::Pinch { sensitivity } => {
+                if !Self::uses_mouse(devices) || consumed.map_or(false, |c| c.hides_pinch()) {
+                    return 0.0;
+                }
+                self.pinch * sensitivity
+            }
+        }
+    }
+
+    /// Returns true if the mouse and touch screen are one of the given player devices, or no
+    /// devices are given.
+    fn uses_mouse(devices: Option<&[InputDevice]>) -> bool {
+        devices.map_or(true, |devices| {
+            devices.contains(&InputDevice::KeyboardMouse)
+        })
+    }
+
+    /// Returns the motion of the mouse during the current frame.
+    pub fn mouse_motion(&self) -> (f64, f64) {
+        self.mouse_motion
+    }
+
+    /// Returns the amount the mouse wheel was scrolled during the current frame, horizontally and
+    /// vertically.
+    pub fn mouse_wheel_delta(&self) -> (f64, f64) {
+        self.mouse_wheel
+    }
+
+    /// Evaluates a 2D axis without smoothing, remapping its buttons and controllers to the given
+    /// player devices and ignoring input consumed by higher contexts.
+    pub(crate) fn evaluate_axis_2d(
+        &self,
+        axis: &Axis2D,
+        devices: Option<&[InputDevice]>,
+        consumed: Option<&Consumed>,
+    ) -> Vector2<f64> {
+        match *axis {
+            Axis2D::Emulated {
+                up,
+                down,
+                left,
+                right,
                 ..
-            } => self
-                .controller_axes
-                .iter()
-                .find(|&&(id, a, _)| id == controller_id && a == axis)
-                .map(|&(_, _, val)| if invert { -val } else { val })
-                .map(|val| {
-                    if val < -dead_zone {
-                        (val + dead_zone) / (1.0 - dead_zone)
-                    } else if val > dead_zone {
-                        (val - dead_zone) / (1.0 - dead_zone)
-                    } else {
+            } => {
+                let direction = |pos: Button, neg: Button| {
+                    let pos = self.mapped_button_is_down(pos, devices, consumed);
+                    let neg = self.mapped_button_is_down(neg, devices, consumed);
+                    if pos == neg {
                         0.0
+                    } else if pos {
+                        1.0
+                    } else {
+                        -1.0
                     }
-                })
-                .unwrap_or(0.0),
-        })
+                };
+                let value = Vector2::new(direction(right, left), direction(up, down));
+                let length = value.norm();
+                if length > 1.0 {
+                    value / length
+                } else {
+                    value
+                }
+            }
+            Axis2D::Controller {
+                controller_id,
+                x,
+                y,
+                invert_x,
+                invert_y,
+                dead_zone,
+                ref curve,
+                sensitivity,
+            } => {
+                let controller_id = match devices {
+                    Some(devices) => match player::controller_id(controller_id, devices) {
+                        Some(id) => id,
+                        None => return Vector2::zeros(),
+                    },
+                    None => controller_id,
+                };
+                if consumed.map_or(false, |c| {
+                    c.hides_axis(controller_id, x) || c.hides_axis(controller_id, y)
+                }) {
+                    return Vector2::zeros();
+                }
+                let component = |axis: ControllerAxis, invert: bool| {
+                    let val = self
+                        .controller_axis_value(controller_id, axis)
+                        .unwrap_or(0.0);
+                    if invert {
+                        -val
+                    } else {
+                        val
+                    }
+                };
+                let value = Vector2::new(component(x, invert_x), component(y, invert_y));
+                let magnitude = value.norm();
+                if magnitude == 0.0 {
+                    return value;
+                }
+                let processed = curve.process(magnitude.min(1.0), dead_zone, sensitivity);
+                value * (processed / magnitude)
+            }
+        }
+    }
+
+    /// Moves the values of smoothed axes towards their current input over `delta` seconds.
+    fn update_smoothing(&mut self, delta: f64) {
+        let mut ids = HashSet::default();
+        let mut ids_2d = HashSet::default();
+        for bindings in self
+            .context_stack()
+            .map(|context| &context.bindings)
+            .chain(Some(&self.bindings))
+        {
+            ids.extend(bindings.axes.keys());
+            ids_2d.extend(bindings.axes_2d.keys());
+        }
+
+        let mut smoothed = HashMap::default();
+        for id in ids {
+            if let Some((axis, consumed)) = self.resolve_axis(id) {
+                if let Axis::Emulated {
+                    smoothing: Some(smoothing),
+                    ..
+                } = *axis
+                {
+                    let target = self.evaluate_axis(axis, None, Some(&consumed));
+                    let value = self.smoothed_axes.get(id).cloned().unwrap_or(0.0);
+                    smoothed.insert(id.clone(), smoothing.step(value, target, delta));
+                }
+            }
+        }
+        let mut smoothed_2d = HashMap::default();
+        for id in ids_2d {
+            if let Some((axis, consumed)) = self.resolve_axis_2d(id) {
+                if let Axis2D::Emulated {
+                    smoothing: Some(smoothing),
+                    ..
+                } = *axis
+                {
+                    let target = self.evaluate_axis_2d(axis, None, Some(&consumed));
+                    let value = self
+                        .smoothed_axes_2d
+                        .get(id)
+                        .cloned()
+                        .unwrap_or_else(Vector2::zeros);
+                    let value = Vector2::new(
+                        smoothing.step(value.x, target.x, delta),
+                        smoothing.step(value.y, target.y, delta),
+                    );
+                    smoothed_2d.insert(id.clone(), value);
+                }
+            }
+        }
+        self.smoothed_axes = smoothed;
+        self.smoothed_axes_2d = smoothed_2d;
+    }
+
+    /// Checks if a button is down, remapping it to the given player devices and ignoring input
+    /// consumed by higher contexts.
+    pub(crate) fn mapped_button_is_down(
+        &self,
+        button: Button,
+        devices: Option<&[InputDevice]>,
+        consumed: Option<&Consumed>,
+    ) -> bool {
+        if consumed.map_or(false, |c| c.hides_button(button)) {
+            return false;
+        }
+        match devices {
+            Some(devices) => {
+                player::map_button(button, devices).map_or(false, |b| self.button_is_down(b))
+            }
+            None => self.button_is_down(button),
+        }
+    }
+
+    /// Returns true if the action was pressed during the current frame.
+    pub fn action_just_pressed<T: Hash + Eq + ?Sized>(&self, action: &T) -> bool
+    where
+        AC: Borrow<T>,
+    {
+        self.actions_pressed
+            .iter()
+            .any(|a| Borrow::<T>::borrow(a) == action)
+    }
+
+    /// Returns true if the action was released during the current frame.
+    pub fn action_just_released<T: Hash + Eq + ?Sized>(&self, action: &T) -> bool
+    where
+        AC: Borrow<T>,
+    {
+        self.actions_released
+            .iter()
+            .any(|a| Borrow::<T>::borrow(a) == action)
+    }
+
+    /// Returns for how many seconds the action has been held down, or `None` if it is not down.
+    pub fn action_held_duration<T: Hash + Eq + ?Sized>(&self, action: &T) -> Option<f64>
+    where
+        AC: Borrow<T>,
+    {
+        self.actions_down
+            .get(action)
+            .map(|pressed_at| self.time - pressed_at)
+    }
+
+    /// Returns true if the trigger with the given id fired during the current frame.
+    pub fn action_triggered<T: Hash + Eq + ?Sized>(&self, id: &T) -> bool
+    where
+        AC: Borrow<T>,
+    {
+        self.triggered.iter().any(|a| Borrow::<T>::borrow(a) == id)
     }
 
     /// Returns true if any of the actions bindings is down.
     ///
     /// If a binding represents a combination of buttons, all of them need to be down.
+    /// The action is resolved from the top of the context stack down, see `InputContext`.
     pub fn action_is_down<T: Hash + Eq + ?Sized>(&self, action: &T) -> Option<bool>
     where
         AC: Borrow<T>,
     {
-        self.bindings.actions.get(action).map(|combinations| {
-            combinations.iter().any(|combination| {
-                combination
-                    .iter()
-                    .all(|button| self.button_is_down(*button))
-            })
+        let mut consumed = Consumed::default();
+        for context in self.context_stack() {
+            if let Some(combinations) = context.bindings.actions.get(action) {
+                return Some(self.any_combination_is_down(combinations, None, Some(&consumed)));
+            }
+            consumed.consume(context);
+        }
+        self.bindings
+            .actions
+            .get(action)
+            .map(|combinations| self.any_combination_is_down(combinations, None, Some(&consumed)))
+    }
+
+    /// Checks if all buttons of any combination are down, remapping them to the given player
+    /// devices and ignoring input consumed by higher contexts.
+    pub(crate) fn any_combination_is_down(
+        &self,
+        combinations: &[SmallVec<[Button; 2]>],
+        devices: Option<&[InputDevice]>,
+        consumed: Option<&Consumed>,
+    ) -> bool {
+        combinations.iter().any(|combination| {
+            combination
+                .iter()
+                .all(|button| self.mapped_button_is_down(*button, devices, consumed))
         })
     }
 
+    /// Registers a named input context, replacing and returning any context with the same name.
+    ///
+    /// The context is inactive until it is pushed with `push_context`, unless it replaces an
+    /// active context.
+    pub fn insert_context<N: Into<String>>(
+        &mut self,
+        name: N,
+        context: InputContext<AX, AC>,
+    ) -> Option<InputContext<AX, AC>> {
+        let replaced = self.contexts.insert(name.into(), context);
+        self.sort_contexts();
+        replaced
+    }
+
+    /// Removes a registered input context, deactivating it.
+    pub fn remove_context(&mut self, name: &str) -> Option<InputContext<AX, AC>> {
+        self.deactivate_context(name);
+        self.contexts.remove(name)
+    }
+
+    /// Returns a registered input context.
+    pub fn context(&self, name: &str) -> Option<&InputContext<AX, AC>> {
+        self.contexts.get(name)
+    }
+
+    /// Returns a registered input context mutably.
+    ///
+    /// A changed `priority` of an active context takes effect when the next context is pushed,
+    /// or at the start of the next frame.
+    pub fn context_mut(&mut self, name: &str) -> Option<&mut InputContext<AX, AC>> {
+        self.contexts.get_mut(name)
+    }
+
+    /// Pushes a registered input context onto the context stack, moving it to the top of its
+    /// priority if it is already active.
+    ///
+    /// Returns false if no context with that name is registered.
+    pub fn push_context(&mut self, name: &str) -> bool {
+        if !self.contexts.contains_key(name) {
+            return false;
+        }
+        self.deactivate_context(name);
+        self.active_contexts.push(name.to_owned());
+        self.sort_contexts();
+        true
+    }
+
+    /// Pops the top input context from the context stack, returning its name.
+    ///
+    /// This is the most recently pushed context of the highest priority, which is not
+    /// necessarily the most recently pushed context.
+    pub fn pop_context(&mut self) -> Option<String> {
+        self.active_contexts.pop()
+    }
+
+    /// Removes an input context from the context stack, returning true if it was active.
+    pub fn deactivate_context(&mut self, name: &str) -> bool {
+        let len = self.active_contexts.len();
+        self.active_contexts.retain(|n| n != name);
+        self.active_contexts.len() != len
+    }
+
+    /// Returns true if the input context is on the context stack.
+    pub fn is_context_active(&self, name: &str) -> bool {
+        self.active_contexts.iter().any(|n| n == name)
+    }
+
+    /// Returns the names of the active input contexts, from the top of the context stack down.
+    pub fn active_contexts(&self) -> impl Iterator<Item = &str> {
+        self.active_contexts.iter().rev().map(String::as_str)
+    }
+
+    /// Orders the active contexts by priority, contexts of equal priority stay in push order.
+    fn sort_contexts(&mut self) {
+        let contexts = &self.contexts;
+        self.active_contexts.sort_by_key(|name| {
+            contexts
+                .get(name)
+                .map_or(i32::min_value(), |context| context.priority)
+        });
+    }
+
+    /// Returns the active contexts from the top of the stack down.
+    fn context_stack(&self) -> impl Iterator<Item = &InputContext<AX, AC>> {
+        let contexts = &self.contexts;
+        self.active_contexts
+            .iter()
+            .rev()
+            .filter_map(move |name| contexts.get(name))
+    }
+
+    /// Detects actions pressed or released since the last update, sends their events and updates
+    /// the triggers.
+    fn update_actions(&mut self, event_handler: &mut EventChannel<InputEvent<AC>>) {
+        let mut actions = self.actions_down.keys().collect::<HashSet<_>>();
+        for bindings in self
+            .context_stack()
+            .map(|context| &context.bindings)
+            .chain(Some(&self.bindings))
+        {
+            actions.extend(bindings.actions.keys());
+        }
+
+        let mut pressed = Vec::new();
+        let mut released = Vec::new();
+        for action in actions {
+            let down = self.action_is_down(action).unwrap_or(false);
+            match self.actions_down.get(action) {
+                None if down => pressed.push(action.clone()),
+                Some(&pressed_at) if !down => released.push((action.clone(), pressed_at)),
+                _ => {}
+            }
+        }
+
+        let time = self.time;
+        for action in pressed {
+            for (id, trigger) in self.bindings.triggers.iter() {
+                let state = self.trigger_states.entry(id.clone()).or_default();
+                if trigger.on_press(&action, time, state) {
+                    self.triggered.push(id.clone());
+                    event_handler.single_write(ActionTriggered(id.clone()));
+                }
+            }
+            event_handler.single_write(ActionPressed(action.clone()));
+            self.actions_down.insert(action.clone(), time);
+            self.actions_pressed.push(action);
+        }
+        for (action, pressed_at) in released {
+            for (id, trigger) in self.bindings.triggers.iter() {
+                let state = self.trigger_states.entry(id.clone()).or_default();
+                if trigger.on_release(&action, pressed_at, time, state) {
+                    self.triggered.push(id.clone());
+                    event_handler.single_write(ActionTriggered(id.clone()));
+                }
+            }
+            event_handler.single_write(ActionReleased(action.clone()));
+            self.actions_down.remove(&action);
+            self.actions_released.push(action);
+        }
+    }
+
+    /// Updates the touches on the screen, recognizing gestures and pinching.
+    fn send_touch(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        position: (f64, f64),
+        event_handler: &mut EventChannel<InputEvent<AC>>,
+    ) {
+        let (x, y) = position;
+        let index = self.touches.iter().position(|touch| touch.id == id);
+        match (phase, index) {
+            (TouchPhase::Started, None) => {
+                self.touches.push(TouchPoint {
+                    id,
+                    position,
+                    start_position: position,
+                    start_time: self.time,
+                });
+                self.multi_touch |= self.touches.len() > 1;
+                event_handler.single_write(TouchStarted { id, x, y });
+            }
+            (TouchPhase::Moved, Some(i)) => {
+                let distance_before = touch::pinch_distance(&self.touches);
+                let old = self.touches[i].position;
+                self.touches[i].position = position;
+                if let (Some(before), Some(after)) =
+                    (distance_before, touch::pinch_distance(&self.touches))
+                {
+                    if i < 2 && before > 0.0 {
+                        self.pinch += after / before - 1.0;
+                    }
+                }
+                event_handler.single_write(TouchMoved {
+                    id,
+                    x,
+                    y,
+                    delta_x: x - old.0,
+                    delta_y: y - old.1,
+                });
+            }
+            (TouchPhase::Ended, Some(i)) | (TouchPhase::Cancelled, Some(i)) => {
+                let mut touch = self.touches.remove(i);
+                touch.position = position;
+                event_handler.single_write(TouchEnded { id, x, y });
+                if self.touches.is_empty() {
+                    let multi_touch = self.multi_touch;
+                    self.multi_touch = false;
+                    if phase == TouchPhase::Ended && !multi_touch {
+                        if let Some(gesture) = self.gesture_settings.recognize(&touch, self.time) {
+                            self.gestures.push(gesture);
+                            event_handler.iter_write(
+                                [
+                                    GestureRecognized(gesture),
+                                    ButtonPressed(Button::Gesture(gesture)),
+                                ]
+                                .iter()
+                                .cloned(),
+                            );
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
     /// Retrieve next free controller number to allocate new controller to
     fn alloc_controller_id(&self) -> u32 {
         let mut i = 0u32;
@@ -556,13 +1126,21 @@ where
     }
 
     /// Map controller's index from external event into controller_id
-    fn controller_idx_to_id(&self, index: u32) -> Option<u32> {
+    pub(crate) fn controller_idx_to_id(&self, index: u32) -> Option<u32> {
         self.connected_controllers
             .iter()
             .find(|ids| ids.1 == index)
             .map(|ids| ids.0)
     }
 
+    /// Map controller_id into the controller's index used by external events
+    pub(crate) fn controller_id_to_idx(&self, controller_id: u32) -> Option<u32> {
+        self.connected_controllers
+            .iter()
+            .find(|ids| ids.0 == controller_id)
+            .map(|ids| ids.1)
+    }
+
     /// Iterates all input bindings and invokes ActionWheelMoved for each action bound to the mouse wheel
     fn invoke_wheel_moved(
         &self,
@@ -17,15 +17,25 @@ extern crate sdl2;
 #[cfg(feature = "sdl_controller")]
 pub use self::sdl_events_system::SdlEventsSystem;
 pub use self::{
-    axis::Axis,
+    axis::{Axis, Axis2D, AxisSmoothing, MouseAxis, ResponseCurve},
     bindings::Bindings,
     bundle::InputBundle,
     button::Button,
-    controller::{ControllerAxis, ControllerButton},
+    context::{InputConsumption, InputContext},
+    controller::{ControllerAxis, ControllerButton, ControllerEvent},
     event::InputEvent,
     input_handler::InputHandler,
+    player::{InputDevice, PlayerEvent, PlayerInput, PlayerInputSystem, PlayerInputs},
+    rebind::{
+        BindingConflict, CapturedInput, ConflictSource, InputRebindSystem, InputRebinder,
+        RebindCapture, RebindError, RebindTarget,
+    },
+    recording::{InputRecorder, InputRecording, RawInput, RecordedFrame},
+    rumble::ControllerRumble,
     scroll_direction::ScrollDirection,
     system::InputSystem,
+    touch::{Gesture, GestureSettings, SwipeDirection, TouchPoint},
+    trigger::ActionTrigger,
     util::{get_input_axis_simple, get_key, is_close_requested, is_key_down},
 };
 
@@ -36,11 +46,18 @@ mod axis;
 mod bindings;
 mod bundle;
 mod button;
+mod context;
 mod controller;
 mod event;
 mod input_handler;
+mod player;
+mod rebind;
+mod recording;
+mod rumble;
 mod scroll_direction;
 mod system;
+mod touch;
+mod trigger;
 mod util;
 
 #[cfg(feature = "sdl_controller")]
@@ -0,0 +1,686 @@
+//! Input contexts of local players, with their own bindings and devices.
+
+use std::{borrow::Borrow, hash::Hash};
+
+use smallvec::SmallVec;
+
+use amethyst_core::{
+    nalgebra::Vector2,
+    shrev::{EventChannel, ReaderId},
+    specs::prelude::{Read, Resources, System, Write},
+};
+
+use crate::{Bindings, Button, CapturedInput, InputEvent, InputHandler};
+
+/// An input device which can be assigned to a player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputDevice {
+    /// The keyboard, mouse and touch screen.
+    KeyboardMouse,
+    /// The controller with the given id.
+    Controller(u32),
+}
+
+impl InputDevice {
+    /// Returns the device a button of the `InputHandler` belongs to.
+    fn of_button(button: Button) -> Self {
+        match button {
+            Button::Controller(id, _) => InputDevice::Controller(id),
+            _ => InputDevice::KeyboardMouse,
+        }
+    }
+}
+
+/// Returns the id of the `index`th controller of a player.
+pub(crate) fn controller_id(index: u32, devices: &[InputDevice]) -> Option<u32> {
+    devices
+        .iter()
+        .filter_map(|device| match *device {
+            InputDevice::Controller(id) => Some(id),
+            InputDevice::KeyboardMouse => None,
+        })
+        .nth(index as usize)
+}
+
+/// Maps a button of player bindings to the corresponding button of the player's devices.
+pub(crate) fn map_button(button: Button, devices: &[InputDevice]) -> Option<Button> {
+    match button {
+        Button::Controller(index, button) => {
+            controller_id(index, devices).map(|id| Button::Controller(id, button))
+        }
+        _ if devices.contains(&InputDevice::KeyboardMouse) => Some(button),
+        _ => None,
+    }
+}
+
+/// Maps captured input of the `InputHandler` to the corresponding input of player bindings, or
+/// `None` if it does not come from one of the player's devices.
+pub(crate) fn map_captured_input(
+    input: CapturedInput,
+    devices: &[InputDevice],
+) -> Option<CapturedInput> {
+    let controller_index = |id: u32| {
+        devices
+            .iter()
+            .filter_map(|device| match *device {
+                InputDevice::Controller(id) => Some(id),
+                InputDevice::KeyboardMouse => None,
+            })
+            .position(|controller_id| controller_id == id)
+            .map(|index| index as u32)
+    };
+    match input {
+        CapturedInput::Button(Button::Controller(id, button)) => controller_index(id)
+            .map(|index| CapturedInput::Button(Button::Controller(index, button))),
+        CapturedInput::Button(_) if devices.contains(&InputDevice::KeyboardMouse) => Some(input),
+        CapturedInput::Button(_) => None,
+        CapturedInput::ControllerAxis {
+            controller_id,
+            axis,
+        } => controller_index(controller_id).map(|index| CapturedInput::ControllerAxis {
+            controller_id: index,
+            axis,
+        }),
+    }
+}
+
+/// The input context of a single local player.
+///
+/// Player bindings address controllers by their index among the controllers assigned to the
+/// player rather than by controller id, so `Button::Controller(0, ControllerButton::A)` is the
+/// A button of the first controller of the player, whichever pad that is. Keyboard and mouse
+/// bindings only apply if the player has been assigned `InputDevice::KeyboardMouse`.
+#[derive(Derivative)]
+#[derivative(Clone(bound = "AX: Clone, AC: Clone"))]
+pub struct PlayerInput<AX, AC>
+where
+    AX: Hash + Eq,
+    AC: Hash + Eq,
+{
+    /// Maps the inputs of the player's devices to actions and axes.
+    pub bindings: Bindings<AX, AC>,
+    devices: SmallVec<[InputDevice; 2]>,
+}
+
+impl<AX, AC> PlayerInput<AX, AC>
+where
+    AX: Hash + Eq,
+    AC: Hash + Eq,
+{
+    /// Creates a player without devices, using the given bindings.
+    pub fn new(bindings: Bindings<AX, AC>) -> Self {
+        PlayerInput {
+            bindings,
+            devices: SmallVec::new(),
+        }
+    }
+
+    /// Returns the devices assigned to the player, in order of assignment.
+    pub fn devices(&self) -> &[InputDevice] {
+        &self.devices
+    }
+
+    /// Returns true if any device is assigned to the player.
+    pub fn is_joined(&self) -> bool {
+        !self.devices.is_empty()
+    }
+}
+
+impl<AX, AC> PlayerInput<AX, AC>
+where
+    AX: Hash + Eq + Clone + Send + Sync + 'static,
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    /// Returns the value of an axis of the player, if the id doesn't exist this returns None.
+    pub fn axis_value<T: Hash + Eq + ?Sized>(
+        &self,
+        handler: &InputHandler<AX, AC>,
+        id: &T,
+    ) -> Option<f64>
+    where
+        AX: Borrow<T>,
+    {
+        self.bindings
+            .axes
+            .get(id)
+            .map(|axis| handler.evaluate_axis(axis, Some(self.devices()), None))
+    }
+
+    /// Returns the value of a 2D axis of the player, if the id doesn't exist this returns None.
+    ///
+    /// Smoothing of emulated 2D axes is not applied to player axes.
+    pub fn axis_2d_value<T: Hash + Eq + ?Sized>(
+        &self,
+        handler: &InputHandler<AX, AC>,
+        id: &T,
+    ) -> Option<Vector2<f64>>
+    where
+        AX: Borrow<T>,
+    {
+        self.bindings
+            .axes_2d
+            .get(id)
+            .map(|axis| handler.evaluate_axis_2d(axis, Some(self.devices()), None))
+    }
+
+    /// Returns true if any of the player's bindings of the action is down.
+    pub fn action_is_down<T: Hash + Eq + ?Sized>(
+        &self,
+        handler: &InputHandler<AX, AC>,
+        action: &T,
+    ) -> Option<bool>
+    where
+        AC: Borrow<T>,
+    {
+        self.bindings.actions.get(action).map(|combinations| {
+            handler.any_combination_is_down(combinations, Some(self.devices()), None)
+        })
+    }
+
+    /// Checks if a button of the player is down.
+    pub fn button_is_down(&self, handler: &InputHandler<AX, AC>, button: Button) -> bool {
+        handler.mapped_button_is_down(button, Some(self.devices()), None)
+    }
+}
+
+/// Resource holding the input contexts of a fixed number of local players.
+///
+/// Devices are assigned to players with `assign`, or by pressing one of the join buttons
+/// while the `PlayerInputSystem` runs.
+///
+/// ### Example:
+///
+/// ```rust,ignore
+/// let players = PlayerInputs::new(4, bindings)
+///     .with_join_buttons(vec![
+///         Button::Controller(0, ControllerButton::A),
+///         Button::Key(VirtualKeyCode::Return),
+///     ]);
+/// let input_bundle = InputBundle::<String, String>::new().with_player_inputs(players);
+///
+/// // in a system
+/// if let Some(player) = players.player(1) {
+///     let jump = player.action_is_down(&input_handler, "jump");
+/// }
+/// ```
+#[derive(Derivative)]
+#[derivative(Clone(bound = "AX: Clone, AC: Clone"))]
+pub struct PlayerInputs<AX, AC>
+where
+    AX: Hash + Eq,
+    AC: Hash + Eq,
+{
+    players: Vec<PlayerInput<AX, AC>>,
+    join_buttons: SmallVec<[Button; 4]>,
+}
+
+impl<AX, AC> Default for PlayerInputs<AX, AC>
+where
+    AX: Hash + Eq,
+    AC: Hash + Eq,
+{
+    fn default() -> Self {
+        PlayerInputs {
+            players: Vec::new(),
+            join_buttons: SmallVec::new(),
+        }
+    }
+}
+
+impl<AX, AC> PlayerInputs<AX, AC>
+where
+    AX: Hash + Eq + Clone,
+    AC: Hash + Eq + Clone,
+{
+    /// Creates `count` players, each using a copy of the given bindings.
+    pub fn new(count: usize, bindings: Bindings<AX, AC>) -> Self {
+        PlayerInputs {
+            players: (0..count)
+                .map(|_| PlayerInput::new(bindings.clone()))
+                .collect(),
+            join_buttons: SmallVec::new(),
+        }
+    }
+
+    /// Sets the buttons which assign their device to the first free player when pressed.
+    ///
+    /// A controller button joins with any controller, regardless of its index.
+    pub fn with_join_buttons<I>(mut self, buttons: I) -> Self
+    where
+        I: IntoIterator<Item = Button>,
+    {
+        self.join_buttons = buttons.into_iter().collect();
+        self
+    }
+
+    /// Returns the number of players.
+    pub fn count(&self) -> usize {
+        self.players.len()
+    }
+
+    /// Returns the input context of a player.
+    pub fn player(&self, player: usize) -> Option<&PlayerInput<AX, AC>> {
+        self.players.get(player)
+    }
+
+    /// Returns the input context of a player mutably.
+    pub fn player_mut(&mut self, player: usize) -> Option<&mut PlayerInput<AX, AC>> {
+        self.players.get_mut(player)
+    }
+
+    /// Returns an iterator over the input contexts of all players.
+    pub fn players(&self) -> impl Iterator<Item = &PlayerInput<AX, AC>> {
+        self.players.iter()
+    }
+
+    /// Returns the player the device is assigned to.
+    pub fn player_of(&self, device: InputDevice) -> Option<usize> {
+        self.players
+            .iter()
+            .position(|player| player.devices.contains(&device))
+    }
+
+    /// Assigns a device to a player, unassigning it from any other player.
+    ///
+    /// Returns false if the player does not exist.
+    pub fn assign(&mut self, player: usize, device: InputDevice) -> bool {
+        if player >= self.players.len() {
+            return false;
+        }
+        self.unassign(device);
+        self.players[player].devices.push(device);
+        true
+    }
+
+    /// Unassigns a device, returning the player it was assigned to.
+    pub fn unassign(&mut self, device: InputDevice) -> Option<usize> {
+        let player = self.player_of(device)?;
+        self.players[player].devices.retain(|d| *d != device);
+        Some(player)
+    }
+
+    /// Assigns an unassigned device to the first player without devices, returning the player.
+    pub fn join(&mut self, device: InputDevice) -> Option<usize> {
+        if self.player_of(device).is_some() {
+            return None;
+        }
+        let player = self.players.iter().position(|p| !p.is_joined())?;
+        self.players[player].devices.push(device);
+        Some(player)
+    }
+
+    /// Unassigns all devices of a player.
+    pub fn leave(&mut self, player: usize) {
+        if let Some(player) = self.players.get_mut(player) {
+            player.devices.clear();
+        }
+    }
+
+    fn is_join_button(&self, button: Button) -> bool {
+        self.join_buttons.iter().any(|join| match (*join, button) {
+            (Button::Controller(_, join), Button::Controller(_, button)) => join == button,
+            (join, button) => join == button,
+        })
+    }
+}
+
+/// Events sent by the `PlayerInputSystem` when devices are assigned to or lost by players.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayerEvent {
+    /// A device was assigned to a player by pressing a join button.
+    Joined {
+        /// The index of the player.
+        player: usize,
+        /// The assigned device.
+        device: InputDevice,
+    },
+    /// A controller assigned to a player was disconnected, and unassigned from the player.
+    DeviceDisconnected {
+        /// The index of the player.
+        player: usize,
+        /// The disconnected device.
+        device: InputDevice,
+    },
+}
+
+/// System assigning devices to players when join buttons are pressed, and unassigning
+/// disconnected controllers.
+///
+/// Sends `PlayerEvent`s to `EventChannel<PlayerEvent>`.
+pub struct PlayerInputSystem<AX, AC>
+where
+    AX: Hash + Eq,
+    AC: Hash + Eq,
+{
+    reader: Option<ReaderId<InputEvent<AC>>>,
+    players: Option<PlayerInputs<AX, AC>>,
+}
+
+impl<AX, AC> PlayerInputSystem<AX, AC>
+where
+    AX: Hash + Eq,
+    AC: Hash + Eq,
+{
+    /// Creates a new player input system, inserting the given players on setup.
+    pub fn new(players: Option<PlayerInputs<AX, AC>>) -> Self {
+        PlayerInputSystem {
+            reader: None,
+            players,
+        }
+    }
+}
+
+impl<'a, AX, AC> System<'a> for PlayerInputSystem<AX, AC>
+where
+    AX: Hash + Eq + Clone + Send + Sync + 'static,
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    type SystemData = (
+        Read<'a, EventChannel<InputEvent<AC>>>,
+        Read<'a, InputHandler<AX, AC>>,
+        Write<'a, PlayerInputs<AX, AC>>,
+        Write<'a, EventChannel<PlayerEvent>>,
+    );
+
+    fn run(&mut self, (input, handler, mut players, mut output): Self::SystemData) {
+        for event in
+            input.read(&mut self.reader.as_mut().expect(
+                "`PlayerInputSystem::setup` was not called before `PlayerInputSystem::run`",
+            ))
+        {
+            if let InputEvent::ButtonPressed(button) = *event {
+                if players.is_join_button(button) {
+                    let device = InputDevice::of_button(button);
+                    if let Some(player) = players.join(device) {
+                        output.single_write(PlayerEvent::Joined { player, device });
+                    }
+                }
+            }
+        }
+
+        for player in 0..players.count() {
+            let disconnected = players.players[player]
+                .devices
+                .iter()
+                .filter(|device| match **device {
+                    InputDevice::Controller(id) => !handler.is_controller_connected(id),
+                    InputDevice::KeyboardMouse => false,
+                })
+                .cloned()
+                .collect::<SmallVec<[InputDevice; 2]>>();
+            for device in disconnected {
+                players.unassign(device);
+                output.single_write(PlayerEvent::DeviceDisconnected { player, device });
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        use amethyst_core::specs::prelude::SystemData;
+        Self::SystemData::setup(res);
+        self.reader = Some(
+            res.fetch_mut::<EventChannel<InputEvent<AC>>>()
+                .register_reader(),
+        );
+        if let Some(players) = self.players.take() {
+            *res.fetch_mut::<PlayerInputs<AX, AC>>() = players;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::VirtualKeyCode;
+
+    use amethyst_core::specs::{RunNow, World};
+
+    use crate::{
+        controller::{ControllerAxis, ControllerButton, ControllerEvent},
+        Axis, RawInput, ResponseCurve,
+    };
+
+    use super::*;
+
+    fn send(world: &World, input: RawInput) {
+        world
+            .write_resource::<InputHandler<String, String>>()
+            .send_raw_input(
+                &input,
+                &mut world.write_resource::<EventChannel<InputEvent<String>>>(),
+            );
+    }
+
+    fn press(world: &World, which: u32) {
+        send(
+            world,
+            RawInput::Controller(ControllerEvent::ControllerButtonPressed {
+                which,
+                button: ControllerButton::A,
+            }),
+        );
+    }
+
+    fn release(world: &World, which: u32) {
+        send(
+            world,
+            RawInput::Controller(ControllerEvent::ControllerButtonReleased {
+                which,
+                button: ControllerButton::A,
+            }),
+        );
+    }
+
+    fn setup() -> (
+        World,
+        PlayerInputSystem<String, String>,
+        ReaderId<PlayerEvent>,
+    ) {
+        let mut bindings = Bindings::new();
+        bindings.insert_action_binding(
+            "jump".to_string(),
+            vec![Button::Controller(0, ControllerButton::A)],
+        );
+        bindings.insert_axis(
+            "move".to_string(),
+            Axis::Controller {
+                controller_id: 0,
+                axis: ControllerAxis::LeftX,
+                invert: false,
+                dead_zone: 0.0,
+                curve: ResponseCurve::Linear,
+                sensitivity: 1.0,
+            },
+        );
+        let players = PlayerInputs::new(3, bindings).with_join_buttons(vec![
+            Button::Controller(0, ControllerButton::A),
+            Button::Key(VirtualKeyCode::Return),
+        ]);
+
+        let mut world = World::new();
+        let mut system = PlayerInputSystem::new(Some(players));
+        RunNow::setup(&mut system, &mut world.res);
+        let reader = world
+            .write_resource::<EventChannel<PlayerEvent>>()
+            .register_reader();
+        (world, system, reader)
+    }
+
+    fn player_events(world: &World, reader: &mut ReaderId<PlayerEvent>) -> Vec<PlayerEvent> {
+        world
+            .read_resource::<EventChannel<PlayerEvent>>()
+            .read(reader)
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn join_with_join_buttons() {
+        let (world, mut system, mut reader) = setup();
+        // The controllers with index 5 and 7 get the ids 0 and 1.
+        for &which in &[5, 7] {
+            send(
+                &world,
+                RawInput::Controller(ControllerEvent::ControllerConnected { which }),
+            );
+        }
+
+        press(&world, 7);
+        send(
+            &world,
+            RawInput::KeyPressed {
+                key_code: VirtualKeyCode::Return,
+                scancode: 28,
+            },
+        );
+        system.run_now(&world.res);
+        assert_eq!(
+            vec![
+                PlayerEvent::Joined {
+                    player: 0,
+                    device: InputDevice::Controller(1),
+                },
+                PlayerEvent::Joined {
+                    player: 1,
+                    device: InputDevice::KeyboardMouse,
+                },
+            ],
+            player_events(&world, &mut reader)
+        );
+
+        // A device which is already assigned does not join another player.
+        release(&world, 7);
+        press(&world, 7);
+        system.run_now(&world.res);
+        assert!(player_events(&world, &mut reader).is_empty());
+        assert!(!world
+            .read_resource::<PlayerInputs<String, String>>()
+            .player(2)
+            .unwrap()
+            .is_joined());
+
+        press(&world, 5);
+        system.run_now(&world.res);
+        assert_eq!(
+            vec![PlayerEvent::Joined {
+                player: 2,
+                device: InputDevice::Controller(0),
+            }],
+            player_events(&world, &mut reader)
+        );
+        let players = world.read_resource::<PlayerInputs<String, String>>();
+        assert_eq!(Some(0), players.player_of(InputDevice::Controller(1)));
+        assert_eq!(Some(1), players.player_of(InputDevice::KeyboardMouse));
+        assert_eq!(Some(2), players.player_of(InputDevice::Controller(0)));
+    }
+
+    #[test]
+    fn unassign_disconnected_controllers() {
+        let (world, mut system, mut reader) = setup();
+        for &which in &[5, 7] {
+            send(
+                &world,
+                RawInput::Controller(ControllerEvent::ControllerConnected { which }),
+            );
+        }
+        {
+            let mut players = world.write_resource::<PlayerInputs<String, String>>();
+            assert!(players.assign(0, InputDevice::KeyboardMouse));
+            assert!(players.assign(0, InputDevice::Controller(1)));
+            assert!(players.assign(1, InputDevice::Controller(0)));
+            // Assigning a device again moves it to the new player.
+            assert!(players.assign(2, InputDevice::Controller(0)));
+            assert!(!players.player(1).unwrap().is_joined());
+            assert!(!players.assign(3, InputDevice::Controller(0)));
+        }
+
+        send(
+            &world,
+            RawInput::Controller(ControllerEvent::ControllerDisconnected { which: 7 }),
+        );
+        system.run_now(&world.res);
+        assert_eq!(
+            vec![PlayerEvent::DeviceDisconnected {
+                player: 0,
+                device: InputDevice::Controller(1),
+            }],
+            player_events(&world, &mut reader)
+        );
+        {
+            let players = world.read_resource::<PlayerInputs<String, String>>();
+            assert_eq!(
+                &[InputDevice::KeyboardMouse],
+                players.player(0).unwrap().devices()
+            );
+            assert_eq!(
+                &[InputDevice::Controller(0)],
+                players.player(2).unwrap().devices()
+            );
+        }
+
+        system.run_now(&world.res);
+        assert!(player_events(&world, &mut reader).is_empty());
+    }
+
+    #[test]
+    fn resolve_bindings_of_player_controllers() {
+        let (world, _, _) = setup();
+        for &which in &[5, 7] {
+            send(
+                &world,
+                RawInput::Controller(ControllerEvent::ControllerConnected { which }),
+            );
+        }
+        {
+            let mut players = world.write_resource::<PlayerInputs<String, String>>();
+            players.assign(0, InputDevice::Controller(1));
+            players.assign(1, InputDevice::KeyboardMouse);
+            players.assign(2, InputDevice::Controller(0));
+        }
+
+        press(&world, 7);
+        send(
+            &world,
+            RawInput::Controller(ControllerEvent::ControllerAxisMoved {
+                which: 7,
+                axis: ControllerAxis::LeftX,
+                value: 0.5,
+            }),
+        );
+
+        let handler = world.read_resource::<InputHandler<String, String>>();
+        let players = world.read_resource::<PlayerInputs<String, String>>();
+        // The first controller of player 0 is the controller with id 1.
+        let player = players.player(0).unwrap();
+        assert_eq!(Some(true), player.action_is_down(&handler, "jump"));
+        assert_eq!(Some(0.5), player.axis_value(&handler, "move"));
+        let player = players.player(1).unwrap();
+        assert_eq!(Some(false), player.action_is_down(&handler, "jump"));
+        assert_eq!(Some(0.0), player.axis_value(&handler, "move"));
+        let player = players.player(2).unwrap();
+        assert_eq!(Some(false), player.action_is_down(&handler, "jump"));
+        assert_eq!(Some(0.0), player.axis_value(&handler, "move"));
+        assert_eq!(None, player.axis_value(&handler, "look"));
+    }
+
+    #[test]
+    fn map_player_buttons() {
+        let devices = [InputDevice::Controller(3), InputDevice::Controller(1)];
+        assert_eq!(
+            Some(Button::Controller(1, ControllerButton::A)),
+            map_button(Button::Controller(1, ControllerButton::A), &devices)
+        );
+        assert_eq!(
+            Some(Button::Controller(3, ControllerButton::B)),
+            map_button(Button::Controller(0, ControllerButton::B), &devices)
+        );
+        assert_eq!(
+            None,
+            map_button(Button::Controller(2, ControllerButton::B), &devices)
+        );
+        assert_eq!(None, map_button(Button::ScanCode(12), &devices));
+        assert_eq!(
+            Some(Button::ScanCode(12)),
+            map_button(Button::ScanCode(12), &[InputDevice::KeyboardMouse])
+        );
+    }
+}
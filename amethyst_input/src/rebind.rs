@@ -0,0 +1,634 @@
+//! Capturing input to rebind actions and axes at runtime.
+
+use std::{hash::Hash, marker::PhantomData};
+
+use smallvec::SmallVec;
+use winit::VirtualKeyCode;
+
+use amethyst_core::{
+    shrev::{EventChannel, ReaderId},
+    specs::prelude::{Read, Resources, System, Write},
+};
+
+use crate::{
+    player, Axis, Axis2D, Bindings, Button, ControllerAxis, InputEvent, InputHandler, PlayerInputs,
+};
+
+/// The binding to replace with captured input.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RebindTarget<AX, AC> {
+    /// The binding of an action at the given index, or a new binding if the index is out of
+    /// range.
+    Action {
+        /// The rebound action.
+        action: AC,
+        /// The index of the binding to replace.
+        index: usize,
+    },
+    /// The positive button of an emulated axis.
+    AxisPositive(AX),
+    /// The negative button of an emulated axis.
+    AxisNegative(AX),
+    /// The controller axis of an axis.
+    ControllerAxis(AX),
+}
+
+/// Input captured for rebinding.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum CapturedInput {
+    /// A button was pressed.
+    Button(Button),
+    /// A controller axis was moved past the capture threshold.
+    ControllerAxis {
+        /// The id of the controller.
+        controller_id: u32,
+        /// The moved axis.
+        axis: ControllerAxis,
+    },
+}
+
+/// An existing binding using the same input as a captured one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BindingConflict<AX, AC> {
+    /// A binding of the action uses the input.
+    Action(AC),
+    /// The axis uses the input.
+    Axis(AX),
+}
+
+/// The bindings a `BindingConflict` was found in.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConflictSource {
+    /// The bindings of the `InputHandler`.
+    Handler,
+    /// The bindings of the active input context with the given name.
+    Context(String),
+    /// The bindings of the local player with the given index in `PlayerInputs`.
+    Player(usize),
+}
+
+/// Errors returned by `Bindings::rebind`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RebindError {
+    /// The rebound axis does not exist.
+    UnknownAxis,
+    /// Buttons can only be bound to the positive or negative side of an emulated axis.
+    ExpectedButton,
+    /// Controller axes can only be bound to `RebindTarget::ControllerAxis`.
+    ExpectedControllerAxis,
+    /// The positive or negative side of an axis was rebound, but the axis is not
+    /// `Axis::Emulated`.
+    NotEmulated,
+}
+
+impl<AX, AC> Bindings<AX, AC>
+where
+    AX: Hash + Eq + Clone,
+    AC: Hash + Eq + Clone,
+{
+    /// Returns all actions and axes using the input.
+    pub fn conflicts(&self, input: CapturedInput) -> Vec<BindingConflict<AX, AC>> {
+        let mut conflicts = Vec::new();
+        if let CapturedInput::Button(button) = input {
+            for (action, combinations) in self.actions.iter() {
+                if combinations.iter().any(|c| c.contains(&button)) {
+                    conflicts.push(BindingConflict::Action(action.clone()));
+                }
+            }
+        }
+        for (id, axis) in self.axes.iter() {
+            let conflict = match (axis, input) {
+                (&Axis::Emulated { pos, neg, .. }, CapturedInput::Button(button)) => {
+                    pos == button || neg == button
+                }
+                (
+                    &Axis::Controller {
+                        controller_id,
+                        axis,
+                        ..
+                    },
+                    CapturedInput::ControllerAxis {
+                        controller_id: id,
+                        axis: a,
+                    },
+                ) => controller_id == id && axis == a,
+                _ => false,
+            };
+            if conflict {
+                conflicts.push(BindingConflict::Axis(id.clone()));
+            }
+        }
+        for (id, axis) in self.axes_2d.iter() {
+            if axis_2d_uses(axis, input) {
+                conflicts.push(BindingConflict::Axis(id.clone()));
+            }
+        }
+        conflicts
+    }
+
+    /// Replaces the target binding with captured input.
+    ///
+    /// Rebinding an action replaces the combination at the target index with the captured
+    /// button. Rebinding a `ControllerAxis` turns the axis into a controller axis, keeping its
+    /// other settings if it already was one.
+    pub fn rebind(
+        &mut self,
+        target: &RebindTarget<AX, AC>,
+        input: CapturedInput,
+    ) -> Result<(), RebindError> {
+        match (target, input) {
+            (&RebindTarget::Action { ref action, index }, CapturedInput::Button(button)) => {
+                let mut combination = SmallVec::new();
+                combination.push(button);
+                let combinations = self
+                    .actions
+                    .entry(action.clone())
+                    .or_insert_with(SmallVec::new);
+                let index = if index < combinations.len() {
+                    combinations[index] = combination;
+                    index
+                } else {
+                    combinations.push(combination);
+                    combinations.len() - 1
+                };
+                // Drop other bindings to the same button only after the target slot was
+                // replaced, so that removing them does not shift the index.
+                let mut i = 0;
+                combinations.retain(|c| {
+                    let keep = i == index || c.len() != 1 || c[0] != button;
+                    i += 1;
+                    keep
+                });
+                Ok(())
+            }
+            (&RebindTarget::AxisPositive(ref id), CapturedInput::Button(button)) => {
+                match self.axes.get_mut(id).ok_or(RebindError::UnknownAxis)? {
+                    &mut Axis::Emulated { ref mut pos, .. } => {
+                        *pos = button;
+                        Ok(())
+                    }
+                    _ => Err(RebindError::NotEmulated),
+                }
+            }
+            (&RebindTarget::AxisNegative(ref id), CapturedInput::Button(button)) => {
+                match self.axes.get_mut(id).ok_or(RebindError::UnknownAxis)? {
+                    &mut Axis::Emulated { ref mut neg, .. } => {
+                        *neg = button;
+                        Ok(())
+                    }
+                    _ => Err(RebindError::NotEmulated),
+                }
+            }
+            (
+                &RebindTarget::ControllerAxis(ref id),
+                CapturedInput::ControllerAxis {
+                    controller_id,
+                    axis,
+                },
+            ) => {
+                if let Some(&mut Axis::Controller {
+                    controller_id: ref mut bound_id,
+                    axis: ref mut bound_axis,
+                    ..
+                }) = self.axes.get_mut(id)
+                {
+                    *bound_id = controller_id;
+                    *bound_axis = axis;
+                    return Ok(());
+                }
+                self.axes.insert(
+                    id.clone(),
+                    Axis::Controller {
+                        controller_id,
+                        axis,
+                        invert: false,
+                        dead_zone: 0.0,
+                        curve: Default::default(),
+                        sensitivity: 1.0,
+                    },
+                );
+                Ok(())
+            }
+            (&RebindTarget::ControllerAxis(_), CapturedInput::Button(_)) => {
+                Err(RebindError::ExpectedControllerAxis)
+            }
+            (_, CapturedInput::ControllerAxis { .. }) => Err(RebindError::ExpectedButton),
+        }
+    }
+
+    /// Removes the input from all action bindings, resolving conflicts in favour of a new
+    /// binding.
+    ///
+    /// Axes can not leave the input unbound, so they are left unchanged and returned as the
+    /// remaining conflicts, to be rebound to other input or accepted as they are.
+    pub fn unbind(&mut self, input: CapturedInput) -> Vec<BindingConflict<AX, AC>> {
+        if let CapturedInput::Button(button) = input {
+            for combinations in self.actions.values_mut() {
+                combinations.retain(|c| !c.contains(&button));
+            }
+            self.actions
+                .retain(|_, combinations| !combinations.is_empty());
+        }
+        self.conflicts(input)
+    }
+}
+
+impl<AX, AC> InputHandler<AX, AC>
+where
+    AX: Hash + Eq + Clone + Send + Sync + 'static,
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    /// Returns all bindings using the input: those of the handler, of the active input
+    /// contexts, and of the local players the device of the input is assigned to.
+    pub fn binding_conflicts(
+        &self,
+        input: CapturedInput,
+        players: Option<&PlayerInputs<AX, AC>>,
+    ) -> Vec<(ConflictSource, BindingConflict<AX, AC>)> {
+        let mut conflicts = self
+            .bindings
+            .conflicts(input)
+            .into_iter()
+            .map(|conflict| (ConflictSource::Handler, conflict))
+            .collect::<Vec<_>>();
+        for name in self.active_contexts() {
+            if let Some(context) = self.context(name) {
+                conflicts.extend(
+                    context
+                        .bindings
+                        .conflicts(input)
+                        .into_iter()
+                        .map(|conflict| (ConflictSource::Context(name.to_owned()), conflict)),
+                );
+            }
+        }
+        let players = players.into_iter().flat_map(|players| players.players());
+        for (index, player) in players.enumerate() {
+            if let Some(input) = player::map_captured_input(input, player.devices()) {
+                conflicts.extend(
+                    player
+                        .bindings
+                        .conflicts(input)
+                        .into_iter()
+                        .map(|conflict| (ConflictSource::Player(index), conflict)),
+                );
+            }
+        }
+        conflicts
+    }
+}
+
+/// Returns true if the 2D axis uses the input.
+fn axis_2d_uses(axis: &Axis2D, input: CapturedInput) -> bool {
+    match (axis, input) {
+        (
+            &Axis2D::Emulated {
+                up,
+                down,
+                left,
+                right,
+                ..
+            },
+            CapturedInput::Button(button),
+        ) => [up, down, left, right].contains(&button),
+        (
+            &Axis2D::Controller {
+                controller_id,
+                x,
+                y,
+                ..
+            },
+            CapturedInput::ControllerAxis {
+                controller_id: id,
+                axis,
+            },
+        ) => controller_id == id && (x == axis || y == axis),
+        _ => false,
+    }
+}
+
+/// The result of capturing input with the `InputRebinder`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RebindCapture<AX, AC> {
+    /// The binding to replace.
+    pub target: RebindTarget<AX, AC>,
+    /// The captured input.
+    pub input: CapturedInput,
+    /// The existing bindings using the captured input, see `InputHandler::binding_conflicts`.
+    pub conflicts: Vec<(ConflictSource, BindingConflict<AX, AC>)>,
+}
+
+/// Resource capturing the next pressed button or moved controller axis to rebind an action
+/// or axis.
+///
+/// Captures are performed by the `InputRebindSystem`, added by `InputBundle::with_rebinding`.
+/// Captured input is not applied automatically, so the game can ask the player how to resolve
+/// conflicts first. The changed bindings can then be saved with `amethyst_config::Config::write`.
+///
+/// ### Example:
+///
+/// ```rust,ignore
+/// // when the player selects "jump" in the options menu
+/// rebinder.capture(RebindTarget::Action { action: "jump".to_string(), index: 0 });
+///
+/// // in a later frame
+/// if let Some(capture) = rebinder.take_capture() {
+///     if capture.conflicts.is_empty() || player_confirmed {
+///         // axes still using the input are returned, so they can be rebound in turn
+///         let remaining = input_handler.bindings.unbind(capture.input);
+///         input_handler.bindings.rebind(&capture.target, capture.input)?;
+///         input_handler.bindings.write("resources/bindings.ron")?;
+///     }
+/// }
+/// ```
+pub struct InputRebinder<AX, AC> {
+    target: Option<RebindTarget<AX, AC>>,
+    capture: Option<RebindCapture<AX, AC>>,
+    axis_threshold: f64,
+    cancel_button: Option<Button>,
+}
+
+impl<AX, AC> Default for InputRebinder<AX, AC> {
+    fn default() -> Self {
+        InputRebinder {
+            target: None,
+            capture: None,
+            axis_threshold: 0.5,
+            cancel_button: Some(Button::Key(VirtualKeyCode::Escape)),
+        }
+    }
+}
+
+impl<AX, AC> InputRebinder<AX, AC> {
+    /// Creates a rebinder with an axis threshold of `0.5`, cancelled by the escape key.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets how far a controller axis must be moved to be captured.
+    pub fn with_axis_threshold(mut self, threshold: f64) -> Self {
+        self.axis_threshold = threshold;
+        self
+    }
+
+    /// Sets the button cancelling a capture instead of being captured.
+    pub fn with_cancel_button(mut self, button: Option<Button>) -> Self {
+        self.cancel_button = button;
+        self
+    }
+
+    /// Starts capturing input for the target, replacing any capture in progress.
+    pub fn capture(&mut self, target: RebindTarget<AX, AC>) {
+        self.target = Some(target);
+        self.capture = None;
+    }
+
+    /// Cancels the capture in progress.
+    pub fn cancel(&mut self) {
+        self.target = None;
+    }
+
+    /// Returns the target of the capture in progress.
+    pub fn capturing(&self) -> Option<&RebindTarget<AX, AC>> {
+        self.target.as_ref()
+    }
+
+    /// Takes the result of the last completed capture.
+    pub fn take_capture(&mut self) -> Option<RebindCapture<AX, AC>> {
+        self.capture.take()
+    }
+}
+
+/// System capturing input for the `InputRebinder`.
+pub struct InputRebindSystem<AX, AC> {
+    reader: Option<ReaderId<InputEvent<AC>>>,
+    marker: PhantomData<AX>,
+}
+
+impl<AX, AC> InputRebindSystem<AX, AC> {
+    /// Creates a new rebind system.
+    pub fn new() -> Self {
+        InputRebindSystem {
+            reader: None,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, AX, AC> System<'a> for InputRebindSystem<AX, AC>
+where
+    AX: Hash + Eq + Clone + Send + Sync + 'static,
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    type SystemData = (
+        Read<'a, EventChannel<InputEvent<AC>>>,
+        Read<'a, InputHandler<AX, AC>>,
+        Option<Read<'a, PlayerInputs<AX, AC>>>,
+        Write<'a, InputRebinder<AX, AC>>,
+    );
+
+    fn run(&mut self, (events, handler, players, mut rebinder): Self::SystemData) {
+        for event in
+            events.read(self.reader.as_mut().expect(
+                "`InputRebindSystem::setup` was not called before `InputRebindSystem::run`",
+            ))
+        {
+            if rebinder.target.is_none() {
+                continue;
+            }
+            let input = match *event {
+                InputEvent::ButtonPressed(button) => Some(CapturedInput::Button(button)),
+                InputEvent::MouseWheelMoved(direction) => {
+                    Some(CapturedInput::Button(Button::MouseWheel(direction)))
+                }
+                InputEvent::ControllerAxisMoved { which, axis, value }
+                    if value.abs() >= rebinder.axis_threshold =>
+                {
+                    handler.controller_idx_to_id(which).map(|controller_id| {
+                        CapturedInput::ControllerAxis {
+                            controller_id,
+                            axis,
+                        }
+                    })
+                }
+                _ => None,
+            };
+            let input = match input {
+                Some(input) => input,
+                None => continue,
+            };
+            let target = rebinder.target.take().expect("Unreachable: checked above");
+            if rebinder.cancel_button.map(CapturedInput::Button) == Some(input) {
+                continue;
+            }
+            rebinder.capture = Some(RebindCapture {
+                target,
+                input,
+                conflicts: handler.binding_conflicts(input, players.as_ref().map(|p| &**p)),
+            });
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        use amethyst_core::specs::prelude::SystemData;
+        Self::SystemData::setup(res);
+        self.reader = Some(
+            res.fetch_mut::<EventChannel<InputEvent<AC>>>()
+                .register_reader(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ControllerButton, InputContext, InputDevice};
+
+    use super::*;
+
+    #[test]
+    fn rebind_with_conflicts() {
+        let space = Button::Key(VirtualKeyCode::Space);
+        let mut bindings = Bindings::<String, String>::new();
+        bindings.insert_action_binding("jump".to_string(), vec![space]);
+        bindings.insert_axis(
+            "vertical".to_string(),
+            Axis::Emulated {
+                pos: Button::Key(VirtualKeyCode::W),
+                neg: Button::Key(VirtualKeyCode::S),
+                smoothing: None,
+            },
+        );
+
+        let input = CapturedInput::Button(Button::Key(VirtualKeyCode::W));
+        assert_eq!(
+            vec![BindingConflict::Axis("vertical".to_string())],
+            bindings.conflicts(input)
+        );
+        let target = RebindTarget::Action {
+            action: "jump".to_string(),
+            index: 0,
+        };
+        // The axis can not leave its positive side unbound, so it is kept and reported.
+        assert_eq!(
+            vec![BindingConflict::Axis("vertical".to_string())],
+            bindings.unbind(input)
+        );
+        assert!(bindings.axis("vertical").is_some());
+        bindings.rebind(&target, input).unwrap();
+        assert!(bindings.conflicts(CapturedInput::Button(space)).is_empty());
+        assert_eq!(2, bindings.conflicts(input).len());
+
+        let input = CapturedInput::Button(Button::Key(VirtualKeyCode::J));
+        bindings.rebind(&target, input).unwrap();
+        assert!(bindings.unbind(input).is_empty());
+        assert!(bindings.action_bindings("jump").is_none());
+
+        let target = RebindTarget::AxisPositive("horizontal".to_string());
+        assert_eq!(
+            Err(RebindError::UnknownAxis),
+            bindings.rebind(&target, input)
+        );
+    }
+
+    #[test]
+    fn rebind_action_at_index() {
+        let key = |key| -> SmallVec<[Button; 2]> { SmallVec::from_vec(vec![Button::Key(key)]) };
+        let mut bindings = Bindings::<String, String>::new();
+        bindings.insert_action_binding("jump".to_string(), vec![Button::Key(VirtualKeyCode::J)]);
+        bindings
+            .insert_action_binding("jump".to_string(), vec![Button::Key(VirtualKeyCode::Space)]);
+        bindings.insert_action_binding("jump".to_string(), vec![Button::Key(VirtualKeyCode::W)]);
+
+        // The duplicate binding before the index is removed after the index is replaced.
+        let target = RebindTarget::Action {
+            action: "jump".to_string(),
+            index: 2,
+        };
+        let input = CapturedInput::Button(Button::Key(VirtualKeyCode::J));
+        bindings.rebind(&target, input).unwrap();
+        assert_eq!(
+            &[key(VirtualKeyCode::Space), key(VirtualKeyCode::J)][..],
+            bindings.action_bindings("jump").unwrap()
+        );
+
+        let target = RebindTarget::Action {
+            action: "jump".to_string(),
+            index: 5,
+        };
+        let input = CapturedInput::Button(Button::Key(VirtualKeyCode::Space));
+        bindings.rebind(&target, input).unwrap();
+        assert_eq!(
+            &[key(VirtualKeyCode::J), key(VirtualKeyCode::Space)][..],
+            bindings.action_bindings("jump").unwrap()
+        );
+    }
+
+    #[test]
+    fn rebind_sides_of_controller_axis() {
+        let mut bindings = Bindings::<String, String>::new();
+        bindings.insert_axis(
+            "horizontal".to_string(),
+            Axis::Controller {
+                controller_id: 0,
+                axis: ControllerAxis::LeftX,
+                invert: false,
+                dead_zone: 0.0,
+                curve: Default::default(),
+                sensitivity: 1.0,
+            },
+        );
+        let input = CapturedInput::Button(Button::Key(VirtualKeyCode::D));
+        for target in vec![
+            RebindTarget::AxisPositive("horizontal".to_string()),
+            RebindTarget::AxisNegative("horizontal".to_string()),
+        ] {
+            assert_eq!(
+                Err(RebindError::NotEmulated),
+                bindings.rebind(&target, input)
+            );
+        }
+        let input = CapturedInput::ControllerAxis {
+            controller_id: 0,
+            axis: ControllerAxis::LeftY,
+        };
+        let target = RebindTarget::AxisPositive("horizontal".to_string());
+        assert_eq!(
+            Err(RebindError::ExpectedButton),
+            bindings.rebind(&target, input)
+        );
+    }
+
+    #[test]
+    fn conflicts_of_contexts_and_players() {
+        let space = Button::Key(VirtualKeyCode::Space);
+        let a = ControllerButton::A;
+        let mut bindings = Bindings::<String, String>::new();
+        bindings.insert_action_binding("confirm".to_string(), vec![space]);
+        bindings.insert_action_binding("accept".to_string(), vec![Button::Controller(0, a)]);
+        let mut handler = InputHandler::<String, String>::new();
+        handler.insert_context("menu", InputContext::new(bindings.clone()));
+        let mut players = PlayerInputs::new(2, bindings);
+        players.assign(1, InputDevice::Controller(3));
+
+        let input = CapturedInput::Button(space);
+        assert!(handler.binding_conflicts(input, Some(&players)).is_empty());
+        assert!(handler.push_context("menu"));
+        assert_eq!(
+            vec![(
+                ConflictSource::Context("menu".to_string()),
+                BindingConflict::Action("confirm".to_string())
+            )],
+            handler.binding_conflicts(input, Some(&players))
+        );
+
+        // The first controller of player 1 is the controller with id 3.
+        let input = CapturedInput::Button(Button::Controller(3, a));
+        assert_eq!(
+            vec![(
+                ConflictSource::Player(1),
+                BindingConflict::Action("accept".to_string())
+            )],
+            handler.binding_conflicts(input, Some(&players))
+        );
+    }
+}
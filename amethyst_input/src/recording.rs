@@ -0,0 +1,384 @@
+//! Recording of input streams and their deterministic playback.
+
+use std::mem;
+
+use winit::{
+    dpi::LogicalPosition, DeviceEvent, ElementState, Event, KeyboardInput, MouseButton,
+    MouseScrollDelta, Touch, TouchPhase, VirtualKeyCode, WindowEvent,
+};
+
+use crate::controller::ControllerEvent;
+
+/// A raw input, as consumed by the `InputHandler`.
+///
+/// Unlike `winit::Event`s, raw inputs carry no window or device ids, so they can be serialized
+/// into an `InputRecording`. Cursor positions are stored in physical pixels, so playback
+/// does not depend on the hidpi factor of the window.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RawInput {
+    /// A key was pressed.
+    KeyPressed {
+        /// The `VirtualKeyCode` of the key.
+        key_code: VirtualKeyCode,
+        /// The scancode of the key.
+        scancode: u32,
+    },
+    /// A key was released.
+    KeyReleased {
+        /// The `VirtualKeyCode` of the key.
+        key_code: VirtualKeyCode,
+        /// The scancode of the key.
+        scancode: u32,
+    },
+    /// A unicode character was received by the window.
+    KeyTyped(char),
+    /// A mouse button was pressed.
+    MouseButtonPressed(MouseButton),
+    /// A mouse button was released.
+    MouseButtonReleased(MouseButton),
+    /// The cursor was moved to a new position, in physical pixels.
+    CursorMoved {
+        /// The x coordinate of the cursor.
+        x: f64,
+        /// The y coordinate of the cursor.
+        y: f64,
+    },
+    /// The mouse device was moved.
+    MouseMoved {
+        /// The amount the mouse moved horizontally.
+        delta_x: f64,
+        /// The amount the mouse moved vertically.
+        delta_y: f64,
+    },
+    /// The mouse wheel was scrolled.
+    MouseWheelMoved {
+        /// The amount scrolled horizontally.
+        delta_x: f64,
+        /// The amount scrolled vertically.
+        delta_y: f64,
+    },
+    /// A touch started, moved or ended.
+    Touch {
+        /// The id of the touch.
+        id: u64,
+        /// The phase of the touch.
+        phase: TouchPhase,
+        /// The horizontal position in physical pixels.
+        x: f64,
+        /// The vertical position in physical pixels.
+        y: f64,
+    },
+    /// The window lost focus.
+    FocusLost,
+    /// A controller event.
+    Controller(ControllerEvent),
+}
+
+impl RawInput {
+    /// Converts a `winit::Event` into a raw input, returning `None` for events which are
+    /// ignored by the `InputHandler`.
+    pub fn from_event(event: &Event, hidpi: f64) -> Option<Self> {
+        match *event {
+            Event::WindowEvent { ref event, .. } => match *event {
+                WindowEvent::ReceivedCharacter(c) => Some(RawInput::KeyTyped(c)),
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state,
+                            virtual_keycode: Some(key_code),
+                            scancode,
+                            ..
+                        },
+                    ..
+                } => Some(match state {
+                    ElementState::Pressed => RawInput::KeyPressed { key_code, scancode },
+                    ElementState::Released => RawInput::KeyReleased { key_code, scancode },
+                }),
+                WindowEvent::MouseInput { state, button, .. } => Some(match state {
+                    ElementState::Pressed => RawInput::MouseButtonPressed(button),
+                    ElementState::Released => RawInput::MouseButtonReleased(button),
+                }),
+                WindowEvent::CursorMoved {
+                    position: LogicalPosition { x, y },
+                    ..
+                } => Some(RawInput::CursorMoved {
+                    x: x * hidpi,
+                    y: y * hidpi,
+                }),
+                WindowEvent::Touch(Touch {
+                    id,
+                    phase,
+                    location: LogicalPosition { x, y },
+                    ..
+                }) => Some(RawInput::Touch {
+                    id,
+                    phase,
+                    x: x * hidpi,
+                    y: y * hidpi,
+                }),
+                WindowEvent::Focused(false) => Some(RawInput::FocusLost),
+                _ => None,
+            },
+            Event::DeviceEvent { ref event, .. } => match *event {
+                DeviceEvent::MouseMotion {
+                    delta: (delta_x, delta_y),
+                } => Some(RawInput::MouseMoved { delta_x, delta_y }),
+                DeviceEvent::MouseWheel {
+                    delta: MouseScrollDelta::LineDelta(delta_x, delta_y),
+                } => Some(RawInput::MouseWheelMoved {
+                    delta_x: delta_x.into(),
+                    delta_y: delta_y.into(),
+                }),
+                DeviceEvent::MouseWheel {
+                    delta: MouseScrollDelta::PixelDelta(LogicalPosition { x, y }),
+                } => Some(RawInput::MouseWheelMoved {
+                    delta_x: x,
+                    delta_y: y,
+                }),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// The raw inputs received during a single frame of an `InputRecording`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// The index of the frame, counted from the start of the recording.
+    pub frame: u64,
+    /// The time of the frame in seconds, counted from the start of the recording.
+    pub time: f64,
+    /// The inputs received during the frame, in order.
+    pub inputs: Vec<RawInput>,
+}
+
+/// A timestamped and frame-indexed stream of raw inputs.
+///
+/// Recordings only contain frames during which input was received. They implement
+/// `amethyst_config::Config`, so they can be written to and loaded from RON files with
+/// `Config::write` and `Config::load_no_fallback`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputRecording {
+    /// The number of frames recorded, including frames without input.
+    pub length: u64,
+    /// The recorded frames, ordered by frame index.
+    pub frames: Vec<RecordedFrame>,
+}
+
+#[derive(Debug)]
+enum RecorderState {
+    Idle,
+    Recording(InputRecording),
+    Playing {
+        recording: InputRecording,
+        next: usize,
+    },
+}
+
+impl Default for RecorderState {
+    fn default() -> Self {
+        RecorderState::Idle
+    }
+}
+
+/// Resource recording the raw input received by the `InputHandler`, or playing back a
+/// recording in place of live input.
+///
+/// While a recording is played back, live input is discarded. Recorded inputs are replayed
+/// at the same frame index they were received at, so with a fixed time step playback is
+/// deterministic.
+///
+/// ### Example:
+///
+/// ```rust,ignore
+/// world.write_resource::<InputRecorder>().start_recording();
+/// // ...
+/// let recording = world.write_resource::<InputRecorder>().stop_recording();
+/// recording.write("replay.ron")?;
+/// // ...
+/// let recording = InputRecording::load_no_fallback("replay.ron")?;
+/// world.write_resource::<InputRecorder>().play(recording);
+/// ```
+#[derive(Debug, Default)]
+pub struct InputRecorder {
+    state: RecorderState,
+    started: bool,
+    frame: u64,
+    start_time: f64,
+    time: f64,
+}
+
+impl InputRecorder {
+    /// Creates an idle recorder.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Starts recording, discarding any recording or playback in progress.
+    ///
+    /// Frame indices and timestamps are counted from the next frame.
+    pub fn start_recording(&mut self) {
+        self.state = RecorderState::Recording(InputRecording::default());
+        self.restart();
+    }
+
+    /// Stops recording and returns everything recorded since `start_recording`.
+    ///
+    /// Returns an empty recording if the recorder was not recording.
+    pub fn stop_recording(&mut self) -> InputRecording {
+        match mem::replace(&mut self.state, RecorderState::Idle) {
+            RecorderState::Recording(mut recording) => {
+                if self.started {
+                    recording.length = self.frame + 1;
+                }
+                recording
+            }
+            state => {
+                self.state = state;
+                InputRecording::default()
+            }
+        }
+    }
+
+    /// Starts playing back a recording, discarding any recording or playback in progress.
+    ///
+    /// The first recorded frame is played back during the next frame.
+    pub fn play(&mut self, recording: InputRecording) {
+        self.state = RecorderState::Playing { recording, next: 0 };
+        self.restart();
+    }
+
+    /// Stops playback, returning to live input.
+    pub fn stop_playback(&mut self) {
+        if self.is_playing() {
+            self.state = RecorderState::Idle;
+        }
+    }
+
+    /// Returns `true` while recording.
+    pub fn is_recording(&self) -> bool {
+        match self.state {
+            RecorderState::Recording(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` while playing back a recording.
+    ///
+    /// Playback stops automatically after the last recorded frame.
+    pub fn is_playing(&self) -> bool {
+        match self.state {
+            RecorderState::Playing { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the index of the current frame of the recording or playback.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Returns the time of the current frame in seconds, counted from the start of the
+    /// recording or playback.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Records a raw input for the current frame, if recording.
+    ///
+    /// This is called by the `InputSystem`, and should be called by systems feeding other
+    /// inputs, like controller events, into the `InputHandler`.
+    pub fn record(&mut self, input: &RawInput) {
+        if let RecorderState::Recording(ref mut recording) = self.state {
+            let frame = self.frame;
+            if recording.frames.last().map_or(true, |f| f.frame != frame) {
+                recording.frames.push(RecordedFrame {
+                    frame,
+                    time: self.time,
+                    inputs: Vec::new(),
+                });
+            }
+            let last = recording.frames.len() - 1;
+            recording.frames[last].inputs.push(input.clone());
+        }
+    }
+
+    /// Advances the recorder to the next frame, and returns the inputs to play back during it.
+    ///
+    /// `time` is the absolute time of the new frame in seconds. This is called by the
+    /// `InputSystem` once per frame, before any input of the frame is handled.
+    pub(crate) fn advance(&mut self, time: f64) -> Vec<RawInput> {
+        if self.started {
+            self.frame += 1;
+        } else {
+            self.started = true;
+            self.start_time = time;
+        }
+        self.time = time - self.start_time;
+
+        let mut inputs = Vec::new();
+        let mut finished = false;
+        if let RecorderState::Playing {
+            ref recording,
+            ref mut next,
+        } = self.state
+        {
+            while let Some(frame) = recording.frames.get(*next) {
+                if frame.frame > self.frame {
+                    break;
+                }
+                inputs.extend(frame.inputs.iter().cloned());
+                *next += 1;
+            }
+            finished = *next >= recording.frames.len() && self.frame + 1 >= recording.length;
+        }
+        if finished {
+            self.state = RecorderState::Idle;
+        }
+        inputs
+    }
+
+    fn restart(&mut self) {
+        self.started = false;
+        self.frame = 0;
+        self.start_time = 0.0;
+        self.time = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn play_back_recorded_frames() {
+        let mut recorder = InputRecorder::new();
+        recorder.start_recording();
+        assert!(recorder.advance(10.0).is_empty());
+        recorder.record(&RawInput::KeyTyped('a'));
+        recorder.advance(10.5);
+        recorder.advance(11.0);
+        recorder.record(&RawInput::FocusLost);
+        recorder.advance(11.5);
+        let recording = recorder.stop_recording();
+        assert_eq!(4, recording.length);
+        assert_eq!(2, recording.frames.len());
+        assert_eq!(1.0, recording.frames[1].time);
+
+        recorder.play(recording);
+        let mut frames = Vec::new();
+        while recorder.is_playing() {
+            frames.push(recorder.advance(0.0));
+        }
+        assert_eq!(
+            vec![
+                vec![RawInput::KeyTyped('a')],
+                vec![],
+                vec![RawInput::FocusLost],
+                vec![],
+            ],
+            frames
+        );
+    }
+}
@@ -0,0 +1,60 @@
+//! Force feedback requests for controllers.
+
+/// A request to rumble controllers, sent through an `EventChannel<ControllerRumble>`.
+///
+/// With the `sdl_controller` feature the `SdlEventsSystem` plays the rumble on controllers
+/// supporting haptic feedback. Without it, or for controllers without rumble motors, requests
+/// are ignored, so gameplay code can send them unconditionally.
+///
+/// ### Example:
+///
+/// ```rust,ignore
+/// fn run(&mut self, mut rumble: Write<'a, EventChannel<ControllerRumble>>) {
+///     rumble.single_write(ControllerRumble::new(0, 0.75, 0.2));
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ControllerRumble {
+    /// The id of the controller to rumble, as used by `InputHandler`, or `None` to rumble all
+    /// connected controllers.
+    pub controller_id: Option<u32>,
+    /// The strength of the rumble, from 0 to 1.
+    pub strength: f32,
+    /// The duration of the rumble in seconds.
+    pub duration: f64,
+}
+
+impl ControllerRumble {
+    /// Rumbles a single controller with the given strength, from 0 to 1, for `duration` seconds.
+    pub fn new(controller_id: u32, strength: f32, duration: f64) -> Self {
+        ControllerRumble {
+            controller_id: Some(controller_id),
+            strength,
+            duration,
+        }
+    }
+
+    /// Rumbles all connected controllers with the given strength, from 0 to 1, for `duration`
+    /// seconds.
+    pub fn all(strength: f32, duration: f64) -> Self {
+        ControllerRumble {
+            controller_id: None,
+            strength,
+            duration,
+        }
+    }
+
+    /// Stops the rumble of a controller, or of all controllers if `controller_id` is `None`.
+    pub fn stop(controller_id: Option<u32>) -> Self {
+        ControllerRumble {
+            controller_id,
+            strength: 0.0,
+            duration: 0.0,
+        }
+    }
+
+    /// Returns true if the request stops the rumble instead of starting one.
+    pub fn is_stop(&self) -> bool {
+        self.strength <= 0.0 || self.duration <= 0.0
+    }
+}
@@ -4,17 +4,18 @@ use sdl2::{
     self,
     controller::{AddMappingError, Axis, Button, GameController},
     event::Event,
-    EventPump, GameControllerSubsystem, Sdl,
+    haptic::Haptic,
+    EventPump, GameControllerSubsystem, HapticSubsystem, Sdl,
 };
 
 use amethyst_core::{
-    shrev::EventChannel,
-    specs::prelude::{Resources, RunNow, SystemData, Write},
+    shrev::{EventChannel, ReaderId},
+    specs::prelude::{Read, Resources, RunNow, SystemData, Write},
 };
 
 use super::{
     controller::{ControllerAxis, ControllerButton, ControllerEvent},
-    InputEvent, InputHandler,
+    ControllerRumble, InputEvent, InputHandler, InputRecorder, RawInput,
 };
 
 /// A collection of errors that can occur in the SDL system.
@@ -50,7 +51,11 @@ pub enum ControllerMappings {
     FromString(String),
 }
 
-/// A system that pumps SDL events into the `amethyst_input` APIs.
+/// A system that pumps SDL events into the `amethyst_input` APIs, and plays `ControllerRumble`
+/// requests on controllers supporting haptic feedback.
+///
+/// The system runs thread local, after the `InputSystem`, so controller input is queued with
+/// `InputHandler::queue_raw_input` and processed at the start of the next frame.
 pub struct SdlEventsSystem<AX, AC>
 where
     AX: Hash + Eq,
@@ -62,12 +67,19 @@ where
     controller_subsystem: GameControllerSubsystem,
     /// Vector of opened controllers and their corresponding joystick indices
     opened_controllers: Vec<(u32, GameController)>,
+    /// The haptic subsystem, if SDL supports haptic feedback on this platform
+    haptic_subsystem: Option<HapticSubsystem>,
+    /// Rumble motors of opened controllers and the corresponding controller instance ids
+    haptics: Vec<(u32, Haptic)>,
+    rumble_reader: Option<ReaderId<ControllerRumble>>,
     marker: PhantomData<(AX, AC)>,
 }
 
 type SdlEventsData<'a, AX, AC> = (
     Write<'a, InputHandler<AX, AC>>,
     Write<'a, EventChannel<InputEvent<AC>>>,
+    Option<Write<'a, InputRecorder>>,
+    Read<'a, EventChannel<ControllerRumble>>,
 );
 
 impl<'a, AX, AC> RunNow<'a> for SdlEventsSystem<AX, AC>
@@ -76,7 +88,8 @@ where
     AC: Hash + Eq + Clone + Send + Sync + 'static,
 {
     fn run_now(&mut self, res: &'a Resources) {
-        let (mut handler, mut output) = SdlEventsData::fetch(res);
+        let (mut handler, _, recorder, rumble) = SdlEventsData::fetch(res);
+        let playing = recorder.as_ref().map_or(false, |r| r.is_playing());
 
         let mut event_pump = self
             .event_pump
@@ -84,13 +97,51 @@ where
             .expect("Unreachable: `event_pump` is always reinserted after `take`");
         for event in event_pump.poll_iter() {
             // handle appropriate events locally
-            self.handle_sdl_event(&event, &mut handler, &mut output);
+            if let Some(event) = self.handle_sdl_event(&event) {
+                // live input is replaced by the recording during playback
+                if !playing {
+                    // This system runs after the `InputSystem`, so the event is processed at
+                    // the start of the next frame, where all systems see the actions it changes.
+                    handler.queue_raw_input(RawInput::Controller(event));
+                }
+            }
         }
         self.event_pump = Some(event_pump);
+
+        let requests =
+            rumble.read(self.rumble_reader.as_mut().expect(
+                "`SdlEventsSystem::setup` was not called before `SdlEventsSystem::run_now`",
+            ));
+        for request in requests {
+            let which = match request.controller_id {
+                Some(controller_id) => match handler.controller_id_to_idx(controller_id) {
+                    Some(which) => Some(which),
+                    None => continue,
+                },
+                None => None,
+            };
+            for (_, haptic) in self
+                .haptics
+                .iter_mut()
+                .filter(|entry| which.map_or(true, |which| which == entry.0))
+            {
+                if request.is_stop() {
+                    haptic.rumble_stop();
+                } else {
+                    let strength = request.strength.min(1.0);
+                    haptic.rumble_play(strength, (request.duration * 1000.0) as u32);
+                }
+            }
+        }
     }
 
     fn setup(&mut self, res: &mut Resources) {
-        let (mut handler, mut output) = SdlEventsData::fetch(res);
+        <SdlEventsData<'_, AX, AC> as SystemData<'_>>::setup(res);
+        self.rumble_reader = Some(
+            res.fetch_mut::<EventChannel<ControllerRumble>>()
+                .register_reader(),
+        );
+        let (mut handler, mut output, _, _) = SdlEventsData::fetch(res);
         self.initialize_controllers(&mut handler, &mut output);
     }
 }
@@ -124,73 +175,56 @@ where
             None => {}
         };
 
+        // rumble is optional, controllers still work without haptic support
+        let haptic_subsystem = sdl_context.haptic().ok();
+
         Ok(SdlEventsSystem {
             sdl_context,
             event_pump: Some(event_pump),
             controller_subsystem,
             opened_controllers: vec![],
+            haptic_subsystem,
+            haptics: vec![],
+            rumble_reader: None,
             marker: PhantomData,
         })
     }
 
-    fn handle_sdl_event(
-        &mut self,
-        event: &Event,
-        handler: &mut InputHandler<AX, AC>,
-        output: &mut EventChannel<InputEvent<AC>>,
-    ) {
+    /// Converts an SDL event into a controller event, opening and closing controllers as they
+    /// are connected and disconnected.
+    fn handle_sdl_event(&mut self, event: &Event) -> Option<ControllerEvent> {
         use self::ControllerEvent::*;
 
         match *event {
             Event::ControllerAxisMotion {
                 which, axis, value, ..
-            } => {
-                handler.send_controller_event(
-                    &ControllerAxisMoved {
-                        which: which as u32,
-                        axis: axis.into(),
-                        value: if value > 0 {
-                            (value as f64) / 32767f64
-                        } else {
-                            (value as f64) / 32768f64
-                        },
-                    },
-                    output,
-                );
-            }
-            Event::ControllerButtonDown { which, button, .. } => {
-                handler.send_controller_event(
-                    &ControllerButtonPressed {
-                        which: which as u32,
-                        button: button.into(),
-                    },
-                    output,
-                );
-            }
-            Event::ControllerButtonUp { which, button, .. } => {
-                handler.send_controller_event(
-                    &ControllerButtonReleased {
-                        which: which as u32,
-                        button: button.into(),
-                    },
-                    output,
-                );
-            }
+            } => Some(ControllerAxisMoved {
+                which: which as u32,
+                axis: axis.into(),
+                value: if value > 0 {
+                    (value as f64) / 32767f64
+                } else {
+                    (value as f64) / 32768f64
+                },
+            }),
+            Event::ControllerButtonDown { which, button, .. } => Some(ControllerButtonPressed {
+                which: which as u32,
+                button: button.into(),
+            }),
+            Event::ControllerButtonUp { which, button, .. } => Some(ControllerButtonReleased {
+                which: which as u32,
+                button: button.into(),
+            }),
             Event::ControllerDeviceRemoved { which, .. } => {
                 self.close_controller(which as u32);
-                handler.send_controller_event(
-                    &ControllerDisconnected {
-                        which: which as u32,
-                    },
-                    output,
-                );
-            }
-            Event::ControllerDeviceAdded { which, .. } => {
-                self.open_controller(which).map(|idx| {
-                    handler.send_controller_event(&ControllerConnected { which: idx }, output);
-                });
+                Some(ControllerDisconnected {
+                    which: which as u32,
+                })
             }
-            _ => {}
+            Event::ControllerDeviceAdded { which, .. } => self
+                .open_controller(which)
+                .map(|idx| ControllerConnected { which: idx }),
+            _ => None,
         }
     }
 
@@ -199,6 +233,13 @@ where
             self.controller_subsystem.open(which).ok().map(|c| {
                 let id = c.instance_id() as u32;
                 self.opened_controllers.push((which, c));
+                if let Some(haptic) = self
+                    .haptic_subsystem
+                    .as_ref()
+                    .and_then(|haptics| haptics.open_from_joystick_id(which).ok())
+                {
+                    self.haptics.push((id, haptic));
+                }
                 id
             })
         } else {
@@ -214,6 +255,7 @@ where
         if let Some(i) = index {
             self.opened_controllers.swap_remove(i);
         }
+        self.haptics.retain(|&(id, _)| id != which);
     }
 
     fn initialize_controllers(
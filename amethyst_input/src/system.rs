@@ -7,15 +7,21 @@ use winit::Event;
 use amethyst_core::{
     shrev::{EventChannel, ReaderId},
     specs::prelude::{Read, ReadExpect, Resources, System, Write},
+    timing::Time,
 };
 use amethyst_renderer::ScreenDimensions;
 
-use crate::{Bindings, InputEvent, InputHandler};
+use crate::{Bindings, InputEvent, InputHandler, InputRecorder, RawInput};
 
 /// Input system
 ///
 /// Will read `winit::Event` from `EventHandler<winit::Event>`, process them with `InputHandler`,
-/// and push the results in `EventHandler<InputEvent>`.
+/// and push the results in `EventHandler<InputEvent>`. Input queued with
+/// `InputHandler::queue_raw_input` since the last frame is processed first.
+///
+/// The processed input is recorded by the `InputRecorder` while recording. While the
+/// `InputRecorder` plays back a recording, `winit::Event`s are discarded and the recorded
+/// input is processed instead.
 pub struct InputSystem<AX, AC>
 where
     AX: Hash + Eq,
@@ -38,16 +44,17 @@ where
         }
     }
 
-    fn process_event(
-        event: &Event,
+    fn process_input(
+        input: &RawInput,
         handler: &mut InputHandler<AX, AC>,
         output: &mut EventChannel<InputEvent<AC>>,
-        hidpi: f64,
+        recorder: &mut InputRecorder,
     ) where
         AX: Hash + Eq + Clone + Send + Sync + 'static,
         AC: Hash + Eq + Clone + Send + Sync + 'static,
     {
-        handler.send_event(event, output, hidpi);
+        recorder.record(input);
+        handler.send_raw_input(input, output);
     }
 }
 
@@ -61,21 +68,38 @@ where
         Write<'a, InputHandler<AX, AC>>,
         Write<'a, EventChannel<InputEvent<AC>>>,
         ReadExpect<'a, ScreenDimensions>,
+        Write<'a, InputRecorder>,
+        Read<'a, Time>,
     );
 
-    fn run(&mut self, (input, mut handler, mut output, screen_dimensions): Self::SystemData) {
-        for event in input.read(
+    fn run(
+        &mut self,
+        (input, mut handler, mut output, screen_dimensions, mut recorder, time): Self::SystemData,
+    ) {
+        handler.start_frame(time.absolute_time_seconds(), &mut output);
+        let queued = handler.take_queued_input();
+        let playing = recorder.is_playing();
+        for recorded in recorder.advance(time.absolute_time_seconds()) {
+            handler.send_raw_input(&recorded, &mut output);
+        }
+
+        let events = input.read(
             &mut self
                 .reader
                 .as_mut()
                 .expect("`InputSystem::setup` was not called before `InputSystem::run`"),
-        ) {
-            Self::process_event(
-                event,
-                &mut *handler,
-                &mut *output,
-                screen_dimensions.hidpi_factor(),
-            );
+        );
+        if playing {
+            // live input is replaced by the recording
+            return;
+        }
+        for raw in queued {
+            Self::process_input(&raw, &mut *handler, &mut *output, &mut *recorder);
+        }
+        for event in events {
+            if let Some(raw) = RawInput::from_event(event, screen_dimensions.hidpi_factor()) {
+                Self::process_input(&raw, &mut *handler, &mut *output, &mut *recorder);
+            }
         }
     }
 
@@ -88,3 +112,67 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::specs::{RunNow, World};
+
+    use crate::{Button, ControllerButton, ControllerEvent, RawInput};
+
+    use super::*;
+
+    fn queue(world: &World, event: ControllerEvent) {
+        world
+            .write_resource::<InputHandler<String, String>>()
+            .queue_raw_input(RawInput::Controller(event));
+    }
+
+    #[test]
+    fn queued_controller_input_is_just_pressed() {
+        let mut world = World::new();
+        world.add_resource(ScreenDimensions::new(100, 100, 1.0));
+        let mut system = InputSystem::<String, String>::new(None);
+        RunNow::setup(&mut system, &mut world.res);
+        world
+            .write_resource::<InputHandler<String, String>>()
+            .bindings
+            .insert_action_binding(
+                "jump".to_string(),
+                vec![Button::Controller(0, ControllerButton::A)],
+            );
+
+        // Queued like the `SdlEventsSystem` does after all other systems ran.
+        queue(&world, ControllerEvent::ControllerConnected { which: 0 });
+        queue(
+            &world,
+            ControllerEvent::ControllerButtonPressed {
+                which: 0,
+                button: ControllerButton::A,
+            },
+        );
+        system.run_now(&world.res);
+        {
+            let handler = world.read_resource::<InputHandler<String, String>>();
+            assert!(handler.action_just_pressed("jump"));
+        }
+
+        system.run_now(&world.res);
+        {
+            let handler = world.read_resource::<InputHandler<String, String>>();
+            assert!(!handler.action_just_pressed("jump"));
+            assert_eq!(Some(true), handler.action_is_down("jump"));
+        }
+
+        queue(
+            &world,
+            ControllerEvent::ControllerButtonReleased {
+                which: 0,
+                button: ControllerButton::A,
+            },
+        );
+        system.run_now(&world.res);
+        let handler = world.read_resource::<InputHandler<String, String>>();
+        assert!(handler.action_just_released("jump"));
+        assert_eq!(Some(false), handler.action_is_down("jump"));
+    }
+}
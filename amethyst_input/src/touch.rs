@@ -0,0 +1,178 @@
+//! Touch points and gestures recognized from them.
+
+/// A finger touching the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TouchPoint {
+    /// The id of the touch, unique among the touches currently on the screen.
+    pub id: u64,
+    /// The current position of the touch in physical pixels.
+    pub position: (f64, f64),
+    /// The position the touch started at in physical pixels.
+    pub start_position: (f64, f64),
+    /// The time the touch started at in seconds.
+    pub start_time: f64,
+}
+
+impl TouchPoint {
+    /// Returns the distance the touch moved from where it started, in physical pixels.
+    pub fn distance_moved(&self) -> f64 {
+        let (dx, dy) = self.delta();
+        (dx * dx + dy * dy).sqrt()
+    }
+
+    fn delta(&self) -> (f64, f64) {
+        (
+            self.position.0 - self.start_position.0,
+            self.position.1 - self.start_position.1,
+        )
+    }
+}
+
+/// A gesture recognized from touch input.
+///
+/// Gestures can be bound like buttons with `Button::Gesture`, they are down during the frame
+/// they are recognized in. Pinching is an axis, see `Axis::Pinch`.
+#[derive(Eq, PartialEq, Debug, Copy, Clone, Serialize, Deserialize)]
+pub enum Gesture {
+    /// A single finger touched the screen briefly without moving.
+    Tap,
+    /// A single finger moved quickly across the screen in the given direction.
+    Swipe(SwipeDirection),
+}
+
+/// The direction of a swipe.
+#[derive(Eq, PartialEq, Debug, Copy, Clone, Serialize, Deserialize)]
+pub enum SwipeDirection {
+    /// Swipe towards the top of the screen.
+    Up,
+    /// Swipe towards the bottom of the screen.
+    Down,
+    /// Swipe towards the left of the screen.
+    Left,
+    /// Swipe towards the right of the screen.
+    Right,
+}
+
+/// Thresholds used to recognize gestures, distances are in physical pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GestureSettings {
+    /// The longest a touch may last in seconds to be a tap.
+    pub tap_max_duration: f64,
+    /// The furthest a touch may move to be a tap.
+    pub tap_max_distance: f64,
+    /// The longest a touch may last in seconds to be a swipe.
+    pub swipe_max_duration: f64,
+    /// The shortest distance a touch has to move to be a swipe.
+    pub swipe_min_distance: f64,
+}
+
+impl Default for GestureSettings {
+    fn default() -> Self {
+        GestureSettings {
+            tap_max_duration: 0.3,
+            tap_max_distance: 20.0,
+            swipe_max_duration: 0.5,
+            swipe_min_distance: 100.0,
+        }
+    }
+}
+
+impl GestureSettings {
+    /// Recognizes the gesture made by a single touch ending at `time`.
+    pub(crate) fn recognize(&self, touch: &TouchPoint, time: f64) -> Option<Gesture> {
+        let duration = time - touch.start_time;
+        let distance = touch.distance_moved();
+        if duration <= self.tap_max_duration && distance <= self.tap_max_distance {
+            return Some(Gesture::Tap);
+        }
+        if duration <= self.swipe_max_duration && distance >= self.swipe_min_distance {
+            let (dx, dy) = touch.delta();
+            let direction = if dx.abs() >= dy.abs() {
+                if dx > 0.0 {
+                    SwipeDirection::Right
+                } else {
+                    SwipeDirection::Left
+                }
+            } else if dy > 0.0 {
+                SwipeDirection::Down
+            } else {
+                SwipeDirection::Up
+            };
+            return Some(Gesture::Swipe(direction));
+        }
+        None
+    }
+}
+
+/// Returns the distance between the first two touches, if there are at least two.
+pub(crate) fn pinch_distance(touches: &[TouchPoint]) -> Option<f64> {
+    if touches.len() < 2 {
+        return None;
+    }
+    let (a, b) = (touches[0].position, touches[1].position);
+    let (dx, dy) = (a.0 - b.0, a.1 - b.1);
+    Some((dx * dx + dy * dy).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::TouchPhase;
+
+    use amethyst_core::shrev::EventChannel;
+
+    use crate::{Button, InputHandler, RawInput};
+
+    use super::*;
+
+    fn touch(to: (f64, f64)) -> TouchPoint {
+        TouchPoint {
+            id: 0,
+            position: to,
+            start_position: (100.0, 100.0),
+            start_time: 1.0,
+        }
+    }
+
+    #[test]
+    fn recognize_gestures() {
+        let settings = GestureSettings::default();
+        assert_eq!(
+            Some(Gesture::Tap),
+            settings.recognize(&touch((105.0, 100.0)), 1.1)
+        );
+        assert_eq!(None, settings.recognize(&touch((105.0, 100.0)), 2.0));
+        assert_eq!(
+            Some(Gesture::Swipe(SwipeDirection::Up)),
+            settings.recognize(&touch((120.0, -50.0)), 1.25)
+        );
+        assert_eq!(
+            Some(Gesture::Swipe(SwipeDirection::Right)),
+            settings.recognize(&touch((300.0, 150.0)), 1.25)
+        );
+        assert_eq!(None, settings.recognize(&touch((150.0, 100.0)), 1.25));
+    }
+
+    #[test]
+    fn tap_is_down_for_one_frame() {
+        let mut handler = InputHandler::<String, String>::new();
+        let mut events = EventChannel::new();
+        handler.start_frame(1.0, &mut events);
+        for &phase in &[TouchPhase::Started, TouchPhase::Ended] {
+            handler.send_raw_input(
+                &RawInput::Touch {
+                    id: 3,
+                    phase,
+                    x: 10.0,
+                    y: 10.0,
+                },
+                &mut events,
+            );
+        }
+        assert!(handler.button_is_down(Button::Gesture(Gesture::Tap)));
+        assert_eq!(None, handler.touch(3));
+
+        handler.start_frame(1.1, &mut events);
+        assert!(!handler.button_is_down(Button::Gesture(Gesture::Tap)));
+    }
+}
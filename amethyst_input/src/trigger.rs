@@ -0,0 +1,275 @@
+//! Action triggers detecting holds, taps, double-taps and sequences of actions.
+
+use std::hash::Hash;
+
+use fnv::FnvHashMap as HashMap;
+
+/// A trigger derived from the timing of one or more actions.
+///
+/// Triggers are registered with `Bindings::insert_trigger` or in the `triggers` section of the
+/// bindings file, and send an `InputEvent::ActionTriggered` with their id when they fire:
+///
+/// ```ron
+/// (
+///     axes: {},
+///     actions: {
+///         "jump": [[Key(Space)]],
+///         "punch": [[Key(J)]],
+///         "down": [[Key(S)]],
+///         "forward": [[Key(D)]],
+///     },
+///     triggers: {
+///         "high_jump": Hold(action: "jump", duration: 0.5),
+///         "double_jump": DoubleTap(action: "jump", window: 0.3),
+///         "fireball": Sequence(actions: ["down", "forward", "punch"], window: 0.25),
+///     },
+/// )
+/// ```
+///
+/// All durations are in seconds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ActionTrigger<AC> {
+    /// Fires once while the action is held down for at least `duration`.
+    Hold {
+        /// The held action.
+        action: AC,
+        /// The duration the action needs to be held for.
+        duration: f64,
+    },
+    /// Fires when the action is released at most `max_duration` after it was pressed.
+    Tap {
+        /// The tapped action.
+        action: AC,
+        /// The maximum duration between press and release.
+        max_duration: f64,
+    },
+    /// Fires when the action is released at least `min_duration` after it was pressed.
+    LongPress {
+        /// The long-pressed action.
+        action: AC,
+        /// The minimum duration between press and release.
+        min_duration: f64,
+    },
+    /// Fires when the action is pressed a second time at most `window` after the first press.
+    DoubleTap {
+        /// The double-tapped action.
+        action: AC,
+        /// The maximum duration between both presses.
+        window: f64,
+    },
+    /// Fires when the actions are pressed in order, each at most `window` after the previous one.
+    ///
+    /// Pressing any other action of the sequence out of order restarts it, while actions which
+    /// are not part of the sequence are ignored.
+    Sequence {
+        /// The actions to press, in order.
+        actions: Vec<AC>,
+        /// The maximum duration between two consecutive presses.
+        window: f64,
+    },
+}
+
+/// Runtime state of an `ActionTrigger`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TriggerState {
+    armed: bool,
+    step: usize,
+    last: Option<f64>,
+}
+
+impl<AC> ActionTrigger<AC>
+where
+    AC: Hash + Eq,
+{
+    /// Updates the trigger for an action pressed at `time`, returning `true` if it fires.
+    pub(crate) fn on_press(&self, pressed: &AC, time: f64, state: &mut TriggerState) -> bool {
+        match *self {
+            ActionTrigger::Hold { ref action, .. } => {
+                if action == pressed {
+                    state.armed = true;
+                }
+                false
+            }
+            ActionTrigger::DoubleTap {
+                ref action, window, ..
+            } => {
+                if action != pressed {
+                    return false;
+                }
+                if state.last.map_or(false, |last| time - last <= window) {
+                    state.last = None;
+                    true
+                } else {
+                    state.last = Some(time);
+                    false
+                }
+            }
+            ActionTrigger::Sequence {
+                ref actions,
+                window,
+            } => {
+                if !actions.contains(pressed) {
+                    return false;
+                }
+                if state.last.map_or(true, |last| time - last > window) {
+                    state.step = 0;
+                }
+                if actions.get(state.step) != Some(pressed) {
+                    state.step = 0;
+                }
+                if actions.get(state.step) != Some(pressed) {
+                    state.last = None;
+                    return false;
+                }
+                state.step += 1;
+                state.last = Some(time);
+                if state.step == actions.len() {
+                    state.step = 0;
+                    state.last = None;
+                    true
+                } else {
+                    false
+                }
+            }
+            ActionTrigger::Tap { .. } | ActionTrigger::LongPress { .. } => false,
+        }
+    }
+
+    /// Updates the trigger for an action pressed at `pressed_at` and released at `time`,
+    /// returning `true` if it fires.
+    pub(crate) fn on_release(
+        &self,
+        released: &AC,
+        pressed_at: f64,
+        time: f64,
+        state: &mut TriggerState,
+    ) -> bool {
+        match *self {
+            ActionTrigger::Hold { ref action, .. } => {
+                if action == released {
+                    state.armed = false;
+                }
+                false
+            }
+            ActionTrigger::Tap {
+                ref action,
+                max_duration,
+            } => action == released && time - pressed_at <= max_duration,
+            ActionTrigger::LongPress {
+                ref action,
+                min_duration,
+            } => action == released && time - pressed_at >= min_duration,
+            ActionTrigger::DoubleTap { .. } | ActionTrigger::Sequence { .. } => false,
+        }
+    }
+
+    /// Updates the trigger at the start of a frame at `time`, returning `true` if it fires.
+    pub(crate) fn on_frame(
+        &self,
+        actions_down: &HashMap<AC, f64>,
+        time: f64,
+        state: &mut TriggerState,
+    ) -> bool {
+        match *self {
+            ActionTrigger::Hold {
+                ref action,
+                duration,
+            } => {
+                let held = actions_down
+                    .get(action)
+                    .map_or(false, |pressed_at| time - pressed_at >= duration);
+                if state.armed && held {
+                    state.armed = false;
+                    true
+                } else {
+                    false
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequence_restarts_out_of_order() {
+        let trigger = ActionTrigger::Sequence {
+            actions: vec!["down", "forward", "punch"],
+            window: 0.25,
+        };
+        let mut state = TriggerState::default();
+        assert!(!trigger.on_press(&"down", 0.0, &mut state));
+        assert!(!trigger.on_press(&"punch", 0.1, &mut state));
+        assert!(!trigger.on_press(&"down", 0.2, &mut state));
+        assert!(!trigger.on_press(&"jump", 0.3, &mut state));
+        assert!(!trigger.on_press(&"forward", 0.4, &mut state));
+        assert!(trigger.on_press(&"punch", 0.5, &mut state));
+        assert!(!trigger.on_press(&"down", 1.0, &mut state));
+        assert!(!trigger.on_press(&"forward", 1.5, &mut state));
+    }
+
+    #[test]
+    fn double_tap_within_window() {
+        let trigger = ActionTrigger::DoubleTap {
+            action: "jump",
+            window: 0.3,
+        };
+        let mut state = TriggerState::default();
+        assert!(!trigger.on_press(&"jump", 0.0, &mut state));
+        assert!(!trigger.on_press(&"jump", 0.5, &mut state));
+        assert!(trigger.on_press(&"jump", 0.7, &mut state));
+        assert!(!trigger.on_press(&"jump", 0.8, &mut state));
+    }
+
+    #[test]
+    fn hold_fires_once_after_duration() {
+        let trigger = ActionTrigger::Hold {
+            action: "charge",
+            duration: 0.5,
+        };
+        let mut state = TriggerState::default();
+        let mut down = HashMap::default();
+        assert!(!trigger.on_press(&"charge", 0.0, &mut state));
+        down.insert("charge", 0.0);
+        assert!(!trigger.on_frame(&down, 0.4, &mut state));
+        assert!(trigger.on_frame(&down, 0.5, &mut state));
+        assert!(!trigger.on_frame(&down, 0.6, &mut state));
+
+        assert!(!trigger.on_release(&"charge", 0.0, 0.7, &mut state));
+        down.remove("charge");
+        assert!(!trigger.on_press(&"charge", 1.0, &mut state));
+        down.insert("charge", 1.0);
+        assert!(!trigger.on_release(&"charge", 1.0, 1.2, &mut state));
+        down.remove("charge");
+        assert!(!trigger.on_frame(&down, 1.6, &mut state));
+    }
+
+    #[test]
+    fn tap_within_max_duration() {
+        let trigger = ActionTrigger::Tap {
+            action: "jump",
+            max_duration: 0.2,
+        };
+        let mut state = TriggerState::default();
+        assert!(!trigger.on_press(&"jump", 0.0, &mut state));
+        assert!(trigger.on_release(&"jump", 0.0, 0.2, &mut state));
+        assert!(!trigger.on_release(&"jump", 1.0, 1.3, &mut state));
+        assert!(!trigger.on_release(&"fire", 2.0, 2.1, &mut state));
+    }
+
+    #[test]
+    fn long_press_after_min_duration() {
+        let trigger = ActionTrigger::LongPress {
+            action: "jump",
+            min_duration: 0.5,
+        };
+        let mut state = TriggerState::default();
+        assert!(!trigger.on_press(&"jump", 0.0, &mut state));
+        assert!(!trigger.on_release(&"jump", 0.0, 0.4, &mut state));
+        assert!(trigger.on_release(&"jump", 1.0, 1.5, &mut state));
+        assert!(!trigger.on_release(&"fire", 2.0, 3.0, &mut state));
+    }
+}
@@ -0,0 +1,137 @@
+//! Bounding volumes used to cull entities outside the view of the camera.
+
+use amethyst_core::{
+    nalgebra::{Matrix4, Point3, Vector3, Vector4},
+    specs::prelude::{Component, DenseVecStorage},
+};
+
+/// A sphere enclosing the vertices of an entity, in the local space of the entity.
+///
+/// Entities with a `MeshHandle` use the bounds computed from the `MeshData` of their mesh when
+/// it was loaded. Add this component to entities without one, or to override the bounds of the
+/// mesh, for example when vertices are moved by skinning.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BoundingSphere {
+    /// The center of the sphere.
+    pub center: Point3<f32>,
+    /// The radius of the sphere.
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// Creates a bounding sphere.
+    pub fn new(center: Point3<f32>, radius: f32) -> Self {
+        BoundingSphere { center, radius }
+    }
+
+    /// Computes a sphere enclosing the given points, centered on their axis aligned bounding box.
+    ///
+    /// Returns `None` if there are no points.
+    pub fn from_points<I>(points: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = Point3<f32>>,
+        I::IntoIter: Clone,
+    {
+        let points = points.into_iter();
+        let mut rest = points.clone();
+        let first = rest.next()?;
+        let (min, max) = rest.fold((first, first), |(min, max), point| {
+            (
+                Point3::new(min.x.min(point.x), min.y.min(point.y), min.z.min(point.z)),
+                Point3::new(max.x.max(point.x), max.y.max(point.y), max.z.max(point.z)),
+            )
+        });
+        let center = Point3::from_coordinates((min.coords + max.coords) / 2.0);
+        let radius = points
+            .map(|point| (point - center).norm())
+            .fold(0.0, f32::max);
+        Some(BoundingSphere { center, radius })
+    }
+
+    /// Transforms the sphere, scaling its radius by the largest scale of the transformation.
+    pub fn transform(&self, transform: &Matrix4<f32>) -> Self {
+        let scale = (0..3)
+            .map(|i| transform.column(i).xyz().norm())
+            .fold(0.0, f32::max);
+        BoundingSphere {
+            center: transform.transform_point(&self.center),
+            radius: self.radius * scale,
+        }
+    }
+}
+
+impl Component for BoundingSphere {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The six planes enclosing the view of a camera.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frustum {
+    planes: [Vector4<f32>; 6],
+}
+
+impl Frustum {
+    /// Extracts the frustum from the product of the projection and view matrices of a camera.
+    pub fn new(view_projection: &Matrix4<f32>) -> Self {
+        let row = |i: usize| view_projection.row(i).transpose();
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        let normalize = |plane: Vector4<f32>| {
+            let length = Vector3::new(plane.x, plane.y, plane.z).norm();
+            if length > 0.0 {
+                plane / length
+            } else {
+                plane
+            }
+        };
+        Frustum {
+            planes: [
+                normalize(w + x),
+                normalize(w - x),
+                normalize(w + y),
+                normalize(w - y),
+                normalize(w + z),
+                normalize(w - z),
+            ],
+        }
+    }
+
+    /// Returns true if the sphere, in world space, is at least partly inside the frustum.
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        let center = &sphere.center;
+        self.planes.iter().all(|plane| {
+            plane.x * center.x + plane.y * center.y + plane.z * center.z + plane.w >= -sphere.radius
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::nalgebra::Perspective3;
+
+    use super::*;
+
+    #[test]
+    fn cull_spheres_outside_of_the_view() {
+        let frustum = Frustum::new(&Perspective3::new(1.0, 1.0, 0.1, 100.0).to_homogeneous());
+        let sphere = |x, y, z, radius| BoundingSphere::new(Point3::new(x, y, z), radius);
+
+        assert!(frustum.intersects_sphere(&sphere(0.0, 0.0, -10.0, 1.0)));
+        assert!(!frustum.intersects_sphere(&sphere(0.0, 0.0, 10.0, 1.0)));
+        assert!(!frustum.intersects_sphere(&sphere(0.0, 0.0, -200.0, 1.0)));
+        assert!(!frustum.intersects_sphere(&sphere(20.0, 0.0, -10.0, 1.0)));
+        assert!(frustum.intersects_sphere(&sphere(20.0, 0.0, -10.0, 20.0)));
+    }
+
+    #[test]
+    fn sphere_from_points() {
+        let sphere = BoundingSphere::from_points(vec![
+            Point3::new(-1.0, 0.0, 0.0),
+            Point3::new(3.0, 0.0, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+        ])
+        .unwrap();
+        assert_eq!(Point3::new(1.0, 0.5, 0.0), sphere.center);
+        assert!((sphere.radius - (4.0f32 + 0.25).sqrt()).abs() < 1.0e-6);
+        assert_eq!(None, BoundingSphere::from_points(Vec::new()));
+    }
+}
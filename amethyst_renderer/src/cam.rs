@@ -123,6 +123,18 @@ impl<'a> PrefabData<'a> for CameraPrefab {
         };
         storage.insert(entity, Camera { proj }).map(|_| ())
     }
+
+    /// Extracts the projection matrix of the `Camera`, as the projection parameters can not be
+    /// read back.
+    fn extract_from_entity(
+        entity: Entity,
+        storage: &Self::SystemData,
+        _: &[Entity],
+    ) -> Result<Option<Self>, PrefabError> {
+        Ok(storage
+            .get(entity)
+            .map(|camera| CameraPrefab::Matrix(camera.proj)))
+    }
 }
 
 /// Active camera prefab
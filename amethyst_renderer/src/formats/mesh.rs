@@ -1,16 +1,18 @@
 use std::{fmt::Debug, result::Result as StdResult};
 
 use amethyst_assets::{
-    Asset, AssetStorage, Error, Loader, PrefabData, PrefabError, ProcessingState, Result,
+    Asset, AssetStorage, Error, Importer, Loader, PrefabData, PrefabError, ProcessingState, Result,
     ResultExt, SimpleFormat,
 };
 use amethyst_core::{
-    nalgebra::{Vector2, Vector3},
+    nalgebra::{Point3, Vector2, Vector3},
     specs::prelude::{Component, Entity, Read, ReadExpect, VecStorage, WriteStorage},
 };
 
 use crate::{
+    bounds::BoundingSphere,
     mesh::{Mesh, MeshBuilder, MeshHandle},
+    skinning::AnimatedVertexBufferCombination,
     vertex::*,
     wavefront_obj::obj::{
         parse, Normal, NormalIndex, ObjSet, Object, Primitive, TVertex, TextureIndex, Vertex,
@@ -38,7 +40,10 @@ pub enum MeshData {
     PosNormTangTex(Vec<PosNormTangTex>),
 
     /// Create a mesh from a given creator
-    #[serde(skip)]
+    ///
+    /// Only creators returning their vertex buffers from `MeshCreator::vertex_buffers` can be
+    /// serialized, they are deserialized as an `AnimatedComboMeshCreator`.
+    #[serde(with = "serde_creator")]
     Creator(Box<dyn MeshCreator>),
 }
 
@@ -46,6 +51,41 @@ impl Component for MeshData {
     type Storage = VecStorage<Self>;
 }
 
+impl MeshData {
+    /// Computes the sphere enclosing the vertex positions, `None` if there are no vertices.
+    pub fn bounding_sphere(&self) -> Option<BoundingSphere> {
+        fn positions<'a, V: 'a>(
+            vertices: &'a [V],
+            position: fn(&V) -> Vector3<f32>,
+        ) -> impl Iterator<Item = Point3<f32>> + Clone + 'a {
+            vertices
+                .iter()
+                .map(move |vertex| Point3::from_coordinates(position(vertex)))
+        }
+
+        match *self {
+            MeshData::PosColor(ref vertices) => {
+                BoundingSphere::from_points(positions(vertices, |v| v.position))
+            }
+            MeshData::PosColorNorm(ref vertices) => {
+                BoundingSphere::from_points(positions(vertices, |v| v.position))
+            }
+            MeshData::PosTex(ref vertices) => {
+                BoundingSphere::from_points(positions(vertices, |v| v.position))
+            }
+            MeshData::PosNormTex(ref vertices) => {
+                BoundingSphere::from_points(positions(vertices, |v| v.position))
+            }
+            MeshData::PosNormTangTex(ref vertices) => {
+                BoundingSphere::from_points(positions(vertices, |v| v.position))
+            }
+            MeshData::Creator(ref creator) => {
+                BoundingSphere::from_points(positions(creator.vertices(), |v| Vector3::from(v.0)))
+            }
+        }
+    }
+}
+
 impl From<Vec<PosColor>> for MeshData {
     fn from(data: Vec<PosColor>) -> Self {
         MeshData::PosColor(data)
@@ -110,6 +150,18 @@ impl<'a> PrefabData<'a> for MeshData {
             .load_from_data(self.clone(), (), &system_data.2);
         system_data.1.insert(entity, handle).map(|_| ())
     }
+
+    /// Returns an error for entities with a mesh, as its data can not be read back.
+    fn extract_from_entity(
+        entity: Entity,
+        system_data: &Self::SystemData,
+        _: &[Entity],
+    ) -> StdResult<Option<Self>, PrefabError> {
+        match system_data.1.get(entity) {
+            Some(_) => Err(super::extraction_error(entity, "a mesh")),
+            None => Ok(None),
+        }
+    }
 }
 
 /// Allows loading from Wavefront files
@@ -134,6 +186,10 @@ impl SimpleFormat<Mesh> for ObjFormat {
     }
 }
 
+impl Importer<Mesh> for ObjFormat {
+    const VERSION: u32 = 1;
+}
+
 fn convert(
     object: &Object,
     vi: VertexIndex,
@@ -197,6 +253,7 @@ fn from_data(obj_set: ObjSet) -> Vec<PosNormTex> {
 
 /// Create mesh
 pub fn create_mesh_asset(data: MeshData, renderer: &mut Renderer) -> Result<ProcessingState<Mesh>> {
+    let bounds = data.bounding_sphere();
     let data = match data {
         MeshData::PosColor(ref vertices) => {
             let mb = MeshBuilder::new(vertices);
@@ -221,8 +278,11 @@ pub fn create_mesh_asset(data: MeshData, renderer: &mut Renderer) -> Result<Proc
         MeshData::Creator(creator) => creator.build(renderer),
     };
 
-    data.map(ProcessingState::Loaded)
-        .chain_err(|| "Failed to build mesh")
+    data.map(|mut mesh| {
+        mesh.set_bounds(bounds);
+        ProcessingState::Loaded(mesh)
+    })
+    .chain_err(|| "Failed to build mesh")
 }
 
 /// Build Mesh with vertex buffer combination
@@ -255,6 +315,40 @@ pub trait MeshCreator: Send + Sync + Debug + 'static {
 
     /// Clone a boxed version of this object
     fn box_clone(&self) -> Box<dyn MeshCreator>;
+
+    /// Returns a copy of the vertex buffers of the mesh, used to serialize the creator.
+    ///
+    /// Creators returning `None`, the default, can not be serialized.
+    fn vertex_buffers(&self) -> Option<AnimatedVertexBufferCombination> {
+        None
+    }
+}
+
+mod serde_creator {
+    use serde::{ser::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::skinning::{AnimatedComboMeshCreator, AnimatedVertexBufferCombination};
+
+    use super::MeshCreator;
+
+    #[cfg_attr(feature = "cargo-clippy", allow(borrowed_box))]
+    pub fn serialize<S>(creator: &Box<dyn MeshCreator>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        creator
+            .vertex_buffers()
+            .ok_or_else(|| S::Error::custom("The `MeshCreator` can not be serialized"))?
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Box<dyn MeshCreator>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        AnimatedVertexBufferCombination::deserialize(deserializer)
+            .map(|buffers| Box::new(AnimatedComboMeshCreator::new(buffers)) as Box<dyn MeshCreator>)
+    }
 }
 
 impl Clone for Box<dyn MeshCreator> {
@@ -288,6 +382,11 @@ impl MeshCreator for ComboMeshCreator {
     fn box_clone(&self) -> Box<dyn MeshCreator> {
         Box::new((*self).clone())
     }
+
+    fn vertex_buffers(&self) -> Option<AnimatedVertexBufferCombination> {
+        let (positions, colors, tex_coords, normals, tangents) = self.combo.clone();
+        Some((positions, colors, tex_coords, normals, tangents, None, None))
+    }
 }
 
 impl From<VertexBufferCombination> for ComboMeshCreator {
@@ -295,3 +394,20 @@ impl From<VertexBufferCombination> for ComboMeshCreator {
         Self::new(combo)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mesh_creator_round_trip() {
+        let positions = vec![Separate::<Position>::new([0.0, 1.0, 2.0])];
+        let creator = ComboMeshCreator::new((positions.clone(), None, None, None, None));
+
+        let serialized = ron::ser::to_string(&MeshData::from(creator)).unwrap();
+        match ron::de::from_str(&serialized).unwrap() {
+            MeshData::Creator(creator) => assert_eq!(&positions, creator.vertices()),
+            other => panic!("Expected a `MeshData::Creator`, got {:?}", other),
+        }
+    }
+}
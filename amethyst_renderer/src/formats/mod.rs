@@ -6,7 +6,7 @@ pub use self::{mesh::*, mtl::*, texture::*};
 use serde::{de::DeserializeOwned, Serialize};
 
 use amethyst_assets::{AssetPrefab, Format, PrefabData, PrefabError, ProgressCounter};
-use amethyst_core::specs::prelude::Entity;
+use amethyst_core::specs::{error::BoxedErr, prelude::Entity};
 
 use crate::{shape::InternalShape, Mesh, ShapePrefab, Texture};
 
@@ -14,6 +14,15 @@ mod mesh;
 mod mtl;
 mod texture;
 
+/// The error for `PrefabData` which can not be extracted from `entity`, because it has `data`
+/// which can not be read back.
+pub(crate) fn extraction_error(entity: Entity, data: &str) -> PrefabError {
+    PrefabError::Custom(BoxedErr(Box::from(format!(
+        "Can not extract the prefab data of entity {:?}, it has {}",
+        entity, data
+    ))))
+}
+
 /// Internal mesh loading
 ///
 /// ### Type parameters:
@@ -106,4 +115,27 @@ where
 
         Ok(load_mesh || load_material)
     }
+
+    /// Extracts the mesh and material of the entity, the mesh must have been loaded from a file
+    /// by an `AssetPrefab` or generated by a `ShapePrefab`.
+    fn extract_from_entity(
+        entity: Entity,
+        system_data: &Self::SystemData,
+        entities: &[Entity],
+    ) -> Result<Option<Self>, PrefabError> {
+        let meshes = &system_data.0;
+        let mesh = match AssetPrefab::<Mesh, M>::extract_from_entity(entity, meshes, entities) {
+            Ok(mesh) => mesh.map(MeshPrefab::Asset),
+            Err(_) => ShapePrefab::extract_from_entity(entity, meshes, entities)
+                .map_err(|_| extraction_error(entity, "a mesh of unknown origin"))?
+                .map(MeshPrefab::Shape),
+        };
+        let material = MaterialPrefab::extract_from_entity(entity, &system_data.1, entities)?;
+        match (mesh, material) {
+            (Some(mesh), Some(material)) => Ok(Some(GraphicsPrefab { mesh, material })),
+            (None, None) => Ok(None),
+            (None, Some(_)) => Err(Self::missing_field_error(entity, "mesh")),
+            (Some(_), None) => Err(Self::missing_field_error(entity, "material")),
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use amethyst_assets::{Format, Handle, PrefabData, PrefabError, ProgressCounter};
+use amethyst_assets::{Format, Handle, Loader, PrefabData, PrefabError, ProgressCounter};
 use amethyst_core::specs::prelude::{Entity, ReadExpect, WriteStorage};
 
 use crate::{
@@ -94,6 +94,22 @@ where
         .unwrap_or_else(|| def.clone())
 }
 
+fn extract_handle<F>(
+    entity: Entity,
+    handle: &Handle<Texture>,
+    loader: &Loader,
+    def: &Handle<Texture>,
+) -> Result<Option<TexturePrefab<F>>, PrefabError>
+where
+    F: Format<Texture, Options = TextureMetadata> + Clone,
+{
+    if handle == def {
+        Ok(None)
+    } else {
+        TexturePrefab::from_handle(entity, handle, loader).map(Some)
+    }
+}
+
 impl<'a, F> PrefabData<'a> for MaterialPrefab<F>
 where
     F: Format<Texture, Options = TextureMetadata> + Sync + Clone,
@@ -187,4 +203,45 @@ where
         }
         Ok(ret)
     }
+
+    /// Extracts the `Material` of the entity, with the files its textures were loaded from.
+    ///
+    /// Returns an error if a texture other than a default one was not loaded from a file by a
+    /// `TexturePrefab`.
+    fn extract_from_entity(
+        entity: Entity,
+        system_data: &Self::SystemData,
+        _: &[Entity],
+    ) -> Result<Option<Self>, PrefabError> {
+        let (ref materials, ref mat_default, ref tp_data, ref transparent) = *system_data;
+        let mtl = match materials.get(entity) {
+            Some(mtl) => mtl,
+            None => return Ok(None),
+        };
+        let loader = &*tp_data.0;
+        let def = &mat_default.0;
+        Ok(Some(MaterialPrefab {
+            albedo: extract_handle(entity, &mtl.albedo, loader, &def.albedo)?,
+            albedo_offset: mtl.albedo_offset.clone(),
+            emission: extract_handle(entity, &mtl.emission, loader, &def.emission)?,
+            emission_offset: mtl.emission_offset.clone(),
+            normal: extract_handle(entity, &mtl.normal, loader, &def.normal)?,
+            normal_offset: mtl.normal_offset.clone(),
+            metallic: extract_handle(entity, &mtl.metallic, loader, &def.metallic)?,
+            metallic_offset: mtl.metallic_offset.clone(),
+            roughness: extract_handle(entity, &mtl.roughness, loader, &def.roughness)?,
+            roughness_offset: mtl.roughness_offset.clone(),
+            ambient_occlusion: extract_handle(
+                entity,
+                &mtl.ambient_occlusion,
+                loader,
+                &def.ambient_occlusion,
+            )?,
+            ambient_occlusion_offset: mtl.ambient_occlusion_offset.clone(),
+            caveat: extract_handle(entity, &mtl.caveat, loader, &def.caveat)?,
+            caveat_offset: mtl.caveat_offset.clone(),
+            transparent: transparent.contains(entity),
+            alpha_cutoff: mtl.alpha_cutoff,
+        }))
+    }
 }
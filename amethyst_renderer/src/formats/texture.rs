@@ -6,10 +6,11 @@ use gfx::{
     traits::Pod,
 };
 use image::{DynamicImage, ImageFormat, RgbaImage};
+use serde::{de::Error as DeError, Deserialize, Deserializer, Serialize, Serializer};
 
 use amethyst_assets::{
-    AssetStorage, Format, Handle, Loader, PrefabData, PrefabError, ProcessingState,
-    ProgressCounter, Result, ResultExt, SimpleFormat,
+    AssetStorage, Format, Handle, Importer, LoadRequest, Loader, PrefabData, PrefabError,
+    ProcessingState, ProgressCounter, Result, ResultExt, SimpleFormat,
 };
 use amethyst_core::specs::prelude::{Entity, Read, ReadExpect};
 
@@ -20,7 +21,7 @@ use crate::{
 };
 
 /// Additional texture metadata that can be passed to the asset loader or added to the prefab.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct TextureMetadata {
     /// The sampler info describes how to read from the texture, thus specifies
     /// filter and wrap mode.
@@ -135,7 +136,6 @@ impl TextureMetadata {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum TextureData {
     /// Image data
-    #[serde(skip)]
     Image(ImageData, TextureMetadata),
 
     /// Color
@@ -258,13 +258,14 @@ where
                 &system_data.1,
             )),
 
-            TexturePrefab::File(ref name, ref format, ref options) => Some(system_data.0.load(
-                name.as_ref(),
-                format.clone(),
-                options.clone(),
-                progress,
-                &system_data.1,
-            )),
+            TexturePrefab::File(ref name, ref format, ref options) => {
+                // The same texture is often used by many entities, so they share one asset.
+                let request = LoadRequest::new(name.clone(), format.clone(), options.clone());
+                let handle = system_data.0.load_shared(request, progress, &system_data.1);
+                let origin = (name.clone(), format.clone(), options.clone());
+                system_data.0.set_origin(&handle, origin);
+                Some(handle)
+            }
 
             TexturePrefab::Handle(_) => None,
         };
@@ -277,13 +278,60 @@ where
     }
 }
 
+impl<F> TexturePrefab<F>
+where
+    F: Format<Texture, Options = TextureMetadata> + Clone,
+{
+    /// Finds the file a `TexturePrefab` loaded the texture of `handle` from, to extract prefabs
+    /// from live entities.
+    ///
+    /// Returns an error if the texture has not been loaded from a file by a `TexturePrefab`.
+    pub fn from_handle(
+        entity: Entity,
+        handle: &Handle<Texture>,
+        loader: &Loader,
+    ) -> StdResult<Self, PrefabError> {
+        match loader.origin::<Texture, (String, F, TextureMetadata)>(handle) {
+            Some((name, format, options)) => Ok(TexturePrefab::File(name, format, options)),
+            None => Err(super::extraction_error(
+                entity,
+                "a texture which was not loaded from a file",
+            )),
+        }
+    }
+}
+
 /// ImageData provided by formats, can be interpreted as a texture.
+///
+/// Serialized as its width, height and raw pixels, which allows caching decoded images as
+/// artifacts.
 #[derive(Clone, Debug)]
 pub struct ImageData {
     /// The raw image data.
     pub rgba: RgbaImage,
 }
 
+impl Serialize for ImageData {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (self.rgba.width(), self.rgba.height(), &*self.rgba as &[u8]).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ImageData {
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (width, height, pixels) = <(u32, u32, Vec<u8>)>::deserialize(deserializer)?;
+        RgbaImage::from_raw(width, height, pixels)
+            .map(|rgba| ImageData { rgba })
+            .ok_or_else(|| D::Error::custom("Image data does not match the image size"))
+    }
+}
+
 fn load_into_rgba8_from_memory(
     data: &[u8],
     options: TextureMetadata,
@@ -326,6 +374,10 @@ impl SimpleFormat<Texture> for JpgFormat {
     }
 }
 
+impl Importer<Texture> for JpgFormat {
+    const VERSION: u32 = 1;
+}
+
 /// Allows loading of PNG files.
 #[derive(Clone, Deserialize, Serialize)]
 pub struct PngFormat;
@@ -347,6 +399,10 @@ impl SimpleFormat<Texture> for PngFormat {
     }
 }
 
+impl Importer<Texture> for PngFormat {
+    const VERSION: u32 = 1;
+}
+
 /// Allows loading of BMP files.
 #[derive(Clone, Deserialize, Serialize)]
 pub struct BmpFormat;
@@ -363,6 +419,10 @@ impl SimpleFormat<Texture> for BmpFormat {
     }
 }
 
+impl Importer<Texture> for BmpFormat {
+    const VERSION: u32 = 1;
+}
+
 /// Allows loading of TGA files.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TgaFormat;
@@ -384,6 +444,10 @@ impl SimpleFormat<Texture> for TgaFormat {
     }
 }
 
+impl Importer<Texture> for TgaFormat {
+    const VERSION: u32 = 1;
+}
+
 /// Create a texture asset.
 pub fn create_texture_asset(
     data: TextureData,
@@ -524,6 +588,10 @@ impl SimpleFormat<Texture> for TextureFormat {
     }
 }
 
+impl Importer<Texture> for TextureFormat {
+    const VERSION: u32 = 1;
+}
+
 mod serde_helper {
     use crate::tex::{FilterMethod, WrapMode};
 
@@ -548,7 +616,9 @@ mod serde_helper {
 
 #[cfg(test)]
 mod tests {
-    use super::TextureData;
+    use image::RgbaImage;
+
+    use super::{ImageData, TextureData, TextureMetadata};
 
     #[test]
     fn texture_data_from_f32_3() {
@@ -559,4 +629,21 @@ mod tests {
             _ => panic!("Expected [f32; 3] to turn into TextureData::Rgba"),
         }
     }
+
+    #[test]
+    fn image_data_round_trip() {
+        let pixels = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let rgba = RgbaImage::from_raw(2, 1, pixels.clone()).unwrap();
+        let data = TextureData::Image(ImageData { rgba }, TextureMetadata::srgb());
+
+        let serialized = ron::ser::to_string(&data).unwrap();
+        match ron::de::from_str(&serialized).unwrap() {
+            TextureData::Image(image, _) => {
+                assert_eq!((2, 1), image.rgba.dimensions());
+                assert_eq!(pixels, image.rgba.into_raw());
+            }
+            _ => panic!("Expected the image data to round trip"),
+        }
+        assert!(ron::de::from_str::<ImageData>("(2, 2, [1, 2, 3, 4])").is_err());
+    }
 }
@@ -59,6 +59,7 @@ use gfx_window_glutin;
 use glutin;
 
 pub use crate::{
+    bounds::{BoundingSphere, Frustum},
     bundle::RenderBundle,
     cam::{ActiveCamera, ActiveCameraPrefab, Camera, CameraPrefab, Projection},
     color::Rgba,
@@ -123,6 +124,7 @@ pub mod pipe;
 #[macro_use]
 mod macros;
 
+mod bounds;
 mod bundle;
 mod cam;
 mod color;
@@ -11,6 +11,7 @@ use amethyst_assets::Handle;
 use amethyst_core::nalgebra::{Matrix4, Point3, Rotation3, Translation3, Unit, Vector3};
 
 use crate::{
+    bounds::BoundingSphere,
     error::Result,
     types::{Factory, RawBuffer, Slice},
     vertex::{Attributes, VertexFormat},
@@ -132,6 +133,7 @@ pub struct Mesh {
     slice: Slice,
     transform: Matrix4<f32>,
     vbufs: Vec<VertexBuffer>,
+    bounds: Option<BoundingSphere>,
 }
 
 impl Mesh {
@@ -193,6 +195,19 @@ impl Mesh {
     pub fn transform(&self) -> Matrix4<f32> {
         self.transform
     }
+
+    /// Returns the sphere enclosing the vertices of the mesh, used for frustum culling.
+    ///
+    /// Meshes loaded from `MeshData` have their bounds computed when they are created, meshes
+    /// without bounds are treated as a single point at their origin.
+    pub fn bounds(&self) -> Option<BoundingSphere> {
+        self.bounds
+    }
+
+    /// Sets the sphere enclosing the vertices of the mesh.
+    pub fn set_bounds(&mut self, bounds: Option<BoundingSphere>) {
+        self.bounds = bounds;
+    }
 }
 
 /// Builds new meshes.
@@ -293,6 +308,7 @@ where
             slice,
             transform: self.transform,
             vbufs: self.vertices.build(fac)?.collect(),
+            bounds: None,
         })
     }
 }
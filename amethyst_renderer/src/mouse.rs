@@ -1,6 +1,6 @@
 //! Util functions that change how the mouse moves and looks.
 
-use winit::MouseCursor;
+use winit::{dpi::LogicalPosition, MouseCursor};
 
 use super::WindowMessages;
 
@@ -34,3 +34,29 @@ pub fn set_mouse_cursor(msg: &mut WindowMessages, cursor: MouseCursor) {
         win.set_cursor(cursor);
     });
 }
+
+/// Shows or hides the cursor.
+pub fn set_cursor_visible(msg: &mut WindowMessages, visible: bool) {
+    msg.send_command(move |win| win.hide_cursor(!visible));
+}
+
+/// Grabs the cursor to prevent it from going outside the screen, or releases it.
+pub fn set_cursor_grabbed(msg: &mut WindowMessages, grabbed: bool) {
+    msg.send_command(move |win| {
+        if let Err(err) = win.grab_cursor(grabbed) {
+            error!("Unable to grab or release the cursor. Error: {:?}", err);
+        }
+    });
+}
+
+/// Moves the cursor to the center of the window.
+pub fn center_cursor(msg: &mut WindowMessages) {
+    msg.send_command(move |win| {
+        if let Some(size) = win.get_inner_size() {
+            let center = LogicalPosition::new(size.width / 2.0, size.height / 2.0);
+            if let Err(err) = win.set_cursor_position(center) {
+                error!("Unable to move the cursor. Error: {:?}", err);
+            }
+        }
+    });
+}
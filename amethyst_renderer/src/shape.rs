@@ -71,13 +71,44 @@ where
         system_data: &mut <Self as PrefabData<'_>>::SystemData,
     ) -> Result<bool, PrefabError> {
         let (loader, _, mesh_storage) = system_data;
-        self.handle = Some(loader.load_from_data(
+        let handle = loader.load_from_data(
             self.shape.generate::<V>(self.shape_scale),
             progress,
             &mesh_storage,
-        ));
+        );
+        loader.set_origin(&handle, (self.shape.clone(), self.shape_scale));
+        self.handle = Some(handle);
         Ok(true)
     }
+
+    /// Extracts the shape the mesh of the entity was generated from.
+    ///
+    /// Returns an error if the mesh has not been generated by a `ShapePrefab`.
+    fn extract_from_entity(
+        entity: Entity,
+        system_data: &Self::SystemData,
+        _: &[Entity],
+    ) -> Result<Option<Self>, PrefabError> {
+        let handle = match system_data.1.get(entity) {
+            Some(handle) => handle,
+            None => return Ok(None),
+        };
+        match system_data
+            .0
+            .origin::<Mesh, (Shape, Option<(f32, f32, f32)>)>(handle)
+        {
+            Some((shape, shape_scale)) => Ok(Some(ShapePrefab {
+                handle: None,
+                shape,
+                shape_scale,
+                _m: PhantomData,
+            })),
+            None => Err(crate::formats::extraction_error(
+                entity,
+                "a mesh which was not generated from a shape",
+            )),
+        }
+    }
 }
 
 /// Shape generators
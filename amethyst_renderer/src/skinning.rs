@@ -77,7 +77,7 @@ fn build_mesh_with_combo(
 }
 
 /// Mesh creator for `VertexBufferCombination`.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnimatedComboMeshCreator {
     /// The internal mesh combo data.
     pub combo: AnimatedVertexBufferCombination,
@@ -102,6 +102,10 @@ impl MeshCreator for AnimatedComboMeshCreator {
     fn box_clone(&self) -> Box<dyn MeshCreator> {
         Box::new((*self).clone())
     }
+
+    fn vertex_buffers(&self) -> Option<AnimatedVertexBufferCombination> {
+        Some(self.combo.clone())
+    }
 }
 
 impl From<AnimatedVertexBufferCombination> for AnimatedComboMeshCreator {
@@ -139,4 +143,25 @@ impl<'a> PrefabData<'a> for JointTransformsPrefab {
             )
             .map(|_| ())
     }
+
+    fn extract_from_entity(
+        entity: Entity,
+        storage: &Self::SystemData,
+        entities: &[Entity],
+    ) -> StdResult<Option<Self>, PrefabError> {
+        let transforms = match storage.get(entity) {
+            Some(transforms) => transforms,
+            None => return Ok(None),
+        };
+        match entities.iter().position(|e| *e == transforms.skin) {
+            Some(skin) => Ok(Some(JointTransformsPrefab {
+                skin,
+                size: transforms.matrices.len(),
+            })),
+            None => Err(crate::formats::extraction_error(
+                entity,
+                "joint transforms of a skin outside of the extracted prefab",
+            )),
+        }
+    }
 }
@@ -184,6 +184,7 @@ where
         WindowData::setup(res);
         RenderData::<P>::setup(res);
 
+        register_formats(res);
         let mat = create_default_mat(res);
         res.insert(MaterialDefaults(mat));
         let (width, height) = self
@@ -197,6 +198,20 @@ where
     }
 }
 
+/// Registers the mesh and texture formats for `Loader::load_auto`.
+fn register_formats(res: &mut Resources) {
+    use amethyst_assets::Loader;
+
+    use crate::formats::{BmpFormat, JpgFormat, ObjFormat, PngFormat, TextureMetadata, TgaFormat};
+
+    let mut loader = res.fetch_mut::<Loader>();
+    loader.register_format::<Mesh, _>(ObjFormat, (), &["obj"]);
+    loader.register_format::<Texture, _>(PngFormat, TextureMetadata::srgb(), &["png"]);
+    loader.register_format::<Texture, _>(JpgFormat, TextureMetadata::srgb(), &["jpg", "jpeg"]);
+    loader.register_format::<Texture, _>(BmpFormat, TextureMetadata::srgb(), &["bmp"]);
+    loader.register_format::<Texture, _>(TgaFormat, TextureMetadata::srgb(), &["tga"]);
+}
+
 fn create_default_mat(res: &mut Resources) -> Material {
     use crate::mtl::TextureOffset;
 
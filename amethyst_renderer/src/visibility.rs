@@ -2,15 +2,19 @@ use std::cmp::Ordering;
 
 use hibitset::BitSet;
 
+use amethyst_assets::AssetStorage;
 use amethyst_core::{
-    nalgebra::{self as na, Point3, Vector3},
+    nalgebra::{self as na, Point3},
     specs::prelude::{Entities, Entity, Join, Read, ReadStorage, System, Write},
     GlobalTransform,
 };
 
 use crate::{
+    bounds::{BoundingSphere, Frustum},
     cam::{ActiveCamera, Camera},
     hidden::{Hidden, HiddenPropagate},
+    mesh::{Mesh, MeshHandle},
+    pass::get_camera,
     transparent::Transparent,
 };
 
@@ -27,6 +31,10 @@ pub struct Visibility {
 /// Determine what entities are visible to the camera, and which are not. Will also sort transparent
 /// entities back to front based on distance from camera.
 ///
+/// Entities are culled against the view frustum of the camera using their `BoundingSphere`, or
+/// the bounds of their mesh if they have none. Entities without either are treated as a single
+/// point at their origin.
+///
 /// Note that this should run after `GlobalTransform` has been updated for the current frame, and
 /// before rendering occurs.
 pub struct VisibilitySortingSystem {
@@ -38,9 +46,7 @@ pub struct VisibilitySortingSystem {
 struct Internals {
     entity: Entity,
     transparent: bool,
-    centroid: Point3<f32>,
     camera_distance: f32,
-    from_camera: Vector3<f32>,
 }
 
 impl VisibilitySortingSystem {
@@ -63,37 +69,65 @@ impl<'a> System<'a> for VisibilitySortingSystem {
         ReadStorage<'a, Camera>,
         ReadStorage<'a, Transparent>,
         ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, BoundingSphere>,
+        ReadStorage<'a, MeshHandle>,
+        Read<'a, AssetStorage<Mesh>>,
     );
 
     fn run(
         &mut self,
-        (entities, mut visibility, hidden, hidden_prop, active, camera, transparent, global): Self::SystemData,
+        (
+            entities,
+            mut visibility,
+            hidden,
+            hidden_prop,
+            active,
+            camera,
+            transparent,
+            global,
+            bounds,
+            meshes,
+            mesh_storage,
+        ): Self::SystemData,
     ) {
         let origin = Point3::origin();
 
-        let camera: Option<&GlobalTransform> = active
-            .and_then(|a| global.get(a.entity))
-            .or_else(|| (&camera, &global).join().map(|cg| cg.1).next());
-        let camera_backward = camera
-            .map(|c| c.0.column(2).xyz())
-            .unwrap_or_else(Vector3::z);
+        let camera = get_camera(active, &camera, &global);
         let camera_centroid = camera
-            .map(|g| g.0.transform_point(&origin))
+            .map(|(_, g)| g.0.transform_point(&origin))
             .unwrap_or(origin);
+        let frustum = camera.and_then(|(camera, g)| {
+            g.0.try_inverse()
+                .map(|view| Frustum::new(&(camera.proj * view)))
+        });
 
         self.centroids.clear();
         self.centroids.extend(
             (&*entities, &global, !&hidden, !&hidden_prop)
                 .join()
-                .map(|(entity, global, _, _)| (entity, global.0.transform_point(&origin)))
-                .map(|(entity, centroid)| Internals {
+                .map(|(entity, global, _, _)| {
+                    let local = bounds
+                        .get(entity)
+                        .cloned()
+                        .or_else(|| {
+                            meshes
+                                .get(entity)
+                                .and_then(|handle| mesh_storage.get(handle))
+                                .and_then(Mesh::bounds)
+                        })
+                        .unwrap_or_else(|| BoundingSphere::new(origin, 0.0));
+                    (entity, local.transform(&global.0))
+                })
+                .filter(|&(_, ref sphere)| {
+                    frustum
+                        .as_ref()
+                        .map_or(true, |frustum| frustum.intersects_sphere(sphere))
+                })
+                .map(|(entity, sphere)| Internals {
                     entity,
                     transparent: transparent.contains(entity),
-                    centroid,
-                    camera_distance: na::distance_squared(&centroid, &camera_centroid),
-                    from_camera: centroid - camera_centroid,
-                })
-                .filter(|c| c.from_camera.dot(&camera_backward) < 0.), // filter entities behind the camera
+                    camera_distance: na::distance_squared(&sphere.center, &camera_centroid),
+                }),
         );
         self.transparent.clear();
         self.transparent
@@ -0,0 +1,78 @@
+//! Clipboard backends used by text editing.
+
+use std::error::Error;
+
+use ::clipboard::{ClipboardContext, ClipboardProvider};
+
+/// A clipboard text can be cut or copied to and pasted from.
+pub trait Clipboard: Send + Sync + 'static {
+    /// Returns the text on the clipboard.
+    fn get_contents(&mut self) -> Result<String, Box<dyn Error>>;
+
+    /// Replaces the text on the clipboard.
+    fn set_contents(&mut self, contents: String) -> Result<(), Box<dyn Error>>;
+}
+
+/// The clipboard of the operating system.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClipboard;
+
+impl Clipboard for SystemClipboard {
+    fn get_contents(&mut self) -> Result<String, Box<dyn Error>> {
+        ClipboardProvider::new().and_then(|mut ctx: ClipboardContext| ctx.get_contents())
+    }
+
+    fn set_contents(&mut self, contents: String) -> Result<(), Box<dyn Error>> {
+        ClipboardProvider::new().and_then(|mut ctx: ClipboardContext| ctx.set_contents(contents))
+    }
+}
+
+/// A clipboard keeping its contents in memory, useful for headless tests and for isolating the
+/// game from the system clipboard.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryClipboard {
+    /// The text on the clipboard.
+    pub contents: String,
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get_contents(&mut self) -> Result<String, Box<dyn Error>> {
+        Ok(self.contents.clone())
+    }
+
+    fn set_contents(&mut self, contents: String) -> Result<(), Box<dyn Error>> {
+        self.contents = contents;
+        Ok(())
+    }
+}
+
+/// The clipboard used by the `UiKeyboardSystem`, the system clipboard by default.
+///
+/// ### Example:
+///
+/// ```rust,ignore
+/// world.add_resource(UiClipboard::new(MemoryClipboard::default()));
+/// ```
+pub struct UiClipboard {
+    backend: Box<dyn Clipboard>,
+}
+
+impl UiClipboard {
+    /// Creates a clipboard resource using the given backend.
+    pub fn new<C: Clipboard>(backend: C) -> Self {
+        UiClipboard {
+            backend: Box::new(backend),
+        }
+    }
+
+    /// Returns the backend of the clipboard.
+    pub fn backend(&mut self) -> &mut dyn Clipboard {
+        &mut *self.backend
+    }
+}
+
+impl Default for UiClipboard {
+    fn default() -> Self {
+        UiClipboard::new(SystemClipboard)
+    }
+}
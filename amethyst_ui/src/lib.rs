@@ -8,7 +8,6 @@ use amethyst_audio;
 use amethyst_core;
 
 use amethyst_renderer;
-use clipboard;
 #[macro_use]
 extern crate derivative;
 use fnv;
@@ -32,6 +31,7 @@ use winit;
 mod action_components;
 mod bundle;
 mod button;
+mod clipboard;
 mod event;
 mod focused;
 mod font;
@@ -48,6 +48,7 @@ pub use self::{
     action_components::{OnUiActionImage, OnUiActionSound},
     bundle::UiBundle,
     button::{UiButton, UiButtonBuilder, UiButtonBuilderResources, UiButtonSystem},
+    clipboard::{Clipboard, MemoryClipboard, SystemClipboard, UiClipboard},
     event::{MouseReactive, UiEvent, UiEventType, UiMouseSystem},
     focused::UiFocused,
     font::{
@@ -63,7 +64,7 @@ pub use self::{
         UiPrefab, UiTextBuilder, UiTransformBuilder, UiWidget,
     },
     resize::{ResizeSystem, UiResize},
-    text::{LineMode, TextEditing, UiKeyboardSystem, UiText},
+    text::{ImeEvent, LineMode, Preedit, TextEditing, UiKeyboardSystem, UiText},
     transform::{UiFinder, UiTransform},
 };
 
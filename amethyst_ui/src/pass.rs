@@ -32,7 +32,7 @@ use amethyst_renderer::{
     Texture, TextureData, TextureHandle, TextureMetadata, VertexFormat,
 };
 
-use super::*;
+use super::{text::grapheme_byte_index, *};
 
 const VERT_SRC: &[u8] = include_bytes!("shaders/vertex.glsl");
 const FRAG_SRC: &[u8] = include_bytes!("shaders/frag.glsl");
@@ -292,21 +292,48 @@ impl Pass for DrawUi {
 
                 // Build text sections.
                 let editing = editing.get(entity);
+                // Insert the text being composed by an input method at the cursor.
+                let preedit = editing.and_then(|ed| {
+                    ed.preedit
+                        .as_ref()
+                        .filter(|preedit| !preedit.text.is_empty())
+                        .map(|preedit| (ed, preedit))
+                });
+                let composed_string = preedit.map(|(ed, preedit)| {
+                    let mut composed = ui_text.text.clone();
+                    let index = grapheme_byte_index(&composed, ed.cursor_position as usize);
+                    composed.insert_str(index, &preedit.text);
+                    composed
+                });
+                // The glyphs of the preedit, and the glyph the cursor is rendered at.
+                let preedit_glyphs = preedit.map(|(ed, preedit)| {
+                    let start = ed.cursor_position as usize;
+                    start..start + preedit.text.graphemes(true).count()
+                });
+                let cursor_glyph = preedit
+                    .map(|(ed, preedit)| {
+                        let len = preedit.text.graphemes(true).count();
+                        ed.cursor_position as usize + preedit.cursor.unwrap_or(len).min(len)
+                    })
+                    .or_else(|| editing.map(|ed| ed.cursor_position as usize));
+                // The selection is hidden while composing.
+                let selecting = editing.filter(|_| preedit.is_none());
+                let source_string = composed_string.as_ref().unwrap_or(&ui_text.text);
                 let password_string = if ui_text.password {
                     // Build a string composed of black dot characters.
-                    let mut ret = String::with_capacity(ui_text.text.len());
-                    for _grapheme in ui_text.text.graphemes(true) {
+                    let mut ret = String::with_capacity(source_string.len());
+                    for _grapheme in source_string.graphemes(true) {
                         ret.push('\u{2022}');
                     }
                     Some(ret)
                 } else {
                     None
                 };
-                let rendered_string = password_string.as_ref().unwrap_or(&ui_text.text);
+                let rendered_string = password_string.as_ref().unwrap_or(source_string);
                 let hidpi = screen_dimensions.hidpi_factor() as f32;
                 let size = ui_text.font_size;
                 let scale = Scale::uniform(size);
-                let text = editing
+                let text = selecting
                     .and_then(|editing| {
                         if editing.highlight_vector == 0 {
                             return None;
@@ -407,7 +434,7 @@ impl Pass for DrawUi {
                 let cache = &mut self.cached_color_textures;
 
                 // Render text selection
-                if let Some((texture, (start, end))) = editing.and_then(|ed| {
+                if let Some((texture, (start, end))) = selecting.and_then(|ed| {
                     let start = ed
                         .cursor_position
                         .min(ed.cursor_position + ed.highlight_vector)
@@ -481,6 +508,51 @@ impl Pass for DrawUi {
                 ) {
                     error!("Unable to draw text! Error: {:?}", err);
                 }
+                // Underline the text being composed
+                if let Some((texture, glyphs)) = preedit_glyphs.and_then(|glyphs| {
+                    tex_storage
+                        .get(&cached_color_texture(
+                            cache,
+                            ui_text.color,
+                            &loader,
+                            &tex_storage,
+                        ))
+                        .map(|tex| (tex, glyphs))
+                }) {
+                    effect.data.textures.push(texture.view().clone());
+                    effect.data.samplers.push(texture.sampler().clone());
+                    let v_metrics = brush
+                        .fonts()
+                        .get(0)
+                        .expect("Unable to get first font of brush")
+                        .v_metrics(Scale::uniform(ui_text.font_size));
+                    for glyph in brush
+                        .glyphs(&section)
+                        .enumerate()
+                        .filter(|&(i, _g)| glyphs.start <= i && i < glyphs.end)
+                        .map(|(_i, g)| g)
+                    {
+                        let width = glyph.unpositioned().h_metrics().advance_width / hidpi;
+                        let height = (ui_text.font_size / 15.0).max(1.0);
+                        let mut pos = glyph.position();
+                        pos.x /= hidpi;
+                        pos.y /= hidpi;
+                        let vertex_args = VertexArgs {
+                            invert_window_size: invert_window_size.into(),
+                            // gfx-glyph uses y down so we need to convert to y up
+                            coord: [
+                                pos.x + width / 2.0,
+                                screen_dimensions.height() - pos.y + v_metrics.descent / 2.0,
+                            ]
+                            .into(),
+                            dimension: [width, height].into(),
+                        };
+                        effect.update_constant_buffer("VertexArgs", &vertex_args.std140(), encoder);
+                        effect.draw(mesh.slice(), encoder);
+                    }
+                    effect.data.textures.clear();
+                    effect.data.samplers.clear();
+                }
                 // Render cursor
                 if focused.entity == Some(entity) {
                     if let Some((texture, editing)) = editing.as_ref().and_then(|ed| {
@@ -518,13 +590,11 @@ impl Pass for DrawUi {
                                 .v_metrics(Scale::uniform(ui_text.font_size))
                                 .ascent;
                             let glyph_len = brush.glyphs(&section).count();
-                            let (glyph, at_end) = if editing.cursor_position as usize >= glyph_len {
+                            let cursor_glyph = cursor_glyph.unwrap_or(0);
+                            let (glyph, at_end) = if cursor_glyph >= glyph_len {
                                 (brush.glyphs(&section).last(), true)
                             } else {
-                                (
-                                    brush.glyphs(&section).nth(editing.cursor_position as usize),
-                                    false,
-                                )
+                                (brush.glyphs(&section).nth(cursor_glyph), false)
                             };
                             let (height, width) = if editing.use_block_cursor {
                                 let height = if blink_on {
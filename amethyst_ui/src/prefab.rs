@@ -179,6 +179,35 @@ impl<'a> PrefabData<'a> for UiTransformBuilder {
 
         Ok(())
     }
+
+    fn extract_from_entity(
+        entity: Entity,
+        system_data: &Self::SystemData,
+        _: &[Entity],
+    ) -> Result<Option<Self>, PrefabError> {
+        let transform = match system_data.0.get(entity) {
+            Some(transform) => transform,
+            None => return Ok(None),
+        };
+        Ok(Some(UiTransformBuilder {
+            id: transform.id.clone(),
+            x: transform.local_x,
+            y: transform.local_y,
+            z: transform.local_z,
+            width: transform.width,
+            height: transform.height,
+            tab_order: transform.tab_order,
+            opaque: transform.opaque,
+            percent: transform.scale_mode == ScaleMode::Percent,
+            stretch: match transform.stretch {
+                Stretch::NoStretch => None,
+                ref stretch => Some(stretch.clone()),
+            },
+            anchor: transform.anchor.clone(),
+            mouse_reactive: system_data.1.contains(entity),
+            hidden: system_data.2.contains(entity),
+        }))
+    }
 }
 
 /// Loadable `UiText` data
@@ -302,12 +331,53 @@ where
         self.font
             .get_or_insert_with(|| {
                 let (ref loader, _, ref storage) = fonts;
-                AssetPrefab::Handle(get_default_font(loader, storage))
+                let handle = get_default_font(loader, storage);
+                loader.set_origin(&handle, DefaultFont);
+                AssetPrefab::Handle(handle)
             })
             .load_sub_assets(progress, fonts)
     }
+
+    /// Extracts the `UiText` of the entity, its font must be the default font or have been
+    /// loaded from a file by an `AssetPrefab`.
+    fn extract_from_entity(
+        entity: Entity,
+        system_data: &Self::SystemData,
+        _: &[Entity],
+    ) -> Result<Option<Self>, PrefabError> {
+        let (ref texts, ref editables, ref fonts, ref focused) = *system_data;
+        let text = match texts.get(entity) {
+            Some(text) => text,
+            None => return Ok(None),
+        };
+        let font = match fonts.0.origin::<FontAsset, DefaultFont>(&text.font) {
+            Some(DefaultFont) => None,
+            None => Some(AssetPrefab::from_handle(entity, &text.font, &fonts.0)?),
+        };
+        let editable = editables.get(entity).map(|editing| TextEditingPrefab {
+            max_length: editing.max_length,
+            selected_text_color: editing.selected_text_color,
+            selected_background_color: editing.selected_background_color,
+            use_block_cursor: editing.use_block_cursor,
+            focused: focused.entity == Some(entity),
+        });
+        Ok(Some(UiTextBuilder {
+            text: text.text.clone(),
+            font_size: text.font_size,
+            color: text.color,
+            font,
+            password: text.password,
+            align: Some(text.align.clone()),
+            line_mode: Some(text.line_mode.clone()),
+            editable,
+        }))
+    }
 }
 
+/// Origin of the default font loaded by `UiTextBuilder`, which is extracted as no font.
+#[derive(Clone)]
+struct DefaultFont;
+
 /// Loadable `UiImage` data
 ///
 /// ### Type parameters:
@@ -357,6 +427,22 @@ where
         let (_, ref mut textures) = system_data;
         self.image.load_sub_assets(progress, textures)
     }
+
+    /// Extracts the `UiImage` of the entity, its texture must have been loaded from a file by a
+    /// `TexturePrefab`.
+    fn extract_from_entity(
+        entity: Entity,
+        system_data: &Self::SystemData,
+        _: &[Entity],
+    ) -> Result<Option<Self>, PrefabError> {
+        let (ref images, ref textures) = *system_data;
+        match images.get(entity) {
+            Some(image) => Ok(Some(UiImageBuilder {
+                image: TexturePrefab::from_handle(entity, &image.texture, &textures.0)?,
+            })),
+            None => Ok(None),
+        }
+    }
 }
 
 /// Loadable `UiButton` data
@@ -476,6 +562,22 @@ where
         self.hover_sound.load_sub_assets(progress, sounds)?;
         self.release_sound.load_sub_assets(progress, sounds)
     }
+
+    /// Returns an error for entities with a `UiButton`, as its text and font are only part of
+    /// the `UiText` created for the button widget.
+    fn extract_from_entity(
+        entity: Entity,
+        system_data: &Self::SystemData,
+        _: &[Entity],
+    ) -> Result<Option<Self>, PrefabError> {
+        match system_data.0.get(entity) {
+            Some(_) => Err(PrefabError::Custom(BoxedErr(Box::from(format!(
+                "Can not extract the `UiButton` of entity {:?}",
+                entity
+            ))))),
+            None => Ok(None),
+        }
+    }
 }
 
 /// Loadable ui components
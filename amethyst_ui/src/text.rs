@@ -1,6 +1,5 @@
 use std::{cmp::Ordering, ops::Range};
 
-use clipboard::{ClipboardContext, ClipboardProvider};
 use gfx_glyph::{Point, PositionedGlyph};
 use hibitset::BitSet;
 use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
@@ -92,6 +91,9 @@ impl Component for UiText {
 
 /// If this component is attached to an entity with a UiText then that UiText is editable.
 /// This component also controls how that editing works.
+///
+/// Edits can be undone with Ctrl+Z and redone with Ctrl+Shift+Z or Ctrl+Y, consecutive typing
+/// or deleting is undone in a single step.
 pub struct TextEditing {
     /// The current editing cursor position, specified in terms of glyphs, not characters.
     pub cursor_position: isize,
@@ -113,6 +115,18 @@ pub struct TextEditing {
     /// is greater than or equal to 1.0 / CURSOR_BLINK_RATE it should be reset to 0.  When the
     /// player types it should be reset to 0.
     pub(crate) cursor_blink_timer: f32,
+
+    /// Text being composed by an input method editor, rendered inline at the cursor until it is
+    /// committed.
+    pub preedit: Option<Preedit>,
+    /// The maximum number of edits which can be undone.
+    pub max_undo_steps: usize,
+    /// States of the text before recent edits, the most recent last.
+    undo_stack: Vec<EditSnapshot>,
+    /// States of the text before edits were undone, the most recent last.
+    redo_stack: Vec<EditSnapshot>,
+    /// The kind of the last edit, used to undo consecutive typing in a single step.
+    last_edit: Option<EditKind>,
 }
 
 impl TextEditing {
@@ -131,8 +145,127 @@ impl TextEditing {
             selected_background_color,
             use_block_cursor,
             cursor_blink_timer: 0.0,
+            preedit: None,
+            max_undo_steps: 100,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            last_edit: None,
+        }
+    }
+
+    /// Reverts the last edit of the text, returning false if there was nothing to undo.
+    pub fn undo(&mut self, text: &mut String) -> bool {
+        match self.undo_stack.pop() {
+            Some(snapshot) => {
+                let current = self.snapshot(text);
+                self.redo_stack.push(current);
+                self.restore(snapshot, text);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reapplies the last undone edit of the text, returning false if there was nothing to redo.
+    pub fn redo(&mut self, text: &mut String) -> bool {
+        match self.redo_stack.pop() {
+            Some(snapshot) => {
+                let current = self.snapshot(text);
+                self.undo_stack.push(current);
+                self.restore(snapshot, text);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns true if there is an edit to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    /// Returns true if there is an undone edit to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Forgets all edits, e.g. after the text was replaced by the game.
+    pub fn clear_history(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.last_edit = None;
+    }
+
+    fn snapshot(&self, text: &str) -> EditSnapshot {
+        EditSnapshot {
+            text: text.to_owned(),
+            cursor_position: self.cursor_position,
+            highlight_vector: self.highlight_vector,
         }
     }
+
+    fn restore(&mut self, snapshot: EditSnapshot, text: &mut String) {
+        *text = snapshot.text;
+        self.cursor_position = snapshot.cursor_position;
+        self.highlight_vector = snapshot.highlight_vector;
+        self.preedit = None;
+        self.last_edit = None;
+        self.cursor_blink_timer = 0.0;
+    }
+
+    /// Adds the state of the text before an edit to the undo stack.
+    fn record(&mut self, before: EditSnapshot, kind: EditKind) {
+        self.redo_stack.clear();
+        if kind == EditKind::Other || self.last_edit != Some(kind) {
+            self.undo_stack.push(before);
+            if self.undo_stack.len() > self.max_undo_steps {
+                self.undo_stack.remove(0);
+            }
+        }
+        self.last_edit = Some(kind);
+    }
+}
+
+/// Text being composed by an input method editor.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Preedit {
+    /// The text being composed.
+    pub text: String,
+    /// The position of the cursor within the composed text in graphemes, `None` places it at the
+    /// end.
+    pub cursor: Option<usize>,
+}
+
+/// Events of an input method editor composing text, e.g. for CJK input.
+///
+/// winit does not report text composition, so these are sent by platform specific code through
+/// an `EventChannel<ImeEvent>`. The `UiKeyboardSystem` applies them to the focused `TextEditing`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImeEvent {
+    /// The text being composed changed, an empty text ends the composition.
+    Preedit {
+        /// The text being composed.
+        text: String,
+        /// The position of the cursor within the composed text in graphemes.
+        cursor: Option<usize>,
+    },
+    /// The composed text was committed, replacing the preedit.
+    Commit(String),
+}
+
+/// The state of an edited text, as restored by undo and redo.
+struct EditSnapshot {
+    text: String,
+    cursor_position: isize,
+    highlight_vector: isize,
+}
+
+/// Kinds of edits, consecutive typing and deleting are undone in a single step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EditKind {
+    Typing,
+    Deleting,
+    Other,
 }
 
 impl Component for TextEditing {
@@ -148,6 +281,8 @@ struct CachedTabOrder {
 pub struct UiKeyboardSystem {
     /// A reader for winit events.
     reader: Option<ReaderId<Event>>,
+    /// A reader for input method events.
+    ime_reader: Option<ReaderId<ImeEvent>>,
     /// A cache sorted by tab order, and then by Entity.
     tab_order_cache: CachedTabOrder,
     /// This is set to true while the left mouse button is pressed.
@@ -161,6 +296,7 @@ impl UiKeyboardSystem {
     pub fn new() -> Self {
         Self {
             reader: None,
+            ime_reader: None,
             tab_order_cache: CachedTabOrder {
                 cached: BitSet::new(),
                 cache: Vec::new(),
@@ -179,13 +315,26 @@ impl<'a> System<'a> for UiKeyboardSystem {
         ReadStorage<'a, UiTransform>,
         Write<'a, UiFocused>,
         Read<'a, EventChannel<Event>>,
+        Read<'a, EventChannel<ImeEvent>>,
         Read<'a, Time>,
         ReadExpect<'a, ScreenDimensions>,
+        Write<'a, UiClipboard>,
     );
 
     fn run(
         &mut self,
-        (entities, mut text, mut editable, transform, mut focused, events, time, screen_dimensions): Self::SystemData,
+        (
+            entities,
+            mut text,
+            mut editable,
+            transform,
+            mut focused,
+            events,
+            ime_events,
+            time,
+            screen_dimensions,
+            mut clipboard,
+        ): Self::SystemData,
     ) {
         // Populate and update the tab order cache.
         {
@@ -380,6 +529,7 @@ impl<'a> System<'a> for UiKeyboardSystem {
                             {
                                 let (mouse_x, mouse_y) = self.mouse_position;
                                 focused_edit.highlight_vector = 0;
+                                focused_edit.last_edit = None;
                                 focused_edit.cursor_position = closest_glyph_index_to_mouse(
                                     mouse_x,
                                     mouse_y,
@@ -431,22 +581,14 @@ impl<'a> System<'a> for UiKeyboardSystem {
                         } else if input >= '\u{100000}' && input <= '\u{10FFFF}' {
                             continue;
                         }
-                        focused_edit.cursor_blink_timer = 0.0;
-                        delete_highlighted(focused_edit, focused_text);
-                        let start_byte = focused_text
-                            .text
-                            .grapheme_indices(true)
-                            .nth(focused_edit.cursor_position as usize)
-                            .map(|i| i.0)
-                            .unwrap_or_else(|| {
-                                // We are either in a 0 length string, or at the end of a string
-                                // This line returns the correct byte index for both.
-                                focused_text.text.len()
-                            });
-                        if focused_text.text.graphemes(true).count() < focused_edit.max_length {
-                            focused_text.text.insert(start_byte, input);
-                            focused_edit.cursor_position += 1;
-                        }
+                        let mut buffer = [0; 4];
+                        let input = input.encode_utf8(&mut buffer);
+                        edit_text(
+                            focused_edit,
+                            &mut focused_text.text,
+                            EditKind::Typing,
+                            |edit, text| insert_text(edit, text, input),
+                        );
                     }
                     Event::WindowEvent {
                         event:
@@ -461,192 +603,279 @@ impl<'a> System<'a> for UiKeyboardSystem {
                                 ..
                             },
                         ..
-                    } => match v_keycode {
-                        VirtualKeyCode::Home | VirtualKeyCode::Up => {
-                            focused_edit.highlight_vector = if modifiers.shift {
-                                focused_edit.cursor_position
-                            } else {
-                                0
-                            };
-                            focused_edit.cursor_position = 0;
-                            focused_edit.cursor_blink_timer = 0.0;
-                        }
-                        VirtualKeyCode::End | VirtualKeyCode::Down => {
-                            let glyph_len = focused_text.text.graphemes(true).count() as isize;
-                            focused_edit.highlight_vector = if modifiers.shift {
-                                focused_edit.cursor_position - glyph_len
-                            } else {
-                                0
-                            };
-                            focused_edit.cursor_position = glyph_len;
-                            focused_edit.cursor_blink_timer = 0.0;
-                        }
-                        VirtualKeyCode::Back => {
-                            if !delete_highlighted(focused_edit, focused_text) {
-                                if focused_edit.cursor_position > 0 {
-                                    if let Some((byte, len)) = focused_text
-                                        .text
-                                        .grapheme_indices(true)
-                                        .nth(focused_edit.cursor_position as usize - 1)
-                                        .map(|i| (i.0, i.1.len()))
-                                    {
-                                        focused_text.text.drain(byte..(byte + len));
-                                        focused_edit.cursor_position -= 1;
-                                    }
-                                }
-                            }
-                        }
-                        VirtualKeyCode::Delete => {
-                            if !delete_highlighted(focused_edit, focused_text) {
-                                if let Some((start_byte, start_glyph_len)) = focused_text
-                                    .text
-                                    .grapheme_indices(true)
-                                    .nth(focused_edit.cursor_position as usize)
-                                    .map(|i| (i.0, i.1.len()))
-                                {
-                                    focused_edit.cursor_blink_timer = 0.0;
-                                    focused_text
-                                        .text
-                                        .drain(start_byte..(start_byte + start_glyph_len));
-                                }
-                            }
-                        }
-                        VirtualKeyCode::Left => {
-                            if focused_edit.highlight_vector == 0 || modifiers.shift {
-                                if focused_edit.cursor_position > 0 {
-                                    let delta = if ctrl_or_cmd(&modifiers) {
-                                        let mut graphemes = 0;
-                                        for word in focused_text.text.split_word_bounds() {
-                                            let word_graphemes =
-                                                word.graphemes(true).count() as isize;
-                                            if graphemes + word_graphemes
-                                                >= focused_edit.cursor_position
-                                            {
-                                                break;
-                                            }
-                                            graphemes += word_graphemes;
-                                        }
-                                        focused_edit.cursor_position - graphemes
-                                    } else {
-                                        1
-                                    };
-                                    focused_edit.cursor_position -= delta;
-                                    if modifiers.shift {
-                                        focused_edit.highlight_vector += delta;
-                                    }
-                                    focused_edit.cursor_blink_timer = 0.0;
-                                }
-                            } else {
-                                focused_edit.cursor_position = focused_edit.cursor_position.min(
-                                    focused_edit.cursor_position + focused_edit.highlight_vector,
-                                );
-                                focused_edit.highlight_vector = 0;
-                            }
-                        }
-                        VirtualKeyCode::Right => {
-                            if focused_edit.highlight_vector == 0 || modifiers.shift {
-                                let glyph_len = focused_text.text.graphemes(true).count();
-                                if (focused_edit.cursor_position as usize) < glyph_len {
-                                    let delta = if ctrl_or_cmd(&modifiers) {
-                                        let mut graphemes = 0;
-                                        for word in focused_text.text.split_word_bounds() {
-                                            graphemes += word.graphemes(true).count() as isize;
-                                            if graphemes > focused_edit.cursor_position {
-                                                break;
-                                            }
-                                        }
-                                        graphemes - focused_edit.cursor_position
-                                    } else {
-                                        1
-                                    };
-                                    focused_edit.cursor_position += delta;
-                                    if modifiers.shift {
-                                        focused_edit.highlight_vector -= delta;
-                                    }
-                                    focused_edit.cursor_blink_timer = 0.0;
-                                }
-                            } else {
-                                focused_edit.cursor_position = focused_edit.cursor_position.max(
-                                    focused_edit.cursor_position + focused_edit.highlight_vector,
-                                );
-                                focused_edit.highlight_vector = 0;
-                            }
-                        }
-                        VirtualKeyCode::A => {
-                            if ctrl_or_cmd(&modifiers) {
-                                let glyph_len = focused_text.text.graphemes(true).count() as isize;
-                                focused_edit.cursor_position = glyph_len;
-                                focused_edit.highlight_vector = -glyph_len;
-                            }
-                        }
-                        VirtualKeyCode::X => {
-                            if ctrl_or_cmd(&modifiers) {
-                                let new_clip = extract_highlighted(focused_edit, focused_text);
-                                if !new_clip.is_empty() {
-                                    if let Err(e) = ClipboardProvider::new().and_then(
-                                        |mut ctx: ClipboardContext| ctx.set_contents(new_clip),
-                                    ) {
-                                        error!("Error occured when cutting to clipboard: {:?}", e);
-                                    }
-                                }
-                            }
-                        }
-                        VirtualKeyCode::C => {
-                            if ctrl_or_cmd(&modifiers) {
-                                let new_clip = read_highlighted(focused_edit, focused_text);
-                                if !new_clip.is_empty() {
-                                    if let Err(e) = ClipboardProvider::new().and_then(
-                                        |mut ctx: ClipboardContext| {
-                                            ctx.set_contents(new_clip.to_owned())
-                                        },
-                                    ) {
-                                        error!("Error occured when copying to clipboard: {:?}", e);
-                                    }
-                                }
-                            }
-                        }
-                        VirtualKeyCode::V => {
-                            if ctrl_or_cmd(&modifiers) {
-                                delete_highlighted(focused_edit, focused_text);
-
-                                match ClipboardProvider::new()
-                                    .and_then(|mut ctx: ClipboardContext| ctx.get_contents())
-                                {
-                                    Ok(contents) => {
-                                        let index = cursor_byte_index(focused_edit, focused_text);
-                                        let empty_space = focused_edit.max_length
-                                            - focused_text.text.graphemes(true).count();
-                                        let contents = contents
-                                            .graphemes(true)
-                                            .take(empty_space)
-                                            .fold(String::new(), |mut init, new| {
-                                                init.push_str(new);
-                                                init
-                                            });
-                                        focused_text.text.insert_str(index, &contents);
-                                        focused_edit.cursor_position +=
-                                            contents.graphemes(true).count() as isize;
-                                    }
-                                    Err(e) => error!(
-                                        "Error occured when pasting contents of clipboard: {:?}",
-                                        e
-                                    ),
-                                }
-                            }
-                        }
-                        _ => {}
-                    },
+                    } => handle_key(
+                        focused_edit,
+                        &mut focused_text.text,
+                        v_keycode,
+                        modifiers,
+                        clipboard.backend(),
+                    ),
                     _ => {}
                 }
             }
         }
+
+        let ime_events = ime_events.read(
+            self.ime_reader
+                .as_mut()
+                .expect("`UiKeyboardSystem::setup` was not called before `UiKeyboardSystem::run`"),
+        );
+        let mut focused_text_edit = focused
+            .entity
+            .and_then(|entity| zip_options(text.get_mut(entity), editable.get_mut(entity)));
+        if let Some((ref mut focused_text, ref mut focused_edit)) = focused_text_edit {
+            for event in ime_events {
+                handle_ime(focused_edit, &mut focused_text.text, event);
+            }
+        }
     }
 
     fn setup(&mut self, res: &mut Resources) {
         use amethyst_core::specs::prelude::SystemData;
         Self::SystemData::setup(res);
         self.reader = Some(res.fetch_mut::<EventChannel<Event>>().register_reader());
+        self.ime_reader = Some(res.fetch_mut::<EventChannel<ImeEvent>>().register_reader());
+    }
+}
+
+/// Applies an edit to the text, recording it for undo if the text changed.
+fn edit_text<F>(edit: &mut TextEditing, text: &mut String, kind: EditKind, f: F)
+where
+    F: FnOnce(&mut TextEditing, &mut String),
+{
+    let before = edit.snapshot(text);
+    f(edit, text);
+    if *text != before.text {
+        edit.record(before, kind);
+        edit.cursor_blink_timer = 0.0;
+    }
+}
+
+/// Replaces the highlighted text with the input, as far as `max_length` permits.
+fn insert_text(edit: &mut TextEditing, text: &mut String, input: &str) {
+    delete_highlighted(edit, text);
+    let index = cursor_byte_index(edit, text);
+    let empty_space = edit.max_length.saturating_sub(text.graphemes(true).count());
+    let input = input
+        .graphemes(true)
+        .take(empty_space)
+        .fold(String::new(), |mut init, new| {
+            init.push_str(new);
+            init
+        });
+    text.insert_str(index, &input);
+    edit.cursor_position += input.graphemes(true).count() as isize;
+}
+
+/// Processes a key pressed while the text is focused.
+fn handle_key(
+    edit: &mut TextEditing,
+    text: &mut String,
+    key: VirtualKeyCode,
+    modifiers: ModifiersState,
+    clipboard: &mut dyn Clipboard,
+) {
+    match key {
+        VirtualKeyCode::Home | VirtualKeyCode::Up => {
+            edit.highlight_vector = if modifiers.shift {
+                edit.cursor_position
+            } else {
+                0
+            };
+            edit.cursor_position = 0;
+            edit.cursor_blink_timer = 0.0;
+            edit.last_edit = None;
+        }
+        VirtualKeyCode::End | VirtualKeyCode::Down => {
+            let glyph_len = text.graphemes(true).count() as isize;
+            edit.highlight_vector = if modifiers.shift {
+                edit.cursor_position - glyph_len
+            } else {
+                0
+            };
+            edit.cursor_position = glyph_len;
+            edit.cursor_blink_timer = 0.0;
+            edit.last_edit = None;
+        }
+        VirtualKeyCode::Back => edit_text(edit, text, EditKind::Deleting, |edit, text| {
+            if !delete_highlighted(edit, text) && edit.cursor_position > 0 {
+                let start = if ctrl_or_cmd(&modifiers) {
+                    word_start_before(text, edit.cursor_position)
+                } else {
+                    edit.cursor_position - 1
+                };
+                let range = grapheme_bytes(text, start, edit.cursor_position);
+                text.drain(range);
+                edit.cursor_position = start;
+            }
+        }),
+        VirtualKeyCode::Delete => edit_text(edit, text, EditKind::Deleting, |edit, text| {
+            if !delete_highlighted(edit, text) {
+                let end = if ctrl_or_cmd(&modifiers) {
+                    word_end_after(text, edit.cursor_position)
+                } else {
+                    edit.cursor_position + 1
+                };
+                let range = grapheme_bytes(text, edit.cursor_position, end);
+                text.drain(range);
+            }
+        }),
+        VirtualKeyCode::Left => {
+            if edit.highlight_vector == 0 || modifiers.shift {
+                if edit.cursor_position > 0 {
+                    let delta = if ctrl_or_cmd(&modifiers) {
+                        edit.cursor_position - word_start_before(text, edit.cursor_position)
+                    } else {
+                        1
+                    };
+                    edit.cursor_position -= delta;
+                    if modifiers.shift {
+                        edit.highlight_vector += delta;
+                    }
+                    edit.cursor_blink_timer = 0.0;
+                }
+            } else {
+                edit.cursor_position = edit
+                    .cursor_position
+                    .min(edit.cursor_position + edit.highlight_vector);
+                edit.highlight_vector = 0;
+            }
+            edit.last_edit = None;
+        }
+        VirtualKeyCode::Right => {
+            if edit.highlight_vector == 0 || modifiers.shift {
+                let glyph_len = text.graphemes(true).count();
+                if (edit.cursor_position as usize) < glyph_len {
+                    let delta = if ctrl_or_cmd(&modifiers) {
+                        word_end_after(text, edit.cursor_position) - edit.cursor_position
+                    } else {
+                        1
+                    };
+                    edit.cursor_position += delta;
+                    if modifiers.shift {
+                        edit.highlight_vector -= delta;
+                    }
+                    edit.cursor_blink_timer = 0.0;
+                }
+            } else {
+                edit.cursor_position = edit
+                    .cursor_position
+                    .max(edit.cursor_position + edit.highlight_vector);
+                edit.highlight_vector = 0;
+            }
+            edit.last_edit = None;
+        }
+        VirtualKeyCode::A => {
+            if ctrl_or_cmd(&modifiers) {
+                let glyph_len = text.graphemes(true).count() as isize;
+                edit.cursor_position = glyph_len;
+                edit.highlight_vector = -glyph_len;
+                edit.last_edit = None;
+            }
+        }
+        VirtualKeyCode::X => {
+            if ctrl_or_cmd(&modifiers) {
+                let new_clip = read_highlighted(edit, text).to_owned();
+                if !new_clip.is_empty() {
+                    match clipboard.set_contents(new_clip) {
+                        Ok(()) => edit_text(edit, text, EditKind::Other, |edit, text| {
+                            delete_highlighted(edit, text);
+                        }),
+                        Err(e) => error!("Error occured when cutting to clipboard: {:?}", e),
+                    }
+                }
+            }
+        }
+        VirtualKeyCode::C => {
+            if ctrl_or_cmd(&modifiers) {
+                let new_clip = read_highlighted(edit, text);
+                if !new_clip.is_empty() {
+                    if let Err(e) = clipboard.set_contents(new_clip.to_owned()) {
+                        error!("Error occured when copying to clipboard: {:?}", e);
+                    }
+                }
+            }
+        }
+        VirtualKeyCode::V => {
+            if ctrl_or_cmd(&modifiers) {
+                match clipboard.get_contents() {
+                    Ok(contents) => edit_text(edit, text, EditKind::Other, |edit, text| {
+                        insert_text(edit, text, &contents)
+                    }),
+                    Err(e) => error!("Error occured when pasting contents of clipboard: {:?}", e),
+                }
+            }
+        }
+        VirtualKeyCode::Z => {
+            if ctrl_or_cmd(&modifiers) {
+                if modifiers.shift {
+                    edit.redo(text);
+                } else {
+                    edit.undo(text);
+                }
+            }
+        }
+        VirtualKeyCode::Y => {
+            if ctrl_or_cmd(&modifiers) {
+                edit.redo(text);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Applies an input method event to the text.
+fn handle_ime(edit: &mut TextEditing, text: &mut String, event: &ImeEvent) {
+    match *event {
+        ImeEvent::Preedit {
+            text: ref preedit,
+            cursor,
+        } => {
+            edit.preedit = if preedit.is_empty() {
+                None
+            } else {
+                Some(Preedit {
+                    text: preedit.clone(),
+                    cursor,
+                })
+            };
+            edit.cursor_blink_timer = 0.0;
+        }
+        ImeEvent::Commit(ref committed) => {
+            edit.preedit = None;
+            edit_text(edit, text, EditKind::Other, |edit, text| {
+                insert_text(edit, text, committed)
+            });
+        }
+    }
+}
+
+/// Returns the start of the word before the cursor in graphemes, skipping whitespace.
+fn word_start_before(text: &str, cursor: isize) -> isize {
+    let mut start = 0;
+    let mut graphemes = 0;
+    for word in text.split_word_bounds() {
+        if graphemes >= cursor {
+            break;
+        }
+        if !word.trim().is_empty() {
+            start = graphemes;
+        }
+        graphemes += word.graphemes(true).count() as isize;
     }
+    start
+}
+
+/// Returns the end of the word after the cursor in graphemes, skipping whitespace.
+fn word_end_after(text: &str, cursor: isize) -> isize {
+    let mut graphemes = 0;
+    for word in text.split_word_bounds() {
+        graphemes += word.graphemes(true).count() as isize;
+        if graphemes > cursor && !word.trim().is_empty() {
+            break;
+        }
+    }
+    graphemes
 }
 
 fn should_advance_to_end(
@@ -700,59 +929,175 @@ fn ctrl_or_cmd(modifiers: &ModifiersState) -> bool {
         || (cfg!(not(target_os = "macos")) && modifiers.ctrl)
 }
 
-fn read_highlighted<'a>(edit: &TextEditing, text: &'a UiText) -> &'a str {
+fn read_highlighted<'a>(edit: &TextEditing, text: &'a str) -> &'a str {
     let range = highlighted_bytes(edit, text);
-    &text.text[range]
+    &text[range]
 }
 
-/// Removes the highlighted text and returns it in a String.
-fn extract_highlighted(edit: &mut TextEditing, text: &mut UiText) -> String {
-    let range = highlighted_bytes(edit, text);
-    edit.cursor_position = range.start as isize;
-    edit.highlight_vector = 0;
-    text.text.drain(range).collect::<String>()
-}
-
-/// Removes the highlighted text and returns true if anything was deleted..
-fn delete_highlighted(edit: &mut TextEditing, text: &mut UiText) -> bool {
+/// Removes the highlighted text and returns true if anything was deleted.
+fn delete_highlighted(edit: &mut TextEditing, text: &mut String) -> bool {
     if edit.highlight_vector != 0 {
         let range = highlighted_bytes(edit, text);
-        edit.cursor_position = range.start as isize;
+        edit.cursor_position = edit
+            .cursor_position
+            .min(edit.cursor_position + edit.highlight_vector);
         edit.highlight_vector = 0;
-        text.text.drain(range);
+        text.drain(range);
         return true;
     }
     false
 }
 
 // Gets the byte index of the cursor.
-fn cursor_byte_index(edit: &TextEditing, text: &UiText) -> usize {
-    text.text
-        .grapheme_indices(true)
-        .nth(edit.cursor_position as usize)
-        .map(|i| i.0)
-        .unwrap_or_else(|| text.text.len())
+fn cursor_byte_index(edit: &TextEditing, text: &str) -> usize {
+    grapheme_byte_index(text, edit.cursor_position as usize)
 }
 
 /// Returns the byte indices that are highlighted in the string.
-fn highlighted_bytes(edit: &TextEditing, text: &UiText) -> Range<usize> {
+fn highlighted_bytes(edit: &TextEditing, text: &str) -> Range<usize> {
     let start = edit
         .cursor_position
-        .min(edit.cursor_position + edit.highlight_vector) as usize;
+        .min(edit.cursor_position + edit.highlight_vector);
     let end = edit
         .cursor_position
-        .max(edit.cursor_position + edit.highlight_vector) as usize;
-    let start_byte = text
-        .text
-        .grapheme_indices(true)
-        .nth(start)
-        .map(|i| i.0)
-        .unwrap_or_else(|| text.text.len());
-    let end_byte = text
-        .text
-        .grapheme_indices(true)
-        .nth(end)
+        .max(edit.cursor_position + edit.highlight_vector);
+    grapheme_bytes(text, start, end)
+}
+
+/// Returns the byte indices of the graphemes from `start` to `end`.
+fn grapheme_bytes(text: &str, start: isize, end: isize) -> Range<usize> {
+    grapheme_byte_index(text, start as usize)..grapheme_byte_index(text, end as usize)
+}
+
+/// Returns the byte index of a grapheme, or the length of the text if it is past the end.
+pub(crate) fn grapheme_byte_index(text: &str, grapheme: usize) -> usize {
+    text.grapheme_indices(true)
+        .nth(grapheme)
         .map(|i| i.0)
-        .unwrap_or_else(|| text.text.len());
-    start_byte..end_byte
+        .unwrap_or_else(|| text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editing(max_length: usize) -> TextEditing {
+        TextEditing::new(max_length, [1.0; 4], [0.0; 4], false)
+    }
+
+    fn ctrl() -> ModifiersState {
+        if cfg!(target_os = "macos") {
+            ModifiersState {
+                logo: true,
+                ..Default::default()
+            }
+        } else {
+            ModifiersState {
+                ctrl: true,
+                ..Default::default()
+            }
+        }
+    }
+
+    fn type_str(edit: &mut TextEditing, text: &mut String, input: &str) {
+        for c in input.chars() {
+            let mut buffer = [0; 4];
+            let c = c.encode_utf8(&mut buffer);
+            edit_text(edit, text, EditKind::Typing, |edit, text| {
+                insert_text(edit, text, c)
+            });
+        }
+    }
+
+    #[test]
+    fn undo_groups_typing() {
+        let mut edit = editing(10);
+        let mut text = String::new();
+        type_str(&mut edit, &mut text, "hello world");
+        assert_eq!("hello worl", text);
+
+        let mut clipboard = MemoryClipboard::default();
+        handle_key(
+            &mut edit,
+            &mut text,
+            VirtualKeyCode::Back,
+            ctrl(),
+            &mut clipboard,
+        );
+        assert_eq!("hello ", text);
+
+        assert!(edit.undo(&mut text));
+        assert_eq!("hello worl", text);
+        assert!(edit.undo(&mut text));
+        assert_eq!("", text);
+        assert!(!edit.undo(&mut text));
+        assert!(edit.redo(&mut text));
+        assert_eq!("hello worl", text);
+        assert_eq!(10, edit.cursor_position);
+    }
+
+    #[test]
+    fn word_movement_skips_whitespace() {
+        let text = "one  two three";
+        assert_eq!(5, word_start_before(text, 8));
+        assert_eq!(0, word_start_before(text, 5));
+        assert_eq!(8, word_end_after(text, 3));
+        assert_eq!(14, word_end_after(text, 8));
+    }
+
+    #[test]
+    fn cut_and_paste_with_memory_clipboard() {
+        let mut edit = editing(20);
+        let mut text = "copy me".to_string();
+        let mut clipboard = MemoryClipboard::default();
+        edit.cursor_position = 7;
+        edit.highlight_vector = -2;
+
+        handle_key(
+            &mut edit,
+            &mut text,
+            VirtualKeyCode::X,
+            ctrl(),
+            &mut clipboard,
+        );
+        assert_eq!("copy ", text);
+        assert_eq!("me", clipboard.contents);
+
+        edit.cursor_position = 0;
+        handle_key(
+            &mut edit,
+            &mut text,
+            VirtualKeyCode::V,
+            ctrl(),
+            &mut clipboard,
+        );
+        assert_eq!("mecopy ", text);
+        assert_eq!(2, edit.cursor_position);
+    }
+
+    #[test]
+    fn ime_commit_replaces_preedit() {
+        let mut edit = editing(20);
+        let mut text = "ab".to_string();
+        edit.cursor_position = 1;
+        handle_ime(
+            &mut edit,
+            &mut text,
+            &ImeEvent::Preedit {
+                text: "ni".to_string(),
+                cursor: None,
+            },
+        );
+        assert_eq!(Some("ni"), edit.preedit.as_ref().map(|p| p.text.as_str()));
+        assert_eq!("ab", text);
+
+        handle_ime(
+            &mut edit,
+            &mut text,
+            &ImeEvent::Commit("\u{4F60}".to_string()),
+        );
+        assert_eq!(None, edit.preedit);
+        assert_eq!("a\u{4F60}b", text);
+        assert_eq!(2, edit.cursor_position);
+    }
 }
@@ -2,7 +2,8 @@ use amethyst;
 
 use amethyst::{
     assets::{AssetStorage, Loader},
-    core::{Parent, Transform, TransformBundle},
+    controls::{FollowCamera2D, FollowCamera2DSystem},
+    core::{Transform, TransformBundle},
     ecs::{Component, Entity, Join, NullStorage, Read, ReadStorage, System, WriteStorage},
     input::{InputBundle, InputHandler},
     prelude::*,
@@ -109,7 +110,7 @@ fn init_player(world: &mut World, sprite_sheet: &SpriteSheetHandle) -> Entity {
         .build()
 }
 
-fn init_camera(world: &mut World, parent: Entity) {
+fn init_camera(world: &mut World, player: Entity) {
     let mut transform = Transform::default();
     transform.set_z(1.0);
     world
@@ -117,8 +118,8 @@ fn init_camera(world: &mut World, parent: Entity) {
         .with(Camera::from(Projection::orthographic(
             -250.0, 250.0, -250.0, 250.0,
         )))
-        .with(Parent { entity: parent })
         .with(transform)
+        .with(FollowCamera2D::new(player).with_dead_zone(100.0, 60.0))
         .build();
 }
 
@@ -134,8 +135,8 @@ impl SimpleState for Example {
 
         let _background = init_background_sprite(world, &background_sprite_sheet_handle);
         let _reference = init_reference_sprite(world, &circle_sprite_sheet_handle);
-        let parent = init_player(world, &circle_sprite_sheet_handle);
-        init_camera(world, parent);
+        let player = init_player(world, &circle_sprite_sheet_handle);
+        init_camera(world, player);
     }
 }
 
@@ -158,12 +159,13 @@ fn main() -> amethyst::Result<()> {
     );
 
     let game_data = GameDataBuilder::default()
-        .with_bundle(TransformBundle::new())?
         .with_bundle(
             InputBundle::<String, String>::new()
                 .with_bindings_from_file(format!("{}/input.ron", root))?,
         )?
         .with(MovementSystem, "movement", &[])
+        .with(FollowCamera2DSystem, "follow_camera", &["movement"])
+        .with_bundle(TransformBundle::new().with_dep(&["follow_camera"]))?
         .with_bundle(
             RenderBundle::new(pipe, Some(config))
                 .with_sprite_sheet_processor()